@@ -26,6 +26,9 @@ BLOB_HASH=$($OGIT store test.txt)
 echo "Testing: cat"
 CONTENT=$($OGIT cat "$BLOB_HASH")
 [ "$CONTENT" = "Hello, ogit!" ] || { echo "FAIL: cat content mismatch"; exit 1; }
+CONTENT=$($OGIT cat "${BLOB_HASH:0:8}")
+[ "$CONTENT" = "Hello, ogit!" ] || { echo "FAIL: cat with abbreviated hash"; exit 1; }
+! $OGIT cat "not-a-hash" 2>/dev/null || { echo "FAIL: cat accepted an invalid hash"; exit 1; }
 
 # Test write-tree
 echo "Testing: write-tree"
@@ -34,14 +37,22 @@ echo "fn main() {}" > src/main.rs
 TREE_HASH=$($OGIT write-tree .)
 [ -n "$TREE_HASH" ] || { echo "FAIL: write-tree returned empty hash"; exit 1; }
 
-# Test commit
-echo "Testing: commit"
+# Test config identity
+echo "Testing: config user"
+$OGIT config user.name "Demo User"
+$OGIT config user.email "demo@example.com"
+
+# Test add + commit
+echo "Testing: add + commit"
+$OGIT add .
+grep -q "src/main.rs" .ogit/index || { echo "FAIL: add did not stage src/main.rs"; exit 1; }
 COMMIT_HASH=$($OGIT commit -m "Test commit")
 [ -n "$COMMIT_HASH" ] || { echo "FAIL: commit returned empty hash"; exit 1; }
 
 # Test show
 echo "Testing: show"
 $OGIT show "$COMMIT_HASH" | grep -q "tree:" || { echo "FAIL: show missing tree"; exit 1; }
+$OGIT show "$COMMIT_HASH" | grep -q "author:    Demo User <demo@example.com>" || { echo "FAIL: show missing author identity"; exit 1; }
 
 # Test log
 echo "Testing: log"
@@ -55,9 +66,52 @@ OBJECT_COUNT=$($OGIT ls-objects | wc -l)
 # Test second commit (parent chain)
 echo "Testing: commit with parent"
 echo "new file" > new.txt
+$OGIT add new.txt
 COMMIT2_HASH=$($OGIT commit -m "Second commit")
 $OGIT show "$COMMIT2_HASH" | grep -q "parent:" || { echo "FAIL: second commit missing parent"; exit 1; }
 
+# Test repack (gli oggetti restano leggibili dal pack)
+echo "Testing: repack"
+$OGIT repack | grep -q "Packed" || { echo "FAIL: repack did not pack objects"; exit 1; }
+ls .ogit/objects/pack/*.idx > /dev/null || { echo "FAIL: pack index not created"; exit 1; }
+$OGIT log | grep -q "Second commit" || { echo "FAIL: log after repack"; exit 1; }
+
+# Test fsck (solo le righe dei problemi su stdout; nessun errore atteso)
+echo "Testing: fsck"
+$OGIT fsck 2>/dev/null | grep -qv "^dangling\|^unreachable" && { echo "FAIL: fsck reported errors"; exit 1; }
+$OGIT fsck 2>&1 >/dev/null | grep -q "0 errors" || { echo "FAIL: fsck summary"; exit 1; }
+
+# Test prune/gc (il blob orfano sopravvive al periodo di grazia, non a --expire=now)
+echo "Testing: prune and gc"
+echo "orphan" > orphan.txt
+ORPHAN_HASH=$($OGIT store orphan.txt)
+rm orphan.txt
+$OGIT prune --dry-run 2>/dev/null | grep -q "$ORPHAN_HASH" && { echo "FAIL: prune ignored grace period"; exit 1; }
+$OGIT prune --dry-run --expire=now 2>/dev/null | grep -q "$ORPHAN_HASH" || { echo "FAIL: prune dry run"; exit 1; }
+$OGIT cat "$ORPHAN_HASH" > /dev/null || { echo "FAIL: dry run deleted object"; exit 1; }
+$OGIT gc --expire=now | grep -q "Pruned 1 objects" || { echo "FAIL: gc did not prune"; exit 1; }
+$OGIT cat "$ORPHAN_HASH" > /dev/null 2>&1 && { echo "FAIL: orphan survived gc"; exit 1; }
+$OGIT log | grep -q "Second commit" || { echo "FAIL: log after gc"; exit 1; }
+
+# Test repository discovery (sottodirectory, OGIT_DIR, OGIT_WORK_TREE)
+echo "Testing: run from subdirectory"
+(
+    cd src
+    $OGIT log | grep -q "Second commit" || { echo "FAIL: log from subdirectory"; exit 1; }
+    echo "// sub" > sub.rs
+    $OGIT add sub.rs
+    $OGIT status -s | grep -q "A  src/sub.rs" || { echo "FAIL: add from subdirectory"; exit 1; }
+)
+OUTSIDE_DIR=$(mktemp -d)
+(
+    cd "$OUTSIDE_DIR"
+    ! $OGIT log 2>/dev/null || { echo "FAIL: log outside a repository"; exit 1; }
+    OGIT_DIR="$TEST_DIR/.ogit" $OGIT log | grep -q "Second commit" || { echo "FAIL: OGIT_DIR"; exit 1; }
+    OGIT_DIR="$TEST_DIR/.ogit" OGIT_WORK_TREE="$TEST_DIR" $OGIT status -s | grep -q "A  src/sub.rs" \
+        || { echo "FAIL: OGIT_WORK_TREE"; exit 1; }
+)
+rm -rf "$OUTSIDE_DIR"
+
 # Cleanup
 rm -rf "$TEST_DIR"
 
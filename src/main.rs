@@ -1,19 +1,58 @@
 //!ogit <command> [args...]
 //! Commands:
-//!   init              Inizializza repository
+//!   init [--object-format git] Inizializza repository
 //!   store <file>      Salva file come blob, stampa hash
 //!   cat <hash>        Mostra contenuto oggetto
+//!   add [-f] <path>.. Registra file nell'indice
+//!   rm [--cached] <p> Rimuove file dall'indice (e dal disco)
+//!   reset [<path>...] Riporta l'indice allo stato di HEAD
+//!   status [-s]       Mostra modifiche registrate, non registrate e file non tracciati
+//!   branch [...]      Elenca, crea, elimina o rinomina branch
+//!   config <key> [<v>] Legge o imposta una chiave di .ogit/config
+//!   migrate-objects   Riscrive gli oggetti secondo core.compression
+//!   repack            Riunisce gli oggetti loose in un unico pack, con delta tra blob simili
+//!   prune [--dry-run] Elimina gli oggetti loose non raggiungibili più vecchi di gc.pruneExpire
+//!   gc [--dry-run]    Prune, poi repack dei soli oggetti raggiungibili
+//!   fsck              Verifica hash, formato e connettività degli oggetti
+//!   checkout <target> Porta la working directory su un branch o commit
+//!   switch [-c] <br>  Come checkout, ma solo su branch (-c lo crea)
+//!   merge <branch>    Unisce un branch in HEAD (fast-forward o merge a tre vie)
+//!   merge-base <a> <b> Stampa l'antenato comune (--all, --is-ancestor)
+//!   log [--topo-order] [--name-status] Cronologia dei commit raggiungibili da HEAD
+//!   diff [-U<n>] [-M|-C] [<c1> [<c2>]] Differenze fra indice, commit e working directory
+//!
+//! I comandi cercano `.ogit` nella directory corrente e in quelle superiori;
+//! `OGIT_DIR` e `OGIT_WORK_TREE` indicano esplicitamente repository e working directory.
 
+use std::collections::BTreeSet;
 use std::env;
-use std::fs::read;
-use std::path::Path;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process;
 use std::str::from_utf8;
 
-use ogit::initialize_repository::init_repo;
-use ogit::object::{Commit, OObject, OObjectId, OObjectType, TreeEntry};
-use ogit::store::{read_object, write_object, create_commit};
-use ogit::tree::build_tree_from_dir;
+use ogit::branch::{create_branch, delete_branch, list_branches, rename_branch, validate_branch_name};
+use ogit::checkout::{checkout, resolve_checkout_target, CheckoutTarget};
+use ogit::config::Config;
+use ogit::diff::{
+    detect_renames, diff_files, diff_trees, format_patch, index_files, worktree_files, RenameOptions, DEFAULT_CONTEXT,
+};
+use ogit::fsck::fsck;
+use ogit::gc::{gc, parse_expire, prune, PruneOptions, PruneReport};
+use ogit::graph::{is_ancestor, merge_base, merge_bases, RevWalk, WalkOrder};
+use ogit::index::{add_paths, remove_paths, reset_paths, write_tree_from_index, Index};
+use ogit::initialize_repository::init_repo_at;
+use ogit::identity::{identity, Role};
+use ogit::merge::{clear_merge_state, merge, read_merge_state, MergeOutcome};
+use ogit::object::{Commit, OObjectId, OObjectType, ObjectFormat};
+use ogit::object_store::ObjectStore;
+use ogit::pack::{repack, PackOptions, RepackStats};
+use ogit::refs::{read_ref, resolve_ref, update_ref_checked, HEAD, HEADS_PREFIX};
+use ogit::repository::{Repository, OGIT_DIR_ENV, STORE_DIR};
+use ogit::status::status;
+use ogit::store::migrate_objects;
+use ogit::tree::{build_tree_from_dir, flatten_tree_in};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -30,241 +69,787 @@ fn main() {
     
     let command = args[1].as_str();
     
-    let result = match command {
-        "init" => cmd_init(),
-        "store" => cmd_store(&args[2..]),
-        "cat" => cmd_cat(&args[2..]),
-        "write-tree" => cmd_write_tree(&args[2..]),
-        "commit" => cmd_commit(&args[2..]),
-        "show" => cmd_show(&args[2..]),
-        "ls-objects" => cmd_ls_objects(&args[2..]),
-        "log" => cmd_log(&args[2..]),
+    // Tutti i comandi tranne init lavorano sul repository che contiene
+    // la directory corrente (o su quello indicato da OGIT_DIR)
+    let run: fn(&Repository, &[String]) -> Result<(), String> = match command {
+        "init" => {
+            if let Err(e) = cmd_init(&args[2..]) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        "store" => cmd_store,
+        "cat" => cmd_cat,
+        "write-tree" => cmd_write_tree,
+        "commit" => cmd_commit,
+        "show" => cmd_show,
+        "ls-objects" => cmd_ls_objects,
+        "log" => cmd_log,
+        "add" => cmd_add,
+        "rm" => cmd_rm,
+        "reset" => cmd_reset,
+        "status" => cmd_status,
+        "branch" => cmd_branch,
+        "config" => cmd_config,
+        "migrate-objects" => cmd_migrate_objects,
+        "checkout" => cmd_checkout,
+        "switch" => cmd_switch,
+        "merge" => cmd_merge,
+        "merge-base" => cmd_merge_base,
+        "diff" => cmd_diff,
+        "repack" => cmd_repack,
+        "prune" => cmd_prune,
+        "gc" => cmd_gc,
+        "fsck" => cmd_fsck,
         _ => {
             eprintln!("Unknown command: {}", command);
             process::exit(1);
         }
     };
     
+    let result = Repository::from_env()
+        .map_err(String::from)
+        .and_then(|repo| run(&repo, &args[2..]));
     if let Err(e) = result {
         eprintln!("Error: {}", e);
         process::exit(1);
     }
 }
 
-fn cmd_init() -> Result<(), String> {
-    init_repo().map_err(|e| e.to_string())
+fn cmd_init(args: &[String]) -> Result<(), String> {
+    // ogit init [--object-format <ogit|git>]
+    let format = match args {
+        [] => ObjectFormat::Ogit,
+        [flag, name] if flag == "--object-format" => ObjectFormat::parse(name)?,
+        [flag] if flag.starts_with("--object-format=") => {
+            ObjectFormat::parse(&flag["--object-format=".len()..])?
+        }
+        _ => return Err("Usage: ogit init [--object-format <ogit|git>]".into()),
+    };
+    
+    // Con OGIT_DIR il repository si crea lì invece che in ./.ogit
+    let root = env::var_os(OGIT_DIR_ENV).map_or_else(|| PathBuf::from(STORE_DIR), PathBuf::from);
+    init_repo_at(&root, format).map_err(|e| e.to_string())
 }
 
-fn cmd_store(args: &[String]) -> Result<(), String> {
+fn cmd_store(repo: &Repository, args: &[String]) -> Result<(), String> {
     /* Algoritmo */
     // 1. Verifica che args contenga almeno 1 elemento (il path del file)
-    // 2. Leggi il file come Vec<u8>
-    // 3. Crea OObject::new_blob(...)
-    // 4. Chiama write_object(...)
-    // 5. Stampa l'hash
+    // 2. Apri il file e leggine la dimensione
+    // 3. Copialo a blocchi nello store con write_blob_stream (anche file
+    //    di qualche GB non passano mai tutti in memoria)
+    // 4. Stampa l'hash
     if args.is_empty() {
         return Err("Usage: ogit store <file>".into());
     }
 
-    let file_path = Path::new(&args[0]);
-    let file_content = read(file_path)
+    let file = File::open(Path::new(&args[0]))
         .map_err(|e| format!("Failed to read file: {}", e))?;
+    let size = file.metadata()
+        .map_err(|e| format!("Failed to read file: {}", e))?
+        .len();
 
-    let obj = OObject::new_blob(file_content);
-    
-    let store_path = Path::new(".ogit");
-    let id = write_object(store_path, &obj)?;
+    let objects = repo.objects()?;
+    let id = objects.write_blob_stream(&mut BufReader::new(file), size)?;
 
-    println!("{}", id.as_str());
+    println!("{}", id);
 
     Ok(())
 }
 
-fn cmd_cat(args: &[String]) -> Result<(), String> {
+fn cmd_cat(repo: &Repository, args: &[String]) -> Result<(), String> {
     /* Algoritmo */
     // 1. Verifica args[0] esista (l'hash)
-    // 2. Crea OObjectId dall'hash
-    // 3. Chiama read_object
-    // 4. Stampa il contenuto (obj.data come UTF-8 o hex)
+    // 2. Risolve l'hash, anche abbreviato, in un OObjectId
+    // 3. Apre l'oggetto con read_object_stream
+    // 4. Un blob si copia su stdout così com'è, a blocchi; tree e commit
+    //    si stampano come UTF-8 o hex
      if args.is_empty() {
         return Err("Usage: ogit cat <hash>".into());
     }
     
-    let hash = &args[0];
-    let id = OObjectId(hash.clone());
-    
-    let store_path = Path::new(".ogit");
-    let obj = read_object(store_path, &id)?;
+    let objects = repo.objects()?;
+    let id = objects.resolve_prefix(&args[0])?;
+    let mut reader = objects.read_stream(&id)?;
+
+    if reader.kind == OObjectType::Blob {
+        io::copy(&mut reader, &mut io::stdout().lock()).map_err(|e| format!("Failed to write object: {e}"))?;
+        return Ok(());
+    }
 
     // Prova a stampare come UTF-8, altrimenti mostra hex
-    match from_utf8(&obj.data) {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).map_err(|e| format!("Failed to read object: {e}"))?;
+    match from_utf8(&data) {
         Ok(text) => println!("{}", text),
-        Err(_) => println!("{:?}", obj.data),
+        Err(_) => println!("{:?}", data),
     }
     
     Ok(())
 }
 
+/// Byte iniziali di un blob che `show` esamina per decidere se è binario.
+const BINARY_SNIFF_LEN: u64 = 8000;
+
 /// Differenza da cat: show mostra anche il tipo dell'oggetto e formatta meglio l'output.
-fn cmd_show(args: &[String]) -> Result<(), String> {
+fn cmd_show(repo: &Repository, args: &[String]) -> Result<(), String> {
     if args.is_empty() {
         return Err("Usage: ogit show <hash>".into());
     }
     
-    let id = OObjectId(args[0].clone());
-    let store_path = Path::new(".ogit");
-    let obj = read_object(store_path, &id)?;
+    let objects = repo.objects()?;
+    let id = objects.resolve_prefix(&args[0])?;
+    let mut reader = objects.read_stream(&id)?;
     
-    println!("type: {}", obj.kind.as_str());
-    println!("size: {}", obj.data.len());
+    println!("type: {}", reader.kind.as_str());
+    println!("size: {}", reader.size);
     println!("---");
     
-    match obj.kind {
+    match reader.kind {
         OObjectType::Blob => {
-            match from_utf8(&obj.data) {
-                Ok(text) => println!("{}", text),
-                Err(_) => println!("[binary data, {} bytes]", obj.data.len()),
+            // Binario se l'inizio contiene un NUL o non è UTF-8;
+            // il resto si copia senza caricarlo in memoria
+            let mut head = Vec::new();
+            (&mut reader)
+                .take(BINARY_SNIFF_LEN)
+                .read_to_end(&mut head)
+                .map_err(|e| format!("Failed to read object: {e}"))?;
+            let binary = head.contains(&0) || from_utf8(&head).is_err_and(|e| e.error_len().is_some());
+            if binary {
+                println!("[binary data, {} bytes]", reader.size);
+            } else {
+                let mut out = io::stdout().lock();
+                out.write_all(&head)
+                    .and_then(|()| io::copy(&mut reader, &mut out))
+                    .and_then(|_| writeln!(out))
+                    .map_err(|e| format!("Failed to write object: {e}"))?;
             }
         }
         OObjectType::Tree => {
-            let entries = TreeEntry::deserialize_tree(&obj.data)?;
+            let entries = objects.read_tree(&id)?;
             for entry in entries {
-                println!("{} {} {}", entry.kind.as_str(), entry.hash.as_str(), entry.name);
+                println!("{:>6} {} {} {}", entry.mode.as_str(), entry.kind.as_str(), entry.hash, entry.name);
             }
         }
         OObjectType::Commit => {
-            let commit = Commit::deserialize(&obj.data)?;
-            println!("tree:    {}", commit.tree.as_str());
-            if let Some(parent) = &commit.parent {
-                println!("parent:  {}", parent.as_str());
+            let commit = objects.read_commit(&id)?;
+            println!("tree:      {}", commit.tree);
+            for parent in &commit.parents {
+                println!("parent:    {}", parent);
+            }
+            println!("author:    {}", commit.author.to_ident());
+            println!("committer: {}", commit.committer.to_ident());
+            println!("message:");
+            for line in commit.message.lines() {
+                println!("    {line}");
             }
-            println!("author:  {}", commit.author);
-            println!("message: {}", commit.message);
         }
     }
     
     Ok(())
 }
 
-fn cmd_write_tree(args: &[String]) -> Result<(), String> {
+fn cmd_write_tree(repo: &Repository, args: &[String]) -> Result<(), String> {
     if args.is_empty() {
         return Err("Usage: ogit write-tree <dir>".into());
     }
     
     let dir_path = Path::new(&args[0]);
-    let store_path = Path::new(".ogit");
+    let store_path = repo.store_path();
     
     let id = build_tree_from_dir(store_path, dir_path)?;
-    println!("{}", id.as_str());
+    println!("{}", id);
     
     Ok(())
 }
 
-fn cmd_commit(args: &[String]) -> Result<(), String> {
-    // Parsing: -m "message"
-    if args.len() < 2 || args[0] != "-m" {
-        return Err("Usage: ogit commit -m \"message\"".into());
+fn cmd_commit(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let store_path = repo.store_path();
+    let merging = read_merge_state(store_path)?;
+
+    // Parsing: -m "message" [-m "paragrafo"]...  (paragrafi separati da una riga vuota)
+    // Durante un merge il messaggio è facoltativo: si usa quello preparato da `merge`
+    let usage = || String::from("Usage: ogit commit -m \"message\" [-m \"paragraph\"]...");
+    let mut paragraphs = Vec::new();
+    let mut rest = args;
+    while let [flag, value, tail @ ..] = rest {
+        if flag != "-m" {
+            return Err(usage());
+        }
+        paragraphs.push(value.trim_end().to_string());
+        rest = tail;
+    }
+    if !rest.is_empty() {
+        return Err(usage());
+    }
+    let message = match (&merging, paragraphs.is_empty()) {
+        (_, false) => paragraphs.join("\n\n"),
+        (Some(state), true) => state.message.clone(),
+        (None, true) => return Err(usage()),
+    };
+    if let Some(state) = &merging
+        && !state.conflicts.is_empty()
+    {
+        return Err(format!(
+            "Committing is not possible because you have unmerged files:\n    {}",
+            state.conflicts.join("\n    ")
+        ));
     }
-    let message = &args[1];
     
-    let store_path = Path::new(".ogit");
+    // 1. Costruisci tree dall'indice (ciò che è stato registrato con `add`;
+    //    senza indice, il tree di HEAD come per status e diff). Il lock
+    //    resta fino alla fine: un `add` concorrente aspetta il commit
+    let _index_lock = Index::lock(store_path)?;
+    let index = Index::load_or_head(store_path)?;
+    let tree_id = write_tree_from_index(store_path, &index)?;
     
-    // 1. Costruisci tree dalla directory corrente
-    let tree_id = build_tree_from_dir(store_path, Path::new("."))?;
+    // 2. Risolvi HEAD (segue ref: refs/heads/<branch>) per ottenere il parent
+    //    (più il branch unito, se si sta concludendo un merge)
+    let head = resolve_ref(store_path, HEAD)?;
+    let mut parents: Vec<OObjectId> = head.iter().cloned().collect();
+    if let Some(state) = &merging {
+        parents.push(state.head.clone());
+    }
     
-    // 2. Leggi parent da HEAD (se esiste e contiene hash valido)
-    let head_path = store_path.join("HEAD");
-    let parent = if head_path.exists() {
-        let content = std::fs::read_to_string(&head_path)
-            .map_err(|e| format!("Failed to read HEAD: {}", e))?;
-        let trimmed = content.trim();
-        if trimmed.len() == 64 && trimmed.chars().all(|c| c.is_ascii_hexdigit()) {
-            Some(OObjectId(trimmed.to_string()))
-        } else {
-            None
+    // 3. Crea commit con le identità da config / variabili d'ambiente
+    let commit = Commit {
+        tree: tree_id,
+        parents,
+        author: identity(store_path, Role::Author)?,
+        committer: identity(store_path, Role::Committer)?,
+        message,
+    };
+    let commit_id = repo.objects()?.write_commit(&commit)?;
+    
+    // 4. Aggiorna il branch puntato da HEAD (o HEAD stesso se staccato),
+    //    solo se punta ancora al parent
+    update_ref_checked(store_path, HEAD, &commit_id, head.as_ref())?;
+    if merging.is_some() {
+        clear_merge_state(store_path)?;
+    }
+    
+    println!("{}", commit_id);
+    Ok(())
+}
+
+fn cmd_ls_objects(repo: &Repository, _args: &[String]) -> Result<(), String> {
+    // Loose e nei pack, senza duplicati
+    let objects = repo.objects()?;
+    for id in objects.ids()? {
+        // Leggi tipo oggetto
+        let obj = objects.read(&id)?;
+        println!("{} {}", obj.kind.as_str(), id);
+    }
+    
+    Ok(())
+}
+
+/// Visita tutti i commit raggiungibili da HEAD (anche i rami uniti da un merge),
+/// dal più recente al più vecchio secondo la data del committer.
+fn cmd_log(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let mut order = WalkOrder::Date;
+    let mut name_status = false;
+    let mut renames = RenameOptions::default();
+    for arg in args {
+        match arg.as_str() {
+            "--topo-order" => order = WalkOrder::Topological,
+            "--name-status" => name_status = true,
+            _ if parse_rename_arg(arg, &mut renames)? => {}
+            _ => return Err("Usage: ogit log [--topo-order] [--name-status [-M<n>|-C<n>|--no-renames]]".into()),
+        }
+    }
+    
+    let store_path = repo.store_path();
+    let head = match resolve_ref(store_path, HEAD)? {
+        Some(id) => id,
+        None => return Err("No commits yet".into()),
+    };
+    
+    let objects = repo.objects()?;
+    for entry in RevWalk::new(&objects, &[head], order)? {
+        let (id, commit) = entry?;
+        
+        println!("commit {}", id);
+        if commit.is_merge() {
+            let short: Vec<String> = commit
+                .parents
+                .iter()
+                .map(OObjectId::short)
+                .collect();
+            println!("Merge: {}", short.join(" "));
+        }
+        println!("Author: {} <{}>", commit.author.name, commit.author.email);
+        println!("Date:   {}", commit.author.format_date());
+        println!();
+        for line in commit.message.lines() {
+            println!("    {line}");
+        }
+        println!();
+        
+        // Come Git, i merge non mostrano file cambiati
+        if name_status && !commit.is_merge() {
+            let parent_tree = match commit.first_parent() {
+                Some(parent) => Some(objects.read_commit(parent)?.tree),
+                None => None,
+            };
+            let changes = diff_trees(&objects, parent_tree.as_ref(), Some(&commit.tree))?;
+            let changes = detect_renames(&objects, changes, None, &renames)?;
+            for change in &changes {
+                match &change.old_path {
+                    Some(old_path) => {
+                        println!("{}{:03}\t{}\t{}", change.kind.code(), change.similarity, old_path, change.path)
+                    }
+                    None => println!("{}\t{}", change.kind.code(), change.path),
+                }
+            }
+            if !changes.is_empty() {
+                println!();
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Interpreta `-M[<n>]`, `-C[<n>]` e `--no-renames`; `false` se `arg` è un'altra opzione.
+fn parse_rename_arg(arg: &str, options: &mut RenameOptions) -> Result<bool, String> {
+    let threshold = |n: &str| -> Result<u8, String> {
+        let n = n.trim_end_matches('%');
+        match n.parse() {
+            Ok(value) if value <= 100 => Ok(value),
+            _ => Err(format!("Invalid similarity threshold: {}", n)),
+        }
+    };
+    
+    if arg == "--no-renames" {
+        options.renames = false;
+        options.copies = false;
+    } else if let Some(n) = arg.strip_prefix("-M") {
+        options.renames = true;
+        if !n.is_empty() {
+            options.threshold = threshold(n)?;
+        }
+    } else if let Some(n) = arg.strip_prefix("-C") {
+        options.renames = true;
+        options.copies = true;
+        if !n.is_empty() {
+            options.threshold = threshold(n)?;
         }
     } else {
-        None
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+fn cmd_branch(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let store_path = repo.store_path();
+    
+    match args.first().map(String::as_str) {
+        // ogit branch → elenco
+        None => {
+            for branch in list_branches(store_path)? {
+                let marker = if branch.is_current { '*' } else { ' ' };
+                println!("{} {}", marker, branch.name);
+            }
+        }
+        // ogit branch -d|-D <name>
+        Some(flag @ ("-d" | "-D")) => {
+            let name = args.get(1).ok_or("Usage: ogit branch -d <name>")?;
+            let id = delete_branch(store_path, name, flag == "-D")?;
+            println!("Deleted branch {} (was {})", name, id);
+        }
+        // ogit branch -m <old> <new>
+        Some("-m") => {
+            if args.len() < 3 {
+                return Err("Usage: ogit branch -m <old> <new>".into());
+            }
+            rename_branch(store_path, &args[1], &args[2])?;
+        }
+        // ogit branch <name> [<commit>]
+        Some(name) => {
+            let start = args.get(1).map(|rev| resolve_revision(store_path, rev)).transpose()?;
+            create_branch(store_path, name, start.as_ref())?;
+        }
+    }
+    
+    Ok(())
+}
+
+fn cmd_checkout(repo: &Repository, args: &[String]) -> Result<(), String> {
+    if args.is_empty() {
+        return Err("Usage: ogit checkout <branch|commit>".into());
+    }
+    
+    let store_path = repo.store_path();
+    match checkout(store_path, repo.work_tree(), &args[0])? {
+        CheckoutTarget::Branch(name) => println!("Switched to branch '{}'", name),
+        CheckoutTarget::Detached(id) => println!("HEAD is now at {} (detached)", id),
+    }
+    
+    Ok(())
+}
+
+fn cmd_switch(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let store_path = repo.store_path();
+    
+    // ogit switch -c <name> → crea il branch su HEAD e ci si sposta
+    let name = match args {
+        [flag, name] if flag == "-c" => {
+            create_branch(store_path, name, None)?;
+            name
+        }
+        [name] => name,
+        _ => return Err("Usage: ogit switch [-c] <branch>".into()),
     };
     
-    // 3. Crea commit
-    let author = "Default Author";  // per ora semplificato
-    let commit_id = create_commit(store_path, &tree_id, parent.as_ref(), author, message)?;
+    // Un nome non valido (`../x`) non viene mai cercato sotto refs/heads
+    validate_branch_name(name)?;
+    if read_ref(store_path, &format!("{HEADS_PREFIX}{name}"))?.is_none() {
+        return Err(format!("Branch not found: {}", name));
+    }
     
-    // 4. Aggiorna .ogit/HEAD col nuovo hash
-    std::fs::write(&head_path, commit_id.as_str())
-        .map_err(|e| format!("Failed to write HEAD: {}", e))?;
+    checkout(store_path, repo.work_tree(), name)?;
+    println!("Switched to branch '{}'", name);
     
-    println!("{}", commit_id.as_str());
     Ok(())
 }
 
-fn cmd_ls_objects(_args: &[String]) -> Result<(), String> {
-    let objects_path = Path::new(".ogit/objects");
+fn cmd_merge(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let [target] = args else {
+        return Err("Usage: ogit merge <branch|commit>".into());
+    };
     
-    if !objects_path.exists() {
-        return Err("No .ogit repository found".into());
+    let store_path = repo.store_path();
+    let author = identity(store_path, Role::Author)?;
+    let committer = identity(store_path, Role::Committer)?;
+    
+    match merge(store_path, repo.work_tree(), target, &author, &committer)? {
+        MergeOutcome::UpToDate => println!("Already up to date."),
+        MergeOutcome::FastForward(id) => println!("Fast-forward to {}", id),
+        MergeOutcome::Merged(id) => println!("Merge made: {}", id),
+        MergeOutcome::Conflicts(paths) => {
+            for path in &paths {
+                println!("CONFLICT (content): Merge conflict in {}", path);
+            }
+            return Err("Automatic merge failed; fix conflicts and then commit the result.".into());
+        }
+    }
+    
+    Ok(())
+}
+
+/// `HEAD`, un nome di branch o un hash di commit, anche abbreviato.
+fn resolve_revision(store_path: &Path, rev: &str) -> Result<OObjectId, String> {
+    if rev == HEAD {
+        return resolve_ref(store_path, HEAD)?.ok_or_else(|| "No commits yet".to_string());
     }
+    Ok(resolve_checkout_target(store_path, rev)?.1)
+}
+
+fn cmd_merge_base(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let store_path = repo.store_path();
+    let objects = repo.objects()?;
     
-    let subdirs = std::fs::read_dir(objects_path)
-        .map_err(|e| format!("Failed to read objects: {}", e))?;
+    match args {
+        // ogit merge-base --is-ancestor <a> <b> → solo exit status, come Git
+        [flag, a, b] if flag == "--is-ancestor" => {
+            let a = resolve_revision(store_path, a)?;
+            let b = resolve_revision(store_path, b)?;
+            if !is_ancestor(&objects, &a, &b)? {
+                process::exit(1);
+            }
+        }
+        [flag, a, b] if flag == "--all" => {
+            let a = resolve_revision(store_path, a)?;
+            let b = resolve_revision(store_path, b)?;
+            for id in merge_bases(&objects, &a, &b)? {
+                println!("{}", id);
+            }
+        }
+        [a, b] => {
+            let a = resolve_revision(store_path, a)?;
+            let b = resolve_revision(store_path, b)?;
+            match merge_base(&objects, &a, &b)? {
+                Some(id) => println!("{}", id),
+                None => process::exit(1),
+            }
+        }
+        _ => return Err("Usage: ogit merge-base [--all | --is-ancestor] <commit> <commit>".into()),
+    }
     
-    for subdir in subdirs {
-        let subdir = subdir.map_err(|e| e.to_string())?;
-        let subdir_name = subdir.file_name().to_string_lossy().to_string();
-        
-        if !subdir.path().is_dir() {
+    Ok(())
+}
+
+/// ogit diff [-U<n>] [<commit> [<commit>]]
+///
+/// - nessun commit: indice → working directory
+/// - un commit: commit → working directory
+/// - due commit: primo → secondo
+fn cmd_diff(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let store_path = repo.store_path();
+    let work_dir = repo.work_tree();
+    let objects = repo.objects()?;
+    
+    let mut context = DEFAULT_CONTEXT;
+    let mut renames = RenameOptions::default();
+    let mut revisions = Vec::new();
+    for arg in args {
+        if let Some(n) = arg.strip_prefix("--unified=").or_else(|| arg.strip_prefix("-U")) {
+            context = n.parse().map_err(|_| format!("Invalid context lines: {}", n))?;
+        } else if parse_rename_arg(arg, &mut renames)? {
             continue;
+        } else {
+            revisions.push(resolve_revision(store_path, arg)?);
         }
-        
-        let files = std::fs::read_dir(subdir.path())
-            .map_err(|e| e.to_string())?;
-        
-        for file in files {
-            let file = file.map_err(|e| e.to_string())?;
-            let filename = file.file_name().to_string_lossy().to_string();
-            let hash = format!("{}{}", subdir_name, filename);
-            
-            // Leggi tipo oggetto
-            let id = OObjectId(hash.clone());
-            let obj = read_object(Path::new(".ogit"), &id)?;
-            
-            println!("{} {}", obj.kind.as_str(), hash);
+    }
+    
+    let (changes, source) = match revisions.as_slice() {
+        [] => {
+            let old = index_files(store_path)?;
+            let new = worktree_files(store_path, work_dir, old.keys())?;
+            (diff_files(&old, &new), Some(work_dir))
+        }
+        [commit] => {
+            let old = flatten_tree_in(&objects, &objects.read_commit(commit)?.tree)?;
+            let tracked: BTreeSet<String> = old.keys().chain(index_files(store_path)?.keys()).cloned().collect();
+            let new = worktree_files(store_path, work_dir, &tracked)?;
+            (diff_files(&old, &new), Some(work_dir))
         }
+        [from, to] => {
+            let old = objects.read_commit(from)?.tree;
+            let new = objects.read_commit(to)?.tree;
+            (diff_trees(&objects, Some(&old), Some(&new))?, None)
+        }
+        _ => return Err("Usage: ogit diff [-U<n>] [-M<n>|-C<n>|--no-renames] [<commit> [<commit>]]".into()),
+    };
+    
+    for change in &detect_renames(&objects, changes, source, &renames)? {
+        print!("{}", format_patch(&objects, change, source, context)?);
     }
     
     Ok(())
 }
 
-fn cmd_log(_args: &[String]) -> Result<(), String> {
-    let store_path = Path::new(".ogit");
-    let head_path = store_path.join("HEAD");
+/// I path sulla riga di comando sono relativi alla directory corrente;
+/// l'indice li vuole relativi alla radice della working directory.
+fn worktree_paths(repo: &Repository, paths: &[String]) -> Result<Vec<String>, String> {
+    let cwd = env::current_dir().map_err(|e| format!("Failed to read current directory: {e}"))?;
+    paths.iter().map(|path| Ok(repo.worktree_path(&cwd, path)?)).collect()
+}
+
+fn cmd_add(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let force = args.first().is_some_and(|a| a == "-f" || a == "--force");
+    let paths = if force { &args[1..] } else { args };
     
-    if !head_path.exists() {
-        return Err("No commits yet".into());
+    if paths.is_empty() {
+        return Err("Usage: ogit add [-f] <path>...".into());
     }
     
-    let mut current_hash = std::fs::read_to_string(&head_path)
-        .map_err(|e| format!("Failed to read HEAD: {}", e))?
-        .trim()
-        .to_string();
+    add_paths(repo.store_path(), repo.work_tree(), &worktree_paths(repo, paths)?, force)?;
+    Ok(())
+}
+
+fn cmd_rm(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let cached = args.first().is_some_and(|a| a == "--cached");
+    let paths = if cached { &args[1..] } else { args };
     
-    while !current_hash.is_empty() {
-        let id = OObjectId(current_hash.clone());
-        let obj = read_object(store_path, &id)?;
-        let commit = Commit::deserialize(&obj.data)?;
-        
-        println!("commit {}", current_hash);
-        println!("Author: {}", commit.author);
-        println!();
-        println!("    {}", commit.message);
-        println!();
-        
-        current_hash = match commit.parent {
-            Some(parent) => parent.0,
-            None => break,
-        };
+    if paths.is_empty() {
+        return Err("Usage: ogit rm [--cached] <path>...".into());
+    }
+    
+    for path in remove_paths(repo.store_path(), repo.work_tree(), &worktree_paths(repo, paths)?, cached)? {
+        println!("rm '{}'", path);
+    }
+    Ok(())
+}
+
+fn cmd_reset(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let changed = reset_paths(repo.store_path(), &worktree_paths(repo, args)?)?;
+    
+    if !changed.is_empty() {
+        println!("Unstaged changes after reset:");
+        for path in changed {
+            println!("    {}", path);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_status(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let short = args.first().is_some_and(|a| a == "-s" || a == "--short");
+    let st = status(repo.store_path(), repo.work_tree())?;
+    
+    // Formato compatto: <staged><unstaged> <path>
+    if short {
+        for change in &st.staged {
+            println!("{}  {}", change.kind.code(), change.display_path());
+        }
+        for change in &st.unstaged {
+            println!(" {} {}", change.kind.code(), change.display_path());
+        }
+        for path in &st.untracked {
+            println!("?? {}", path);
+        }
+        for path in &st.conflicts {
+            println!("UU {}", path);
+        }
+        return Ok(());
+    }
+    
+    match &st.branch {
+        Some(name) => println!("On branch {}", name),
+        None => println!("HEAD detached"),
+    }
+    
+    if !st.conflicts.is_empty() {
+        println!("\nUnmerged paths (fix conflicts and run \"ogit add\"):");
+        for path in &st.conflicts {
+            println!("    both modified: {}", path);
+        }
+    }
+    if !st.staged.is_empty() {
+        println!("\nChanges to be committed:");
+        for change in &st.staged {
+            println!("    {:<12}{}", format!("{}:", change.kind.as_str()), change.display_path());
+        }
+    }
+    if !st.unstaged.is_empty() {
+        println!("\nChanges not staged for commit:");
+        for change in &st.unstaged {
+            println!("    {:<12}{}", format!("{}:", change.kind.as_str()), change.display_path());
+        }
     }
+    if !st.untracked.is_empty() {
+        println!("\nUntracked files:");
+        for path in &st.untracked {
+            println!("    {}", path);
+        }
+    }
+    if st.is_clean() {
+        println!("nothing to commit, working tree clean");
+    }
+    
+    Ok(())
+}
+
+fn cmd_config(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let store_path = repo.store_path();
+    let mut config = Config::load(store_path)?;
     
+    match args {
+        [key] => match config.get(key) {
+            Some(value) => println!("{}", value),
+            None => return Err(format!("Key not set: {}", key)),
+        },
+        [key, value] => {
+            config.set(key, value)?;
+            config.save(store_path)?;
+        }
+        _ => return Err("Usage: ogit config <key> [<value>]".into()),
+    }
+    
+    Ok(())
+}
+
+fn cmd_migrate_objects(repo: &Repository, _args: &[String]) -> Result<(), String> {
+    let count = migrate_objects(repo.store_path())?;
+    println!("Rewrote {} objects", count);
+    Ok(())
+}
+
+/// `ogit repack [--window=<n>] [--depth=<n>]`: riunisce tutti gli oggetti in
+/// un pack. I flag prevalgono su `pack.window`/`pack.depth`.
+fn cmd_repack(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let store_path = repo.store_path();
+    let mut options = PackOptions::load(store_path)?;
+    for arg in args {
+        let (target, value) = if let Some(n) = arg.strip_prefix("--window=") {
+            (&mut options.window, n)
+        } else if let Some(n) = arg.strip_prefix("--depth=") {
+            (&mut options.depth, n)
+        } else {
+            return Err("Usage: ogit repack [--window=<n>] [--depth=<n>]".into());
+        };
+        *target = value.parse().map_err(|_| format!("Invalid number: {value}"))?;
+    }
+
+    print_repack_stats(&repack(store_path, &options)?);
+    Ok(())
+}
+
+fn print_repack_stats(stats: &RepackStats) {
+    println!(
+        "Packed {} objects ({} as deltas, {} loose removed, {} old packs replaced)",
+        stats.objects, stats.deltas, stats.loose_removed, stats.packs_removed
+    );
+}
+
+/// `ogit prune [--dry-run] [--expire=<tempo>]`: elimina gli oggetti loose non
+/// raggiungibili più vecchi del periodo di grazia (`gc.pruneExpire`, formato
+/// in `ogit::gc::parse_expire`). Con `--dry-run` (`-n`) ne stampa gli hash.
+fn cmd_prune(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let options = prune_options(repo, args, "Usage: ogit prune [--dry-run] [--expire=<time>]")?;
+    let report = prune(repo.store_path(), &options)?;
+    print_prune_report(&report, &options);
     Ok(())
-}
\ No newline at end of file
+}
+
+/// `ogit gc [--dry-run] [--expire=<tempo>]`: come `prune`, ma elimina anche gli
+/// oggetti non raggiungibili nei pack, poi impacchetta quelli raggiungibili.
+fn cmd_gc(repo: &Repository, args: &[String]) -> Result<(), String> {
+    let store_path = repo.store_path();
+    let options = prune_options(repo, args, "Usage: ogit gc [--dry-run] [--expire=<time>]")?;
+    let (report, stats) = gc(store_path, &options, &PackOptions::load(store_path)?)?;
+    print_prune_report(&report, &options);
+    if let Some(stats) = stats {
+        print_repack_stats(&stats);
+    }
+    Ok(())
+}
+
+fn prune_options(repo: &Repository, args: &[String], usage: &str) -> Result<PruneOptions, String> {
+    let mut options = PruneOptions::load(repo.store_path())?;
+    for arg in args {
+        if arg == "-n" || arg == "--dry-run" {
+            options.dry_run = true;
+        } else if let Some(value) = arg.strip_prefix("--expire=") {
+            options.expire = parse_expire(value)?;
+        } else {
+            return Err(usage.into());
+        }
+    }
+    Ok(options)
+}
+
+/// Con `--dry-run` un hash per riga su stdout e il riepilogo su stderr, come `fsck`.
+fn print_prune_report(report: &PruneReport, options: &PruneOptions) {
+    if options.dry_run {
+        for id in &report.pruned {
+            println!("{}", id);
+        }
+        eprintln!("Would prune {} objects ({} recent unreachable kept)", report.pruned.len(), report.kept);
+    } else {
+        println!("Pruned {} objects ({} recent unreachable kept)", report.pruned.len(), report.kept);
+    }
+}
+
+/// Una riga per problema su stdout (formato in `ogit::fsck`), il riepilogo su
+/// stderr; exit status 1 se c'è almeno un errore (gli oggetti dangling non contano).
+fn cmd_fsck(repo: &Repository, args: &[String]) -> Result<(), String> {
+    if !args.is_empty() {
+        return Err("Usage: ogit fsck".into());
+    }
+
+    let report = fsck(repo.store_path())?;
+    for issue in &report.issues {
+        println!("{}", issue);
+    }
+
+    let errors = report.issues.iter().filter(|issue| issue.is_error()).count();
+    eprintln!("Checked {} objects, {} errors", report.objects, errors);
+    if !report.is_ok() {
+        process::exit(1);
+    }
+    Ok(())
+}
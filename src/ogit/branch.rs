@@ -0,0 +1,172 @@
+//! Gestione dei branch: file sotto `.ogit/refs/heads/<nome>`.
+//!
+//! ```text
+//! ogit branch                  → elenca (con * sul corrente)
+//! ogit branch <nome> [<hash>]  → crea su HEAD o sul commit indicato
+//! ogit branch -d <nome>        → elimina se già integrato in HEAD
+//! ogit branch -m <old> <new>   → rinomina
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::OgitError;
+use crate::graph::is_ancestor;
+use crate::object::OObjectId;
+use crate::object_store::LooseStore;
+use crate::refs::{
+    collect_ref_names, current_branch, delete_ref, read_ref, resolve_ref, write_ref, RefValue, HEAD, HEADS_PREFIX,
+};
+use crate::store::read_commit;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchInfo {
+    pub name: String,
+    pub id: OObjectId,
+    pub is_current: bool,
+}
+
+/// Regole (semplificate) di `git check-ref-format` per un nome di branch.
+pub fn validate_branch_name(name: &str) -> Result<(), OgitError> {
+    let invalid = name.is_empty()
+        || name == HEAD
+        || name.starts_with('-')
+        || name.starts_with('/')
+        || name.ends_with('/')
+        || name.ends_with('.')
+        || name.ends_with(".lock")
+        || name.contains("..")
+        || name.contains("//")
+        || name.contains("@{")
+        || name.split('/').any(|part| part.starts_with('.'))
+        || name
+            .chars()
+            .any(|c| c.is_ascii_control() || " ~^:?*[\\".contains(c));
+
+    if invalid {
+        return Err(OgitError::InvalidRef(format!("Invalid branch name: {name}")));
+    }
+    Ok(())
+}
+
+fn branch_ref(name: &str) -> String {
+    format!("{HEADS_PREFIX}{name}")
+}
+
+/// Elenca tutti i branch in ordine alfabetico, marcando quello corrente.
+pub fn list_branches(store_path: &Path) -> Result<Vec<BranchInfo>, OgitError> {
+    let heads_dir = store_path.join(HEADS_PREFIX);
+    let current = current_branch(store_path)?;
+
+    let mut names = Vec::new();
+    if heads_dir.exists() {
+        collect_ref_names(&heads_dir, "", &mut names)?;
+    }
+    names.sort();
+
+    let mut branches = Vec::new();
+    for name in names {
+        if let Some(RefValue::Direct(id)) = read_ref(store_path, &branch_ref(&name))? {
+            let is_current = current.as_deref() == Some(name.as_str());
+            branches.push(BranchInfo { name, id, is_current });
+        }
+    }
+    Ok(branches)
+}
+
+/// Crea un nuovo branch che punta a `start` (o a HEAD se `None`).
+pub fn create_branch(
+    store_path: &Path,
+    name: &str,
+    start: Option<&OObjectId>,
+) -> Result<OObjectId, OgitError> {
+    validate_branch_name(name)?;
+
+    let full = branch_ref(name);
+    if read_ref(store_path, &full)?.is_some() {
+        return Err(OgitError::Rejected(format!("Branch already exists: {name}")));
+    }
+
+    let id = match start {
+        Some(id) => {
+            // Il punto di partenza deve essere un commit esistente
+            read_commit(store_path, id)?;
+            id.clone()
+        }
+        None => resolve_ref(store_path, HEAD)?
+            .ok_or_else(|| OgitError::InvalidRef("Cannot create a branch: HEAD has no commits yet".into()))?,
+    };
+
+    write_ref(store_path, &full, &RefValue::Direct(id.clone()))?;
+    Ok(id)
+}
+
+/// Elimina un branch. Senza `force` rifiuta se il branch contiene commit
+/// non raggiungibili da HEAD (cioè non ancora integrati).
+pub fn delete_branch(store_path: &Path, name: &str, force: bool) -> Result<OObjectId, OgitError> {
+    // Il nome diventa un path sotto `.ogit`: `../x` uscirebbe da refs/heads
+    validate_branch_name(name)?;
+
+    let full = branch_ref(name);
+    let id = match read_ref(store_path, &full)? {
+        Some(RefValue::Direct(id)) => id,
+        _ => return Err(OgitError::InvalidRef(format!("Branch not found: {name}"))),
+    };
+
+    if current_branch(store_path)?.as_deref() == Some(name) {
+        return Err(OgitError::Rejected(format!("Cannot delete the current branch: {name}")));
+    }
+
+    if !force {
+        let merged = match resolve_ref(store_path, HEAD)? {
+            Some(head) => is_ancestor(&LooseStore::open(store_path)?, &id, &head)?,
+            None => false,
+        };
+        if !merged {
+            return Err(OgitError::Rejected(format!("Branch {name} is not fully merged (use -D to force)")));
+        }
+    }
+
+    delete_ref(store_path, &full)?;
+    remove_empty_parents(store_path, &full);
+    Ok(id)
+}
+
+/// Rinomina un branch; se è quello corrente HEAD viene ripuntato.
+pub fn rename_branch(store_path: &Path, old: &str, new: &str) -> Result<(), OgitError> {
+    validate_branch_name(old)?;
+    validate_branch_name(new)?;
+
+    let old_full = branch_ref(old);
+    let new_full = branch_ref(new);
+
+    let id = match read_ref(store_path, &old_full)? {
+        Some(RefValue::Direct(id)) => id,
+        _ => return Err(OgitError::InvalidRef(format!("Branch not found: {old}"))),
+    };
+    if read_ref(store_path, &new_full)?.is_some() {
+        return Err(OgitError::Rejected(format!("Branch already exists: {new}")));
+    }
+
+    write_ref(store_path, &new_full, &RefValue::Direct(id))?;
+    delete_ref(store_path, &old_full)?;
+    remove_empty_parents(store_path, &old_full);
+
+    if current_branch(store_path)?.as_deref() == Some(old) {
+        write_ref(store_path, HEAD, &RefValue::Symbolic(new_full))?;
+    }
+    Ok(())
+}
+
+/// Dopo la rimozione di `refs/heads/a/b` elimina `refs/heads/a` se vuota.
+fn remove_empty_parents(store_path: &Path, full: &str) {
+    let heads_dir = store_path.join(HEADS_PREFIX);
+    let mut dir = store_path.join(full).parent().map(Path::to_path_buf);
+
+    while let Some(d) = dir {
+        if d == heads_dir || fs::remove_dir(&d).is_err() {
+            break;
+        }
+        dir = d.parent().map(Path::to_path_buf);
+    }
+}
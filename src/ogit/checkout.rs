@@ -0,0 +1,199 @@
+//! Checkout: l'inverso di `build_tree_from_dir`.
+//!
+//! Materializza il tree di un commit nella working directory e ripunta HEAD.
+//!
+//! ```text
+//! per ogni path in HEAD ∪ target:
+//!     se HEAD e target coincidono → non toccare (le modifiche locali restano)
+//!     se l'indice o il file locale differiscono sia da HEAD sia dal target → conflitto, abort
+//!     se il path non è nel target → rimuovi file
+//!     altrimenti → scrivi il blob del target (con il suo modo)
+//! ```
+//!
+//! Un cambio del solo modo (es. `chmod +x`) conta come modifica del path.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use crate::branch::validate_branch_name;
+use crate::error::OgitError;
+use crate::index::{stage_file, Index};
+use crate::object::{FileMode, OObjectId, OObjectType, ObjectFormat};
+use crate::object_store::{LooseStore, ObjectStore};
+use crate::refs::{read_ref, resolve_ref, write_ref, RefValue, HEAD, HEADS_PREFIX};
+use crate::tree::{flatten_tree_in, hash_worktree_file, write_worktree_stream};
+
+/// Dove punterà HEAD dopo il checkout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckoutTarget {
+    /// HEAD → `ref: refs/heads/<nome>`
+    Branch(String),
+    /// HEAD contiene direttamente l'hash del commit
+    Detached(OObjectId),
+}
+
+/// Interpreta `target` come nome di branch oppure, in alternativa, come hash
+/// di commit (anche abbreviato, purché univoco).
+pub fn resolve_checkout_target(
+    store_path: &Path,
+    target: &str,
+) -> Result<(CheckoutTarget, OObjectId), OgitError> {
+    // Un nome non valido (`../x`) non viene mai cercato sotto refs/heads
+    let branch_ref = format!("{HEADS_PREFIX}{target}");
+    if validate_branch_name(target).is_ok()
+        && let Some(RefValue::Direct(id)) = read_ref(store_path, &branch_ref)?
+    {
+        return Ok((CheckoutTarget::Branch(target.to_string()), id));
+    }
+
+    let not_found = || OgitError::InvalidRef(format!("Not a branch or commit: {target}"));
+    let objects = LooseStore::open(store_path)?;
+    let id = match objects.resolve_prefix(target) {
+        Ok(id) => id,
+        Err(err @ OgitError::AmbiguousId { .. }) => return Err(err),
+        Err(_) => return Err(not_found()),
+    };
+    objects.read_commit(&id).map_err(|_| not_found())?;
+    Ok((CheckoutTarget::Detached(id.clone()), id))
+}
+
+/// Esegue il checkout di `target` (branch o commit) in `work_dir`.
+pub fn checkout(store_path: &Path, work_dir: &Path, target: &str) -> Result<CheckoutTarget, OgitError> {
+    let (checkout_target, commit_id) = resolve_checkout_target(store_path, target)?;
+
+    let objects = LooseStore::open(store_path)?;
+    let head_tree = match resolve_ref(store_path, HEAD)? {
+        Some(head) => Some(objects.read_commit(&head)?.tree),
+        None => None,
+    };
+    let target_tree = objects.read_commit(&commit_id)?.tree;
+    update_worktree(store_path, work_dir, head_tree.as_ref(), &target_tree, "checkout")?;
+
+    // Ripunta HEAD
+    let head_value = match &checkout_target {
+        CheckoutTarget::Branch(name) => RefValue::Symbolic(format!("{HEADS_PREFIX}{name}")),
+        CheckoutTarget::Detached(id) => RefValue::Direct(id.clone()),
+    };
+    write_ref(store_path, HEAD, &head_value)?;
+
+    Ok(checkout_target)
+}
+
+/// Porta working directory e indice dal tree `from` (`None` = nessun commit)
+/// al tree `to`, toccando solo i path che cambiano. `operation` compare
+/// nel messaggio di errore se una modifica locale andrebbe persa.
+pub fn update_worktree(
+    store_path: &Path,
+    work_dir: &Path,
+    from: Option<&OObjectId>,
+    to: &OObjectId,
+    operation: &str,
+) -> Result<(), OgitError> {
+    // 1. Mappe path → (blob, modo) per i due tree
+    let objects = LooseStore::open(store_path)?;
+    let head_files = match from {
+        Some(tree) => flatten_tree_in(&objects, tree)?,
+        None => BTreeMap::new(),
+    };
+    let target_files = flatten_tree_in(&objects, to)?;
+
+    // 2. Path che il checkout deve cambiare
+    let all_paths: BTreeSet<&String> = head_files.keys().chain(target_files.keys()).collect();
+    let changed: Vec<&String> = all_paths
+        .into_iter()
+        .filter(|p| head_files.get(*p) != target_files.get(*p))
+        .collect();
+
+    // L'indice segue il checkout; un repository senza indice parte da HEAD
+    let lock = Index::lock(store_path)?;
+    let mut index = Index::load_or_head(store_path)?;
+
+    // 3. Rifiuta se una modifica locale, nell'indice o su disco, verrebbe
+    //    sovrascritta: entrambi devono coincidere con HEAD o con il target.
+    //    Si ri-hashano solo i path cambiati, e solo se size e mtime non
+    //    coincidono con l'indice
+    let mut conflicts = Vec::new();
+    for path in &changed {
+        let head = head_files.get(*path);
+        let target = target_files.get(*path);
+
+        let staged = index.get(path).map(|e| (e.hash.clone(), e.mode));
+        let work = worktree_hash(&index, work_dir, path, objects.format())?;
+        let is_known = |hash: Option<&OObjectId>| {
+            hash == head.map(|(h, _)| h) || hash == target.map(|(h, _)| h)
+        };
+        if (staged.as_ref() != head && staged.as_ref() != target) || !is_known(work.as_ref()) {
+            conflicts.push(path.as_str());
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(OgitError::Rejected(format!(
+            "Your local changes would be overwritten by {operation}:\n    {}",
+            conflicts.join("\n    ")
+        )));
+    }
+
+    // 4. Prima le rimozioni (un file può diventare directory nel target)
+    for path in changed.iter().filter(|p| !target_files.contains_key(**p)) {
+        let file_path = work_dir.join(path);
+        if fs::symlink_metadata(&file_path).is_ok() {
+            fs::remove_file(&file_path)
+                .map_err(OgitError::io(format!("Failed to remove {path}")))?;
+        }
+        remove_empty_dirs(work_dir, &file_path);
+        index.remove(path);
+    }
+
+    // 5. Poi le scritture
+    for path in changed {
+        if let Some((id, mode)) = target_files.get(path) {
+            write_blob(&objects, &work_dir.join(path), id, *mode)?;
+            index.insert(stage_file(&objects, work_dir, path)?);
+        }
+    }
+    index.save_locked(lock)
+}
+
+/// Hash del file `path` su disco (`None` se manca): quello dell'indice se
+/// size e mtime coincidono, altrimenti ricalcolato.
+fn worktree_hash(
+    index: &Index,
+    work_dir: &Path,
+    path: &str,
+    format: ObjectFormat,
+) -> Result<Option<OObjectId>, OgitError> {
+    let file_path = work_dir.join(path);
+    let Ok(metadata) = fs::symlink_metadata(&file_path) else {
+        return Ok(None);
+    };
+    if metadata.is_dir() {
+        return Ok(None);
+    }
+    match index.get(path) {
+        Some(entry) if entry.matches_stat(&metadata) => Ok(Some(entry.hash.clone())),
+        _ => Ok(Some(hash_worktree_file(&file_path, format)?.1)),
+    }
+}
+
+/// Il blob viene copiato a blocchi: un file grande non passa tutto in memoria.
+fn write_blob(objects: &LooseStore, file_path: &Path, id: &OObjectId, mode: FileMode) -> Result<(), OgitError> {
+    let mut reader = objects.read_stream(id)?;
+    if reader.kind != OObjectType::Blob {
+        return Err(OgitError::WrongType { id: id.clone(), expected: OObjectType::Blob });
+    }
+    write_worktree_stream(file_path, mode, &mut reader)
+}
+
+/// Rimuove le directory rimaste vuote risalendo fino a `work_dir` (esclusa).
+fn remove_empty_dirs(work_dir: &Path, file_path: &Path) {
+    let mut dir = file_path.parent();
+
+    while let Some(d) = dir {
+        if d == work_dir || fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+}
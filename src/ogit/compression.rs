@@ -0,0 +1,57 @@
+//! Compressione zlib (deflate) degli oggetti su disco, come fa Git.
+//!
+//! Un oggetto non compresso inizia con il nome del tipo (`blob `, `tree `,
+//! `commit `); uno stream zlib inizia invece con il byte `0x78`.
+//! Questo permette di leggere in modo trasparente entrambi i formati.
+
+use std::io::{Read, Write};
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::error::OgitError;
+
+/// Primo byte di uno stream zlib con finestra da 32K (CMF = 0x78).
+const ZLIB_CMF: u8 = 0x78;
+
+#[must_use]
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.first() == Some(&ZLIB_CMF)
+}
+
+/// Compressore che scrive in `out` man mano, per gli oggetti scritti a blocchi.
+pub fn encoder<W: Write>(out: W) -> ZlibEncoder<W> {
+    ZlibEncoder::new(out, Compression::default())
+}
+
+/// Decompressore che legge da `input` man mano, per gli oggetti letti a blocchi.
+pub fn decoder<R: Read>(input: R) -> ZlibDecoder<R> {
+    ZlibDecoder::new(input)
+}
+
+pub fn compress(bytes: &[u8]) -> Result<Vec<u8>, OgitError> {
+    let mut encoder = encoder(Vec::new());
+    encoder
+        .write_all(bytes)
+        .map_err(OgitError::io("Failed to compress"))?;
+    encoder.finish().map_err(OgitError::io("Failed to compress"))
+}
+
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, OgitError> {
+    let mut decoder = decoder(bytes);
+    let mut out = Vec::new();
+    decoder
+        .read_to_end(&mut out)
+        .map_err(OgitError::corrupt_io("Failed to decompress"))?;
+    Ok(out)
+}
+
+/// Restituisce i byte serializzati di un oggetto, decomprimendo se necessario.
+pub fn decode_loose(bytes: &[u8]) -> Result<Vec<u8>, OgitError> {
+    if is_compressed(bytes) {
+        decompress(bytes)
+    } else {
+        Ok(bytes.to_vec())
+    }
+}
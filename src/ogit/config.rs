@@ -0,0 +1,124 @@
+//! Configurazione del repository: `.ogit/config`, formato INI come Git.
+//!
+//! ```text
+//! [core]
+//!     compression = true
+//! [user]
+//!     name = Mario Rossi
+//! ```
+//!
+//! Le chiavi si indirizzano come `<sezione>.<nome>` (es. `core.compression`).
+
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::path::Path;
+
+use crate::error::OgitError;
+use crate::lockfile::write_locked;
+
+const CONFIG_FILE: &str = "config";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    pub sections: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+fn split_key(key: &str) -> Result<(String, String), OgitError> {
+    match key.rsplit_once('.') {
+        Some((section, name)) if !section.is_empty() && !name.is_empty() => {
+            Ok((section.to_lowercase(), name.to_lowercase()))
+        }
+        _ => Err(OgitError::InvalidInput(format!("Invalid config key: {key}"))),
+    }
+}
+
+impl Config {
+    /// Carica `.ogit/config`; un file mancante equivale a una configurazione vuota.
+    pub fn load(store_path: &Path) -> Result<Self, OgitError> {
+        let path = store_path.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = read_to_string(&path)
+            .map_err(OgitError::io("Failed to read config"))?;
+        Self::parse(&content)
+    }
+
+    pub fn save(&self, store_path: &Path) -> Result<(), OgitError> {
+        write_locked(&store_path.join(CONFIG_FILE), self.serialize())
+    }
+
+    pub fn parse(content: &str) -> Result<Self, OgitError> {
+        let mut config = Self::default();
+        let mut section: Option<String> = None;
+
+        for raw in content.lines() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let name = name.trim().to_lowercase();
+                config.sections.entry(name.clone()).or_default();
+                section = Some(name);
+                continue;
+            }
+
+            let current = section
+                .as_ref()
+                .ok_or_else(|| OgitError::InvalidInput(format!("Config entry outside of a section: {line}")))?;
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| OgitError::InvalidInput(format!("Invalid config line: {line}")))?;
+
+            config
+                .sections
+                .entry(current.clone())
+                .or_default()
+                .insert(key.trim().to_lowercase(), value.trim().to_string());
+        }
+
+        Ok(config)
+    }
+
+    #[must_use]
+    pub fn serialize(&self) -> String {
+        let mut out = String::new();
+        for (section, entries) in &self.sections {
+            out.push_str(&format!("[{section}]\n"));
+            for (key, value) in entries {
+                out.push_str(&format!("\t{key} = {value}\n"));
+            }
+        }
+        out
+    }
+
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let (section, name) = split_key(key).ok()?;
+        self.sections.get(&section)?.get(&name).map(String::as_str)
+    }
+
+    /// Valori booleani accettati: `true/false`, `yes/no`, `on/off`, `1/0`.
+    pub fn get_bool(&self, key: &str) -> Result<Option<bool>, OgitError> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(v) => match v.to_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Ok(Some(true)),
+                "false" | "no" | "off" | "0" => Ok(Some(false)),
+                _ => Err(OgitError::InvalidInput(format!("Invalid boolean for {key}: {v}"))),
+            },
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), OgitError> {
+        let (section, name) = split_key(key)?;
+        self.sections
+            .entry(section)
+            .or_default()
+            .insert(name, value.to_string());
+        Ok(())
+    }
+}
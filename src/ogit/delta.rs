@@ -0,0 +1,196 @@
+//! Delta binari copy/insert, come i delta dei pack di Git.
+//!
+//! ```text
+//! dimensione base varint | dimensione risultato varint | istruzioni
+//!   1xxxxxxx [offset 0-4 byte] [len 0-3 byte]   copia dalla base
+//!   0nnnnnnn <n byte>                            inserisce n byte letterali (1..=127)
+//! ```
+//!
+//! Nella copia i bit 0-3 dicono quali byte (little-endian) dell'offset sono
+//! presenti, i bit 4-6 quali byte della lunghezza; lunghezza 0 vale 0x10000.
+//! I varint sono LEB128 (7 bit per byte, bit alto = continua).
+
+use std::collections::HashMap;
+
+use crate::error::OgitError;
+
+/// Lunghezza dei blocchi indicizzati nella base: match più corti non valgono una copia.
+const BLOCK: usize = 16;
+/// Massimo numero di byte in una singola istruzione di insert.
+const MAX_INSERT: usize = 0x7f;
+/// Massima lunghezza codificabile in una singola copia (3 byte).
+const MAX_COPY: usize = 0x00ff_ffff;
+
+/// Calcola il delta che trasforma `base` in `target`.
+///
+/// Algoritmo:
+/// 1. indicizza la base a blocchi di `BLOCK` byte allineati
+/// 2. scorre il target: se i prossimi `BLOCK` byte sono un blocco della base,
+///    estende il match all'indietro (dentro l'insert in sospeso) e in avanti
+///    ed emette una copia
+/// 3. altrimenti accumula il byte nell'insert corrente
+///
+/// Restituisce `None` se la base è troppo grande per gli offset a 32 bit.
+#[must_use]
+pub fn create_delta(base: &[u8], target: &[u8]) -> Option<Vec<u8>> {
+    if u32::try_from(base.len()).is_err() {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(target.len() / 4 + 16);
+    write_size(&mut out, base.len());
+    write_size(&mut out, target.len());
+
+    // 1. Indice dei blocchi: vince la prima occorrenza
+    let mut blocks: HashMap<&[u8], usize> = HashMap::new();
+    for offset in (0..base.len().saturating_sub(BLOCK - 1)).step_by(BLOCK) {
+        blocks.entry(&base[offset..offset + BLOCK]).or_insert(offset);
+    }
+
+    let mut pending = Vec::new();
+    let mut i = 0;
+    while i < target.len() {
+        let found = target.get(i..i + BLOCK).and_then(|block| blocks.get(block));
+        let Some(&offset) = found else {
+            // 3. Insert
+            pending.push(target[i]);
+            i += 1;
+            continue;
+        };
+
+        // 2. Copia, estesa nei due versi
+        let (mut base_start, mut target_start) = (offset, i);
+        while base_start > 0 && !pending.is_empty() && base[base_start - 1] == target[target_start - 1] {
+            base_start -= 1;
+            target_start -= 1;
+            pending.pop();
+        }
+        let mut len = i + BLOCK - target_start;
+        while base_start + len < base.len()
+            && target_start + len < target.len()
+            && base[base_start + len] == target[target_start + len]
+        {
+            len += 1;
+        }
+
+        flush_insert(&mut out, &mut pending);
+        write_copy(&mut out, base_start, len);
+        i = target_start + len;
+    }
+    flush_insert(&mut out, &mut pending);
+    Some(out)
+}
+
+/// Ricostruisce il target applicando `delta` a `base`.
+pub fn apply_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>, OgitError> {
+    let mut pos = 0;
+    let base_len = read_size(delta, &mut pos)?;
+    let target_len = read_size(delta, &mut pos)?;
+    if base_len != base.len() {
+        return Err(OgitError::corrupt(format!("Corrupt delta: base size {base_len}, expected {}", base.len())));
+    }
+
+    let mut out = Vec::with_capacity(target_len);
+    while pos < delta.len() {
+        let op = delta[pos];
+        pos += 1;
+
+        if op & 0x80 != 0 {
+            let offset = read_le(delta, &mut pos, op & 0x0f)?;
+            let len = match read_le(delta, &mut pos, (op >> 4) & 0x07)? {
+                0 => 0x10000,
+                n => n,
+            };
+            let chunk = offset
+                .checked_add(len)
+                .and_then(|end| base.get(offset..end))
+                .ok_or_else(|| OgitError::corrupt("Corrupt delta: copy outside the base"))?;
+            out.extend_from_slice(chunk);
+        } else if op != 0 {
+            let chunk = delta
+                .get(pos..pos + usize::from(op))
+                .ok_or_else(|| OgitError::corrupt("Corrupt delta: truncated insert"))?;
+            out.extend_from_slice(chunk);
+            pos += usize::from(op);
+        } else {
+            return Err(OgitError::corrupt("Corrupt delta: reserved instruction"));
+        }
+    }
+
+    if out.len() != target_len {
+        return Err(OgitError::corrupt(format!("Corrupt delta: result size {}, expected {target_len}", out.len())));
+    }
+    Ok(out)
+}
+
+fn flush_insert(out: &mut Vec<u8>, pending: &mut Vec<u8>) {
+    for chunk in pending.chunks(MAX_INSERT) {
+        out.push(chunk.len() as u8);
+        out.extend_from_slice(chunk);
+    }
+    pending.clear();
+}
+
+fn write_copy(out: &mut Vec<u8>, mut offset: usize, mut len: usize) {
+    while len > 0 {
+        let chunk = len.min(MAX_COPY);
+        let op_pos = out.len();
+        out.push(0x80);
+
+        let mut op = 0x80u8;
+        for (bit, byte) in (offset as u32).to_le_bytes().into_iter().enumerate() {
+            if byte != 0 {
+                op |= 1 << bit;
+                out.push(byte);
+            }
+        }
+        for (bit, byte) in (chunk as u32).to_le_bytes().into_iter().take(3).enumerate() {
+            if byte != 0 {
+                op |= 1 << (bit + 4);
+                out.push(byte);
+            }
+        }
+        out[op_pos] = op;
+
+        offset += chunk;
+        len -= chunk;
+    }
+}
+
+/// Legge un intero little-endian con i soli byte indicati dai bit di `mask`.
+fn read_le(delta: &[u8], pos: &mut usize, mask: u8) -> Result<usize, OgitError> {
+    let mut value = 0usize;
+    for bit in 0..4 {
+        if mask & (1 << bit) != 0 {
+            let byte = *delta.get(*pos).ok_or_else(|| OgitError::corrupt("Corrupt delta: truncated copy"))?;
+            value |= usize::from(byte) << (8 * bit);
+            *pos += 1;
+        }
+    }
+    Ok(value)
+}
+
+fn write_size(out: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn read_size(delta: &[u8], pos: &mut usize) -> Result<usize, OgitError> {
+    let mut value = 0usize;
+    for shift in (0..usize::BITS).step_by(7) {
+        let byte = *delta.get(*pos).ok_or_else(|| OgitError::corrupt("Corrupt delta: truncated header"))?;
+        *pos += 1;
+        value |= usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(OgitError::corrupt("Corrupt delta: size too long"))
+}
@@ -0,0 +1,642 @@
+//! Differenze fra contenuti (riga per riga) e fra tree.
+//!
+//! ```text
+//! old: a b c d        Myers: percorso più corto di cancellazioni (-)
+//! new: a c d e        e inserimenti (+) che trasforma old in new
+//! diff: ' a' '-b' ' c' ' d' '+e'
+//! ```
+//!
+//! L'output unificato (`--- a/…`, `+++ b/…`, `@@ -l,n +l,n @@`) raggruppa le
+//! modifiche in hunk con `context` righe invariate attorno, come `git diff`.
+//!
+//! `detect_renames` trasforma le coppie cancellato + aggiunto con contenuto
+//! uguale o simile in rinomine (e, su richiesta, gli aggiunti in copie).
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::error::OgitError;
+use crate::index::Index;
+use crate::object::{FileMode, OObject, OObjectId, OObjectType, TreeEntry};
+use crate::status::{Change, ChangeKind};
+use crate::object_store::ObjectStore;
+use crate::store::{object_format, object_id_as};
+use crate::tree::{hash_worktree_file, read_worktree_file};
+
+/// Righe di contesto predefinite attorno a ogni modifica (`-U3`).
+pub const DEFAULT_CONTEXT: usize = 3;
+
+/// Somiglianza minima predefinita per rinomine e copie (`-M50%`).
+pub const DEFAULT_RENAME_THRESHOLD: u8 = 50;
+
+/// Oltre questo numero di coppie origine × destinazione si cercano
+/// solo le rinomine esatte (come `diff.renameLimit` di Git).
+const RENAME_LIMIT: usize = 1000 * 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineTag {
+    Context,
+    Removed,
+    Added,
+}
+
+impl LineTag {
+    /// Prefisso della riga nell'output unificato
+    #[must_use]
+    pub const fn prefix(&self) -> char {
+        match self {
+            Self::Context => ' ',
+            Self::Removed => '-',
+            Self::Added => '+',
+        }
+    }
+}
+
+/// Una riga del diff; `text` include il `\n` finale, se presente.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine<'a> {
+    pub tag: LineTag,
+    pub text: &'a [u8],
+}
+
+/// Blocco `@@ -old_start,old_len +new_start,new_len @@`, con righe numerate da 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk<'a> {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine<'a>>,
+}
+
+impl Hunk<'_> {
+    /// Intestazione dell'hunk; come in Git `,1` si omette e un intervallo
+    /// vuoto indica la riga dopo cui avviene l'inserimento.
+    #[must_use]
+    pub fn header(&self) -> String {
+        let range = |start: usize, len: usize| match len {
+            0 => format!("{},0", start - 1),
+            1 => start.to_string(),
+            _ => format!("{start},{len}"),
+        };
+        format!("@@ -{} +{} @@", range(self.old_start, self.old_len), range(self.new_start, self.new_len))
+    }
+}
+
+/// File cambiato fra due tree (o fra un tree e la working directory).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeChange {
+    pub kind: ChangeKind,
+    pub path: String,
+    /// Path di origine per `Renamed` e `Copied`
+    pub old_path: Option<String>,
+    /// Percentuale di contenuto in comune con `old_path` (100 = identico)
+    pub similarity: u8,
+    pub old: Option<(OObjectId, FileMode)>,
+    pub new: Option<(OObjectId, FileMode)>,
+}
+
+impl From<TreeChange> for Change {
+    fn from(change: TreeChange) -> Self {
+        Self {
+            kind: change.kind,
+            path: change.path,
+            old_path: change.old_path,
+            old: change.old.map(|(id, _)| id),
+            new: change.new.map(|(id, _)| id),
+        }
+    }
+}
+
+/// Opzioni di `detect_renames` (come `-M` e `-C` di `git diff`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenameOptions {
+    pub renames: bool,
+    /// Cerca anche copie, usando come origine i file modificati o cancellati
+    pub copies: bool,
+    /// Somiglianza minima, in percentuale, per accoppiare due file diversi
+    pub threshold: u8,
+}
+
+impl Default for RenameOptions {
+    fn default() -> Self {
+        Self { renames: true, copies: false, threshold: DEFAULT_RENAME_THRESHOLD }
+    }
+}
+
+/// Righe di `data`, ognuna con il proprio `\n` (l'ultima può esserne priva).
+#[must_use]
+pub fn split_lines(data: &[u8]) -> Vec<&[u8]> {
+    data.split_inclusive(|&b| b == b'\n').collect()
+}
+
+/// Coppie `(i, j)` di elementi uguali in una sottosequenza comune massima
+/// di `a` e `b`, in ordine crescente.
+#[must_use]
+pub fn matching_lines<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    myers(a, b, (0, 0), &mut pairs);
+    pairs
+}
+
+/// Algoritmo di Myers in spazio lineare (O((N+M)·D) tempo, O(N+M) memoria).
+///
+/// Algoritmo:
+/// 1. prefisso e suffisso comuni sono coppie uguali e non entrano nella ricerca
+/// 2. se una delle due parti centrali è vuota, restano solo inserimenti o cancellazioni
+/// 3. altrimenti cerca lo snake centrale di un percorso minimo (`middle_snake`)
+///    e ripete su ciò che sta prima e dopo: ogni metà ha circa D/2 modifiche,
+///    quindi la ricorsione è profonda O(log D) e nessun fronte viene conservato
+fn myers<T: PartialEq>(a: &[T], b: &[T], (a_start, b_start): (usize, usize), pairs: &mut Vec<(usize, usize)>) {
+    // 1. Prefisso e suffisso comuni
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    pairs.extend((0..prefix).map(|i| (a_start + i, b_start + i)));
+
+    // 2-3. Parte centrale
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    if !a_mid.is_empty() && !b_mid.is_empty() {
+        let (a_mid_start, b_mid_start) = (a_start + prefix, b_start + prefix);
+        let ((x, y), (u, v)) = middle_snake(a_mid, b_mid);
+        myers(&a_mid[..x], &b_mid[..y], (a_mid_start, b_mid_start), pairs);
+        pairs.extend((0..u - x).map(|i| (a_mid_start + x + i, b_mid_start + y + i)));
+        myers(&a_mid[u..], &b_mid[v..], (a_mid_start + u, b_mid_start + v), pairs);
+    }
+
+    pairs.extend((0..suffix).map(|k| (a_start + a.len() - suffix + k, b_start + b.len() - suffix + k)));
+}
+
+/// Snake centrale di un percorso minimo da (0, 0) a (N, M): inizio `(x, y)` e
+/// fine `(u, v)`, con `a[x..u] == b[y..v]` (eventualmente vuoto).
+///
+/// Si estendono insieme i fronti da (0, 0) in avanti e da (N, M) all'indietro,
+/// una modifica per volta: quando si sovrappongono sulla stessa diagonale, lo
+/// snake appena percorso sta a metà del percorso. I fronti sono due vettori
+/// riscritti a ogni passo.
+fn middle_snake<T: PartialEq>(a: &[T], b: &[T]) -> ((usize, usize), (usize, usize)) {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+    // forward[k + offset] = x più avanzato sulla diagonale k = x - y;
+    // backward[k + offset] = lo stesso partendo dalla fine, con x e y contati da (N, M)
+    let mut forward = vec![0isize; 2 * max as usize + 3];
+    let mut backward = vec![0isize; 2 * max as usize + 3];
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let idx = (k + offset) as usize;
+            let start = if k == -d || (k != d && forward[idx - 1] < forward[idx + 1]) {
+                forward[idx + 1]
+            } else {
+                forward[idx - 1] + 1
+            };
+            let mut x = start;
+            while x < n && x - k < m && a[x as usize] == b[(x - k) as usize] {
+                x += 1;
+            }
+            forward[idx] = x;
+
+            // Con delta dispari i fronti si incontrano durante un passo in avanti
+            let back_k = delta - k;
+            if odd && (1 - d..d).contains(&back_k) && x + backward[(back_k + offset) as usize] >= n {
+                return snake(start, k, x);
+            }
+        }
+
+        for k in (-d..=d).step_by(2) {
+            let idx = (k + offset) as usize;
+            let start = if k == -d || (k != d && backward[idx - 1] < backward[idx + 1]) {
+                backward[idx + 1]
+            } else {
+                backward[idx - 1] + 1
+            };
+            let mut x = start;
+            while x < n && x - k < m && a[(n - 1 - x) as usize] == b[(m - 1 - x + k) as usize] {
+                x += 1;
+            }
+            backward[idx] = x;
+
+            // Con delta pari durante un passo all'indietro
+            let forward_k = delta - k;
+            if !odd && (-d..=d).contains(&forward_k) && x + forward[(forward_k + offset) as usize] >= n {
+                // In coordinate normali lo snake va da (N - x, M - y) a (N - start, M - y_start)
+                let ((x0, y0), (x1, y1)) = snake(start, k, x);
+                return ((a.len() - x1, b.len() - y1), (a.len() - x0, b.len() - y0));
+            }
+        }
+    }
+    unreachable!("a shortest edit path has at most N + M steps")
+}
+
+/// Snake sulla diagonale `k` da `x = start` a `x = end`.
+fn snake(start: isize, k: isize, end: isize) -> ((usize, usize), (usize, usize)) {
+    ((start as usize, (start - k) as usize), (end as usize, (end - k) as usize))
+}
+
+/// Diff riga per riga di `old` e `new`.
+#[must_use]
+pub fn diff_lines<'a>(old: &'a [u8], new: &'a [u8]) -> Vec<DiffLine<'a>> {
+    let (a, b) = (split_lines(old), split_lines(new));
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    for (mi, mj) in matching_lines(&a, &b).into_iter().chain([(a.len(), b.len())]) {
+        lines.extend(a[i..mi].iter().map(|text| DiffLine { tag: LineTag::Removed, text }));
+        lines.extend(b[j..mj].iter().map(|text| DiffLine { tag: LineTag::Added, text }));
+        if mi < a.len() {
+            lines.push(DiffLine { tag: LineTag::Context, text: a[mi] });
+        }
+        (i, j) = (mi + 1, mj + 1);
+    }
+    lines
+}
+
+/// Raggruppa il diff in hunk con `context` righe invariate attorno a ogni
+/// modifica; due modifiche separate da al più `2 * context` righe finiscono
+/// nello stesso hunk.
+#[must_use]
+pub fn hunks<'a>(old: &'a [u8], new: &'a [u8], context: usize) -> Vec<Hunk<'a>> {
+    let lines = diff_lines(old, new);
+
+    // Posizione (0-based) in old e new prima di ogni riga
+    let mut positions = Vec::with_capacity(lines.len());
+    let (mut o, mut n) = (0, 0);
+    for line in &lines {
+        positions.push((o, n));
+        match line.tag {
+            LineTag::Context => (o, n) = (o + 1, n + 1),
+            LineTag::Removed => o += 1,
+            LineTag::Added => n += 1,
+        }
+    }
+
+    let changed: Vec<usize> = (0..lines.len()).filter(|&i| lines[i].tag != LineTag::Context).collect();
+    let mut result = Vec::new();
+    let mut k = 0;
+    while k < changed.len() {
+        let start = changed[k].saturating_sub(context);
+        let mut last = changed[k];
+        while k + 1 < changed.len() && changed[k + 1] - last - 1 <= 2 * context {
+            k += 1;
+            last = changed[k];
+        }
+        let end = (last + context + 1).min(lines.len());
+        k += 1;
+
+        let slice = &lines[start..end];
+        let old_len = slice.iter().filter(|l| l.tag != LineTag::Added).count();
+        let new_len = slice.iter().filter(|l| l.tag != LineTag::Removed).count();
+        result.push(Hunk {
+            old_start: positions[start].0 + 1,
+            old_len,
+            new_start: positions[start].1 + 1,
+            new_len,
+            lines: slice.to_vec(),
+        });
+    }
+    result
+}
+
+/// Diff unificato fra `old` e `new`; stringa vuota se non ci sono differenze.
+///
+/// Un contenuto binario (con byte `\0`) produce solo `Binary files … differ`.
+#[must_use]
+pub fn unified_diff(old_label: &str, new_label: &str, old: &[u8], new: &[u8], context: usize) -> String {
+    if old == new {
+        return String::new();
+    }
+    if old.contains(&0) || new.contains(&0) {
+        return format!("Binary files {old_label} and {new_label} differ\n");
+    }
+
+    let mut out = format!("--- {old_label}\n+++ {new_label}\n");
+    for hunk in hunks(old, new, context) {
+        out.push_str(&hunk.header());
+        out.push('\n');
+        for line in &hunk.lines {
+            out.push(line.tag.prefix());
+            out.push_str(&String::from_utf8_lossy(line.text));
+            if !line.text.ends_with(b"\n") {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    out
+}
+
+/// Differenze fra due tree, visitando ricorsivamente le loro entry.
+///
+/// Le sottodirectory con lo stesso hash vengono saltate senza leggerle.
+/// `None` indica un tree vuoto (es. il primo commit).
+pub fn diff_trees(
+    objects: &dyn ObjectStore,
+    old: Option<&OObjectId>,
+    new: Option<&OObjectId>,
+) -> Result<Vec<TreeChange>, OgitError> {
+    let mut changes = Vec::new();
+    diff_subtrees(objects, old, new, "", &mut changes)?;
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(changes)
+}
+
+fn tree_entries(objects: &dyn ObjectStore, tree: Option<&OObjectId>) -> Result<BTreeMap<String, TreeEntry>, OgitError> {
+    Ok(match tree {
+        Some(id) => objects.read_tree(id)?.into_iter().map(|e| (e.name.clone(), e)).collect(),
+        None => BTreeMap::new(),
+    })
+}
+
+fn diff_subtrees(
+    objects: &dyn ObjectStore,
+    old: Option<&OObjectId>,
+    new: Option<&OObjectId>,
+    prefix: &str,
+    changes: &mut Vec<TreeChange>,
+) -> Result<(), OgitError> {
+    if old == new {
+        return Ok(());
+    }
+    let (old_entries, new_entries) = (tree_entries(objects, old)?, tree_entries(objects, new)?);
+    let names: BTreeSet<&String> = old_entries.keys().chain(new_entries.keys()).collect();
+
+    for name in names {
+        let path = format!("{prefix}{name}");
+        let (o, n) = (old_entries.get(name), new_entries.get(name));
+
+        // Una directory diventata file (o viceversa) è una cancellazione più un'aggiunta
+        let (old_tree, new_tree) = (subtree_of(o), subtree_of(n));
+        if old_tree.is_some() || new_tree.is_some() {
+            diff_subtrees(objects, old_tree, new_tree, &format!("{path}/"), changes)?;
+        }
+        if let Some(change) = file_change(path, file_of(o), file_of(n)) {
+            changes.push(change);
+        }
+    }
+    Ok(())
+}
+
+fn subtree_of(entry: Option<&TreeEntry>) -> Option<&OObjectId> {
+    entry.filter(|e| e.kind == OObjectType::Tree).map(|e| &e.hash)
+}
+
+fn file_of(entry: Option<&TreeEntry>) -> Option<(OObjectId, FileMode)> {
+    entry.filter(|e| e.kind != OObjectType::Tree).map(|e| (e.hash.clone(), e.mode))
+}
+
+fn file_change(path: String, old: Option<(OObjectId, FileMode)>, new: Option<(OObjectId, FileMode)>) -> Option<TreeChange> {
+    let kind = match (&old, &new) {
+        (None, Some(_)) => ChangeKind::Added,
+        (Some(_), None) => ChangeKind::Deleted,
+        (Some(a), Some(b)) if a != b => ChangeKind::Modified,
+        _ => return None,
+    };
+    Some(TreeChange { kind, path, old_path: None, similarity: 0, old, new })
+}
+
+/// Differenze fra due mappe `path → (hash, modo)` (es. indice e working directory).
+#[must_use]
+pub fn diff_files(
+    old: &BTreeMap<String, (OObjectId, FileMode)>,
+    new: &BTreeMap<String, (OObjectId, FileMode)>,
+) -> Vec<TreeChange> {
+    let paths: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    paths
+        .into_iter()
+        .filter_map(|path| file_change(path.clone(), old.get(path).cloned(), new.get(path).cloned()))
+        .collect()
+}
+
+/// File dell'indice con hash e modo (il tree di HEAD se l'indice non esiste).
+pub fn index_files(store_path: &Path) -> Result<BTreeMap<String, (OObjectId, FileMode)>, OgitError> {
+    Ok(Index::load_or_head(store_path)?
+        .entries
+        .into_iter()
+        .map(|(path, entry)| (path, (entry.hash, entry.mode)))
+        .collect())
+}
+
+/// Hash e modo dei `paths` presenti nella working directory; quelli
+/// mancanti sono omessi (cioè risultano cancellati).
+pub fn worktree_files<'a>(
+    store_path: &Path,
+    work_dir: &Path,
+    paths: impl IntoIterator<Item = &'a String>,
+) -> Result<BTreeMap<String, (OObjectId, FileMode)>, OgitError> {
+    let format = object_format(store_path)?;
+    let mut files = BTreeMap::new();
+    for path in paths {
+        let file_path = work_dir.join(path);
+        if fs::symlink_metadata(&file_path).is_ok_and(|m| !m.is_dir()) {
+            let (mode, hash) = hash_worktree_file(&file_path, format)?;
+            files.insert(path.clone(), (hash, mode));
+        }
+    }
+    Ok(files)
+}
+
+/// Somiglianza fra due contenuti, in percentuale: byte delle righe in comune
+/// (contate con molteplicità) rispetto al più grande dei due.
+///
+/// I file vuoti non somigliano a niente, così non diventano rinomine casuali.
+#[must_use]
+pub fn similarity(a: &[u8], b: &[u8]) -> u8 {
+    if a.is_empty() || b.is_empty() {
+        return 0;
+    }
+    if a == b {
+        return 100;
+    }
+
+    let mut counts: HashMap<&[u8], usize> = HashMap::new();
+    for line in split_lines(a) {
+        *counts.entry(line).or_insert(0) += 1;
+    }
+    let mut common = 0;
+    for line in split_lines(b) {
+        if let Some(count) = counts.get_mut(line)
+            && *count > 0
+        {
+            *count -= 1;
+            common += line.len();
+        }
+    }
+    // Solo un contenuto identico vale 100
+    (common * 100 / a.len().max(b.len())).min(99) as u8
+}
+
+/// Contenuto del lato vecchio di `change` (sempre dallo store).
+fn old_content(objects: &dyn ObjectStore, change: &TreeChange) -> Result<Vec<u8>, OgitError> {
+    match &change.old {
+        Some((id, _)) => Ok(objects.read(id)?.data),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Contenuto del lato nuovo di `change`: dal file su disco se `work_dir`
+/// è indicata, altrimenti dallo store.
+fn new_content(objects: &dyn ObjectStore, change: &TreeChange, work_dir: Option<&Path>) -> Result<Vec<u8>, OgitError> {
+    match (&change.new, work_dir) {
+        (Some(_), Some(dir)) => Ok(read_worktree_file(&dir.join(&change.path))?.1),
+        (Some((id, _)), None) => Ok(objects.read(id)?.data),
+        (None, _) => Ok(Vec::new()),
+    }
+}
+
+/// Sostituisce le coppie cancellato + aggiunto con `Renamed` (e, con
+/// `options.copies`, gli aggiunti simili a un file esistente con `Copied`).
+///
+/// Algoritmo:
+/// 1. origini = file cancellati (e modificati, per le copie); destinazioni = file
+///    aggiunti; i file vuoti non partecipano
+/// 2. ogni coppia con lo stesso hash vale 100, le altre `similarity` (solo
+///    entro `RENAME_LIMIT`); si scartano quelle sotto `options.threshold`
+/// 3. dalla coppia migliore (a parità, stesso nome di file): la destinazione
+///    diventa una rinomina se l'origine è cancellata e non ancora usata,
+///    altrimenti una copia
+/// 4. i cancellati diventati rinomine spariscono dall'elenco
+pub fn detect_renames(
+    objects: &dyn ObjectStore,
+    changes: Vec<TreeChange>,
+    work_dir: Option<&Path>,
+    options: &RenameOptions,
+) -> Result<Vec<TreeChange>, OgitError> {
+    if !options.renames && !options.copies {
+        return Ok(changes);
+    }
+
+    // 1. Origini e destinazioni, esclusi i file vuoti
+    let empty = object_id_as(&OObject::new_blob(Vec::new()), objects.format());
+    let is_empty = |side: &Option<(OObjectId, FileMode)>| side.as_ref().is_some_and(|(id, _)| id == &empty);
+    let (mut added, mut rest): (Vec<TreeChange>, Vec<TreeChange>) =
+        changes.into_iter().partition(|c| c.kind == ChangeKind::Added && !is_empty(&c.new));
+    let sources: Vec<usize> = (0..rest.len())
+        .filter(|&i| {
+            let kind = rest[i].kind;
+            (kind == ChangeKind::Deleted || (options.copies && kind == ChangeKind::Modified)) && !is_empty(&rest[i].old)
+        })
+        .collect();
+
+    // 2. Coppie candidate (somiglianza, destinazione, origine)
+    let inexact = added.len() * sources.len() <= RENAME_LIMIT;
+    let source_data = if inexact {
+        sources.iter().map(|&i| old_content(objects, &rest[i])).collect::<Result<Vec<_>, _>>()?
+    } else {
+        Vec::new()
+    };
+    let mut candidates = Vec::new();
+    for (d, dest) in added.iter().enumerate() {
+        let dest_data = if inexact { new_content(objects, dest, work_dir)? } else { Vec::new() };
+        for (s, &i) in sources.iter().enumerate() {
+            let same_id = dest.new.as_ref().map(|(id, _)| id) == rest[i].old.as_ref().map(|(id, _)| id);
+            let score = match (same_id, inexact) {
+                (true, _) => 100,
+                (false, true) => similarity(&source_data[s], &dest_data),
+                (false, false) => 0,
+            };
+            if score >= options.threshold.max(1) {
+                candidates.push((score, d, s));
+            }
+        }
+    }
+
+    // 3. Assegnazione, dalla coppia più simile
+    let file_name = |path: &str| path.rsplit('/').next().unwrap_or(path).to_string();
+    candidates.sort_by_key(|&(score, d, s)| {
+        let same_name = file_name(&added[d].path) == file_name(&rest[sources[s]].path);
+        (std::cmp::Reverse(score), !same_name, d, s)
+    });
+
+    let mut assigned = vec![false; added.len()];
+    let mut renamed: HashSet<usize> = HashSet::new();
+    for (score, d, s) in candidates {
+        if assigned[d] {
+            continue;
+        }
+        let source = &rest[sources[s]];
+        let kind = if options.renames && source.kind == ChangeKind::Deleted && !renamed.contains(&s) {
+            renamed.insert(s);
+            ChangeKind::Renamed
+        } else if options.copies {
+            ChangeKind::Copied
+        } else {
+            continue;
+        };
+
+        assigned[d] = true;
+        let dest = &mut added[d];
+        dest.kind = kind;
+        dest.old_path = Some(source.path.clone());
+        dest.old = source.old.clone();
+        dest.similarity = score;
+    }
+
+    // 4. Elenco finale, ordinato per path
+    let renamed_paths: HashSet<String> = renamed.iter().map(|&s| rest[sources[s]].path.clone()).collect();
+    rest.retain(|c| !(c.kind == ChangeKind::Deleted && renamed_paths.contains(&c.path)));
+    rest.extend(added);
+    rest.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(rest)
+}
+
+/// Patch di un singolo file in formato `git diff`.
+///
+/// Il lato vecchio si legge sempre dallo store; il nuovo dallo store oppure,
+/// se `work_dir` è indicata, dal file su disco.
+pub fn format_patch(
+    objects: &dyn ObjectStore,
+    change: &TreeChange,
+    work_dir: Option<&Path>,
+    context: usize,
+) -> Result<String, OgitError> {
+    let path = &change.path;
+    let old_path = change.old_path.as_ref().unwrap_or(path);
+    let mut out = format!("diff --git a/{old_path} b/{path}\n");
+
+    match change.kind {
+        ChangeKind::Renamed => out.push_str(&format!(
+            "similarity index {}%\nrename from {old_path}\nrename to {path}\n",
+            change.similarity
+        )),
+        ChangeKind::Copied => out.push_str(&format!(
+            "similarity index {}%\ncopy from {old_path}\ncopy to {path}\n",
+            change.similarity
+        )),
+        _ => {}
+    }
+
+    let zero = "0".repeat(OObjectId::SHORT_LEN);
+    match (&change.old, &change.new) {
+        (None, Some((id, mode))) => {
+            out.push_str(&format!("new file mode {}\nindex {zero}..{}\n", mode.as_str(), id.short()));
+        }
+        (Some((id, mode)), None) => {
+            out.push_str(&format!("deleted file mode {}\nindex {}..{zero}\n", mode.as_str(), id.short()));
+        }
+        (Some((old_id, old_mode)), Some((new_id, new_mode))) => {
+            if old_mode != new_mode {
+                out.push_str(&format!("old mode {}\nnew mode {}\n", old_mode.as_str(), new_mode.as_str()));
+            }
+            if old_id != new_id {
+                let mode = if old_mode == new_mode { format!(" {}", new_mode.as_str()) } else { String::new() };
+                out.push_str(&format!("index {}..{}{mode}\n", old_id.short(), new_id.short()));
+            }
+        }
+        (None, None) => return Ok(String::new()),
+    }
+
+    let old_data = old_content(objects, change)?;
+    let new_data = new_content(objects, change, work_dir)?;
+    let old_label = if change.old.is_some() { format!("a/{old_path}") } else { "/dev/null".to_string() };
+    let new_label = if change.new.is_some() { format!("b/{path}") } else { "/dev/null".to_string() };
+
+    out.push_str(&unified_diff(&old_label, &new_label, &old_data, &new_data, context));
+    Ok(out)
+}
@@ -0,0 +1,101 @@
+//! Errore tipizzato della libreria.
+//!
+//! I chiamanti distinguono i casi con un `match` sulla variante; il testo
+//! (`Display`) resta quello mostrato dalla CLI. Gli errori di I/O conservano
+//! l'`io::Error` originale, raggiungibile con `Error::source`; anche
+//! `Corrupt`, quando il dato illeggibile è emerso da una lettura fallita
+//! (zlib, file troncato).
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::object::{OObjectId, OObjectType};
+
+#[derive(Debug)]
+pub enum OgitError {
+    /// Oggetto assente sia loose sia nei pack
+    ObjectNotFound(OObjectId),
+    /// L'oggetto esiste ma non è del tipo richiesto
+    WrongType { id: OObjectId, expected: OObjectType },
+    /// Dati nello store illeggibili o incoerenti (oggetti, pack, delta, index)
+    Corrupt { reason: String, source: Option<io::Error> },
+    /// Operazione sul filesystem fallita; `context` dice quale
+    Io { context: String, source: io::Error },
+    /// Ref, branch o revisione inesistente o malformata
+    InvalidRef(String),
+    /// Prefisso abbreviato condiviso da più oggetti
+    AmbiguousId { prefix: String, candidates: Vec<OObjectId> },
+    /// Nessun repository nel path indicato (o nelle directory superiori)
+    NotARepository(PathBuf),
+    /// Il lock file esiste già: un altro processo sta scrivendo lo stesso file
+    Locked(PathBuf),
+    /// Argomento, path, chiave di config o valore non valido
+    InvalidInput(String),
+    /// Operazione rifiutata per lo stato del repository (merge in corso,
+    /// modifiche locali, branch non unito...)
+    Rejected(String),
+}
+
+impl OgitError {
+    /// Adattatore per `map_err`: `.map_err(OgitError::io("Failed to read index"))`.
+    pub fn io(context: impl Into<String>) -> impl FnOnce(io::Error) -> Self {
+        let context = context.into();
+        move |source| Self::Io { context, source }
+    }
+
+    /// `Corrupt` senza un errore di I/O all'origine.
+    pub fn corrupt(reason: impl Into<String>) -> Self {
+        Self::Corrupt { reason: reason.into(), source: None }
+    }
+
+    /// Come `io`, per le letture che falliscono perché il dato è rotto:
+    /// `.map_err(OgitError::corrupt_io("Failed to decompress"))`.
+    pub fn corrupt_io(reason: impl Into<String>) -> impl FnOnce(io::Error) -> Self {
+        let reason = reason.into();
+        move |source| Self::Corrupt { reason, source: Some(source) }
+    }
+}
+
+impl fmt::Display for OgitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ObjectNotFound(id) => write!(f, "Object not found: {id}"),
+            Self::WrongType { id, expected } => write!(f, "Object {id} is not a {}", expected.as_str()),
+            Self::Io { context, source } => write!(f, "{context}: {source}"),
+            Self::Corrupt { reason, source: Some(source) } => write!(f, "{reason}: {source}"),
+            Self::Corrupt { reason, source: None } => f.write_str(reason),
+            Self::NotARepository(path) => write!(f, "Not an ogit repository: {}", path.display()),
+            Self::Locked(path) => write!(
+                f,
+                "Unable to create {}: File exists. Another ogit process seems to be running; \
+                 if it crashed, remove the file manually",
+                path.display()
+            ),
+            Self::AmbiguousId { prefix, candidates } => {
+                write!(f, "Short object id {prefix} is ambiguous; candidates are:")?;
+                candidates.iter().try_for_each(|id| write!(f, "\n  {id}"))
+            }
+            Self::InvalidRef(msg) | Self::InvalidInput(msg) | Self::Rejected(msg) => {
+                f.write_str(msg)
+            }
+        }
+    }
+}
+
+impl Error for OgitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io { source, .. } | Self::Corrupt { source: Some(source), .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// La CLI riporta gli errori come testo.
+impl From<OgitError> for String {
+    fn from(err: OgitError) -> Self {
+        err.to_string()
+    }
+}
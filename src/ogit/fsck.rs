@@ -0,0 +1,269 @@
+//! Verifica di integrità e connettività del repository (`ogit fsck`).
+//!
+//! Di ogni pack si ricalcola il checksum, che deve coincidere con quello in
+//! coda al `.pack` e al `.idx`. Ogni oggetto (loose o nei pack) viene
+//! riletto a blocchi, ri-hashato e confrontato con il suo id; tree e commit
+//! vengono poi interpretati, e se ne ricavano i riferimenti, che devono
+//! puntare a oggetti esistenti e del tipo giusto. Infine si visitano gli oggetti raggiungibili da ref e indice: gli
+//! altri sono `unreachable`, e fra questi quelli che nessun oggetto
+//! referenzia sono `dangling` (le "teste" di ciò che si è perso).
+//!
+//! Ogni problema si stampa su una riga, con campi separati da spazi:
+//!
+//! ```text
+//! hash-mismatch <tipo> <id> <hash reale>
+//! corrupt <tipo|-> <id> <motivo...>
+//! missing <tipo> <id> <referenziato da>
+//! bad-type <tipo atteso> <id> <referenziato da>
+//! bad-ref - - <nome> <motivo...>
+//! bad-pack - - <path> <motivo...>
+//! dangling <tipo> <id>
+//! unreachable <tipo> <id>
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::error::OgitError;
+use crate::index::Index;
+use crate::merge::read_merge_state;
+use crate::object::{Commit, OObject, OObjectId, OObjectType, ObjectFormat, ObjectHasher, TreeEntry};
+use crate::object_store::{LooseStore, ObjectStore};
+use crate::refs::{list_refs, resolve_ref, HEAD};
+
+/// Un problema trovato da `fsck`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsckIssue {
+    /// Il contenuto non ha l'hash del suo id
+    HashMismatch { id: OObjectId, kind: OObjectType, actual: OObjectId },
+    /// Oggetto illeggibile: zlib, header, tree o commit non validi
+    Corrupt { id: OObjectId, kind: Option<OObjectType>, reason: String },
+    /// Riferimento (da un oggetto, un ref o l'indice) a un oggetto assente
+    Missing { id: OObjectId, kind: OObjectType, referenced_by: String },
+    /// Riferimento a un oggetto esistente ma di tipo diverso da quello atteso
+    BadType { id: OObjectId, expected: OObjectType, referenced_by: String },
+    /// Ref illeggibile (contenuto non valido o catena simbolica rotta)
+    BadRef { name: String, reason: String },
+    /// Pack con checksum errato o illeggibile
+    BadPack { path: String, reason: String },
+    /// Non raggiungibile e non referenziato da nessun oggetto
+    Dangling { id: OObjectId, kind: OObjectType },
+    /// Non raggiungibile, ma referenziato da un altro oggetto non raggiungibile
+    Unreachable { id: OObjectId, kind: OObjectType },
+}
+
+impl FsckIssue {
+    /// Gli oggetti non raggiungibili non sono errori: `gc` li elimina.
+    #[must_use]
+    pub const fn is_error(&self) -> bool {
+        !matches!(self, Self::Dangling { .. } | Self::Unreachable { .. })
+    }
+}
+
+impl fmt::Display for FsckIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HashMismatch { id, kind, actual } => write!(f, "hash-mismatch {} {id} {actual}", kind.as_str()),
+            Self::Corrupt { id, kind, reason } => {
+                write!(f, "corrupt {} {id} {reason}", kind.map_or("-", |k| k.as_str()))
+            }
+            Self::Missing { id, kind, referenced_by } => write!(f, "missing {} {id} {referenced_by}", kind.as_str()),
+            Self::BadType { id, expected, referenced_by } => {
+                write!(f, "bad-type {} {id} {referenced_by}", expected.as_str())
+            }
+            Self::BadRef { name, reason } => write!(f, "bad-ref - - {name} {reason}"),
+            Self::BadPack { path, reason } => write!(f, "bad-pack - - {path} {reason}"),
+            Self::Dangling { id, kind } => write!(f, "dangling {} {id}", kind.as_str()),
+            Self::Unreachable { id, kind } => write!(f, "unreachable {} {id}", kind.as_str()),
+        }
+    }
+}
+
+/// Esito di `fsck`: oggetti controllati e problemi, nell'ordine in cui sono emersi.
+#[derive(Debug, Default)]
+pub struct FsckReport {
+    pub objects: usize,
+    pub issues: Vec<FsckIssue>,
+}
+
+impl FsckReport {
+    /// `true` se non ci sono errori (oggetti non raggiungibili ammessi).
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        !self.issues.iter().any(FsckIssue::is_error)
+    }
+}
+
+/// Verifica l'intero repository.
+///
+/// Algoritmo:
+/// 1. per ogni pack: checksum ricalcolato a blocchi contro `.pack` e `.idx`
+/// 2. per ogni oggetto: rilegge a blocchi i byte serializzati (file loose o
+///    pack), ricalcola l'hash e lo confronta con l'id, poi interpreta tree e commit
+///    raccogliendo i riferimenti con il tipo atteso
+/// 3. ogni riferimento deve puntare a un oggetto esistente del tipo atteso
+/// 4. radici: HEAD, tutti i ref, MERGE_HEAD e i blob dell'indice; devono esistere
+/// 5. visita in ampiezza dalle radici; ciò che resta è `dangling` se nessun
+///    oggetto lo referenzia, altrimenti `unreachable`
+pub fn fsck(store_path: &Path) -> Result<FsckReport, OgitError> {
+    let objects = LooseStore::open(store_path)?;
+    let mut report = FsckReport::default();
+
+    // 1. Pack
+    for index in objects.packs()?.indexes() {
+        if let Err(e) = index.verify_checksum() {
+            let path = index.pack_path().display().to_string();
+            report.issues.push(FsckIssue::BadPack { path, reason: e.to_string() });
+        }
+    }
+
+    // 2. Oggetti (tipo `None`: presente ma già segnalato come rotto)
+    let mut kinds: BTreeMap<OObjectId, Option<OObjectType>> = BTreeMap::new();
+    let mut links: BTreeMap<OObjectId, Vec<(OObjectId, OObjectType)>> = BTreeMap::new();
+    for id in objects.ids()? {
+        report.objects += 1;
+        let (kind, object) = match check_object(&objects, &id) {
+            Ok(checked) => checked,
+            Err(issue) => {
+                report.issues.push(issue);
+                kinds.insert(id, None);
+                continue;
+            }
+        };
+        kinds.insert(id.clone(), Some(kind));
+        let Some(object) = object else { continue };
+
+        match object_links(&id, &object) {
+            Ok(targets) => {
+                links.insert(id, targets);
+            }
+            Err(e) => report.issues.push(FsckIssue::Corrupt { id, kind: Some(object.kind), reason: e.to_string() }),
+        }
+    }
+
+    // 3. Riferimenti fra oggetti
+    for (from, targets) in &links {
+        for (to, expected) in targets {
+            if let Some(issue) = check_link(&kinds, to, *expected, &from.to_string()) {
+                report.issues.push(issue);
+            }
+        }
+    }
+
+    // 4. Radici
+    let mut roots = Vec::new();
+    let mut named_roots = Vec::new();
+    for name in std::iter::once(HEAD.to_string()).chain(list_refs(store_path)?) {
+        match resolve_ref(store_path, &name) {
+            Ok(id) => named_roots.push((name, id, OObjectType::Commit)),
+            Err(e) => report.issues.push(FsckIssue::BadRef { name, reason: e.to_string() }),
+        }
+    }
+    match read_merge_state(store_path) {
+        Ok(state) => named_roots.extend(state.map(|s| ("MERGE_HEAD".to_string(), Some(s.head), OObjectType::Commit))),
+        Err(e) => report.issues.push(FsckIssue::BadRef { name: "MERGE_HEAD".to_string(), reason: e.to_string() }),
+    }
+    for entry in Index::load(store_path)?.entries.into_values() {
+        named_roots.push(("index".to_string(), Some(entry.hash), OObjectType::Blob));
+    }
+    for (name, id, expected) in named_roots {
+        let Some(id) = id else { continue };
+        match check_link(&kinds, &id, expected, &name) {
+            Some(issue) => report.issues.push(issue),
+            None => roots.push(id),
+        }
+    }
+
+    // 5. Raggiungibilità
+    let mut reachable: BTreeSet<OObjectId> = BTreeSet::new();
+    let mut queue: VecDeque<OObjectId> = roots.into();
+    while let Some(id) = queue.pop_front() {
+        if !reachable.insert(id.clone()) {
+            continue;
+        }
+        for (target, _) in links.get(&id).into_iter().flatten() {
+            if kinds.get(target).is_some_and(Option::is_some) {
+                queue.push_back(target.clone());
+            }
+        }
+    }
+
+    let referenced: BTreeSet<&OObjectId> = links.values().flatten().map(|(id, _)| id).collect();
+    for (id, kind) in &kinds {
+        let Some(kind) = *kind else { continue };
+        if reachable.contains(id) {
+            continue;
+        }
+        let id = id.clone();
+        report.issues.push(if referenced.contains(&id) {
+            FsckIssue::Unreachable { id, kind }
+        } else {
+            FsckIssue::Dangling { id, kind }
+        });
+    }
+
+    Ok(report)
+}
+
+/// Rilegge un oggetto a blocchi e ne verifica l'hash. Tree e commit si
+/// restituiscono per ricavarne i riferimenti; un blob, che non ne ha, non
+/// viene mai tenuto tutto in memoria.
+fn check_object(objects: &LooseStore, id: &OObjectId) -> Result<(OObjectType, Option<OObject>), FsckIssue> {
+    let corrupt = |kind, e: OgitError| FsckIssue::Corrupt { id: id.clone(), kind, reason: e.to_string() };
+    let read_failed = |kind, e| corrupt(kind, OgitError::corrupt_io(format!("Failed to read {id}"))(e));
+
+    let mut reader = objects.read_stream(id).map_err(|e| corrupt(None, e))?;
+    let kind = reader.kind;
+    let mut hasher = ObjectHasher::new(ObjectFormat::from_id(id), kind, reader.size);
+    let object = if kind == OObjectType::Blob {
+        io::copy(&mut reader, &mut hasher).map_err(|e| read_failed(None, e))?;
+        None
+    } else {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).map_err(|e| read_failed(None, e))?;
+        hasher.update(&data);
+        Some(OObject { kind, data })
+    };
+
+    let actual = hasher.finish().map_err(|e| corrupt(None, e))?;
+    if actual != *id {
+        return Err(FsckIssue::HashMismatch { id: id.clone(), kind, actual });
+    }
+    Ok((kind, object))
+}
+
+/// Oggetti referenziati da un tree o da un commit, con il tipo atteso.
+pub(crate) fn object_links(id: &OObjectId, object: &OObject) -> Result<Vec<(OObjectId, OObjectType)>, OgitError> {
+    let format = ObjectFormat::from_id(id);
+    match object.kind {
+        OObjectType::Blob => Ok(Vec::new()),
+        OObjectType::Tree => Ok(TreeEntry::deserialize_tree_as(&object.data, format)?
+            .into_iter()
+            .map(|entry| (entry.hash, entry.kind))
+            .collect()),
+        OObjectType::Commit => {
+            let commit = Commit::deserialize_as(&object.data, format)?;
+            let mut targets = vec![(commit.tree, OObjectType::Tree)];
+            targets.extend(commit.parents.into_iter().map(|parent| (parent, OObjectType::Commit)));
+            Ok(targets)
+        }
+    }
+}
+
+fn check_link(
+    kinds: &BTreeMap<OObjectId, Option<OObjectType>>,
+    id: &OObjectId,
+    expected: OObjectType,
+    referenced_by: &str,
+) -> Option<FsckIssue> {
+    let referenced_by = referenced_by.to_string();
+    match kinds.get(id) {
+        None => Some(FsckIssue::Missing { id: id.clone(), kind: expected, referenced_by }),
+        Some(Some(kind)) if *kind != expected => {
+            Some(FsckIssue::BadType { id: id.clone(), expected, referenced_by })
+        }
+        Some(_) => None,
+    }
+}
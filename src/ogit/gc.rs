@@ -0,0 +1,246 @@
+//! Eliminazione degli oggetti non raggiungibili (`ogit prune`, `ogit gc`).
+//!
+//! `store`, `write-tree` e i commit abbandonati lasciano oggetti che nessun
+//! ref raggiunge. Sono raggiungibili gli oggetti collegati (commit → tree e
+//! genitori, tree → voci) a HEAD, ai ref in `refs/`, a MERGE_HEAD e ai blob
+//! dell'indice. Il repository non ha un reflog: se verrà aggiunto, le sue
+//! voci andranno fra le radici di `reachable_objects`.
+//!
+//! Un oggetto appena scritto può non essere ancora raggiungibile (`commit`
+//! scrive tree e commit prima di aggiornare il ref), quindi si eliminano solo
+//! gli oggetti più vecchi del periodo di grazia `gc.pruneExpire` (predefinito
+//! due settimane), misurato sulla data di modifica del file.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::fs::{self, File};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::config::Config;
+use crate::error::OgitError;
+use crate::fsck::object_links;
+use crate::index::Index;
+use crate::merge::read_merge_state;
+use crate::object::{OObjectId, OObjectType};
+use crate::object_store::{LooseStore, ObjectStore};
+use crate::pack::{pack_indexes, repack_filtered, PackOptions, RepackStats};
+use crate::refs::{list_refs, resolve_ref, HEAD};
+use crate::store::{list_objects, object_path, write_loose};
+
+/// Chiave di configurazione: periodo di grazia degli oggetti non raggiungibili.
+pub const PRUNE_EXPIRE_KEY: &str = "gc.pruneexpire";
+/// Periodo di grazia predefinito: due settimane, come Git.
+pub const DEFAULT_PRUNE_EXPIRE: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+/// Parametri di `prune` e `gc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PruneOptions {
+    /// Età minima di un oggetto non raggiungibile per eliminarlo; `None`: mai
+    pub expire: Option<Duration>,
+    /// Elenca gli oggetti da eliminare senza toccare lo store
+    pub dry_run: bool,
+}
+
+impl Default for PruneOptions {
+    fn default() -> Self {
+        Self { expire: Some(DEFAULT_PRUNE_EXPIRE), dry_run: false }
+    }
+}
+
+impl PruneOptions {
+    /// Legge `gc.pruneExpire`, con il predefinito se la chiave è assente.
+    pub fn load(store_path: &Path) -> Result<Self, OgitError> {
+        let config = Config::load(store_path)?;
+        let expire = config.get(PRUNE_EXPIRE_KEY).map_or(Ok(Some(DEFAULT_PRUNE_EXPIRE)), parse_expire)?;
+        Ok(Self { expire, ..Self::default() })
+    }
+}
+
+/// Interpreta un periodo di grazia: `now`, `never`, oppure un numero con
+/// unità `s`, `m`, `h`, `d`, `w` (senza unità: secondi), es. `2w`, `90m`.
+pub fn parse_expire(value: &str) -> Result<Option<Duration>, OgitError> {
+    let invalid = || OgitError::InvalidInput(format!("Invalid expiry: {value}"));
+    let value = value.trim();
+    match value {
+        "now" => return Ok(Some(Duration::ZERO)),
+        "never" => return Ok(None),
+        _ => {}
+    }
+
+    let (number, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len()));
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let unit = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    number.checked_mul(unit).map(|seconds| Some(Duration::from_secs(seconds))).ok_or_else(invalid)
+}
+
+/// Esito di `prune` e `gc`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Oggetti eliminati (o da eliminare, con `dry_run`), ordinati per hash
+    pub pruned: Vec<OObjectId>,
+    /// Oggetti non raggiungibili conservati perché più recenti del periodo di grazia
+    pub kept: usize,
+}
+
+/// Tutti gli oggetti raggiungibili da HEAD, dai ref, da MERGE_HEAD e dall'indice.
+///
+/// Un ref illeggibile o un oggetto mancante sono errori: meglio non eliminare
+/// niente che partire da un grafo incompleto.
+pub fn reachable_objects(store_path: &Path) -> Result<BTreeSet<OObjectId>, OgitError> {
+    let objects = LooseStore::open(store_path)?;
+
+    let mut queue = VecDeque::new();
+    for name in std::iter::once(HEAD.to_string()).chain(list_refs(store_path)?) {
+        queue.extend(resolve_ref(store_path, &name)?.map(|id| (id, OObjectType::Commit)));
+    }
+    queue.extend(read_merge_state(store_path)?.map(|state| (state.head, OObjectType::Commit)));
+    queue.extend(Index::load(store_path)?.entries.into_values().map(|entry| (entry.hash, OObjectType::Blob)));
+
+    let mut reachable = BTreeSet::new();
+    while let Some((id, kind)) = queue.pop_front() {
+        if reachable.contains(&id) {
+            continue;
+        }
+        // I blob non hanno collegamenti: basta che esistano
+        if kind == OObjectType::Blob {
+            if !objects.exists(&id)? {
+                return Err(OgitError::ObjectNotFound(id));
+            }
+        } else {
+            queue.extend(object_links(&id, &objects.read(&id)?)?);
+        }
+        reachable.insert(id);
+    }
+    Ok(reachable)
+}
+
+/// Elimina gli oggetti loose non raggiungibili più vecchi del periodo di grazia.
+///
+/// Algoritmo:
+/// 1. insieme degli oggetti raggiungibili (`reachable_objects`)
+/// 2. per ogni oggetto loose fuori dall'insieme: età = adesso - mtime del file
+/// 3. oltre il periodo di grazia lo elimina (con `dry_run` lo elenca soltanto),
+///    altrimenti lo conserva
+///
+/// Gli oggetti nei pack restano dove sono: li elimina `gc`.
+pub fn prune(store_path: &Path, options: &PruneOptions) -> Result<PruneReport, OgitError> {
+    let reachable = reachable_objects(store_path)?;
+    prune_loose(store_path, &reachable, options, SystemTime::now())
+}
+
+/// `prune` seguito da un repack dei soli oggetti raggiungibili.
+///
+/// Algoritmo:
+/// 1. insieme degli oggetti raggiungibili
+/// 2. oggetti non raggiungibili nei pack: se il pack è più vecchio del periodo
+///    di grazia sono da eliminare, altrimenti tornano file loose con la data
+///    del pack, così il periodo di grazia continua a valere
+/// 3. prune degli oggetti loose
+/// 4. repack dei soli oggetti raggiungibili: i vecchi pack spariscono e con
+///    loro gli oggetti da eliminare del punto 2
+///
+/// Con `dry_run` si calcola soltanto l'elenco, senza estrarre né impacchettare.
+pub fn gc(
+    store_path: &Path,
+    options: &PruneOptions,
+    pack_options: &PackOptions,
+) -> Result<(PruneReport, Option<RepackStats>), OgitError> {
+    let now = SystemTime::now();
+    let objects = LooseStore::open(store_path)?;
+
+    // 1. Raggiungibili
+    let reachable = reachable_objects(store_path)?;
+    let loose: BTreeSet<OObjectId> = list_objects(store_path)?.into_iter().collect();
+
+    // 2. Pack, dal più recente: un oggetto presente in più pack prende la data più favorevole
+    let mut packs = Vec::new();
+    for pack in pack_indexes(store_path)? {
+        packs.push((modified(pack.pack_path())?, pack));
+    }
+    packs.sort_by_key(|(mtime, _)| std::cmp::Reverse(*mtime));
+
+    let mut report = PruneReport::default();
+    let mut seen = BTreeSet::new();
+    for (mtime, pack) in &packs {
+        let expired = is_expired(*mtime, options.expire, now);
+        for id in pack.ids()? {
+            if reachable.contains(&id) || loose.contains(&id) || !seen.insert(id.clone()) {
+                continue;
+            }
+            if expired {
+                report.pruned.push(id);
+            } else if options.dry_run {
+                report.kept += 1;
+            } else {
+                // Il file estratto viene contato fra i conservati dal punto 3
+                let offset = pack.find(&id)?.ok_or_else(|| OgitError::ObjectNotFound(id.clone()))?;
+                let path = write_loose(store_path, &id, pack.read_at(offset)?.serialize(), objects.compression())?;
+                File::options()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|file| file.set_modified(*mtime))
+                    .map_err(OgitError::io(format!("Failed to set time of {id}")))?;
+            }
+        }
+    }
+
+    // 3. Loose
+    let loose_report = prune_loose(store_path, &reachable, options, now)?;
+    report.pruned.extend(loose_report.pruned);
+    report.pruned.sort();
+    report.kept += loose_report.kept;
+    if options.dry_run {
+        return Ok((report, None));
+    }
+
+    // 4. Repack
+    let stats = repack_filtered(store_path, pack_options, |id| reachable.contains(id))?;
+    Ok((report, Some(stats)))
+}
+
+fn prune_loose(
+    store_path: &Path,
+    reachable: &BTreeSet<OObjectId>,
+    options: &PruneOptions,
+    now: SystemTime,
+) -> Result<PruneReport, OgitError> {
+    let mut report = PruneReport::default();
+    for id in list_objects(store_path)? {
+        if reachable.contains(&id) {
+            continue;
+        }
+        let path = object_path(store_path, &id);
+        if !is_expired(modified(&path)?, options.expire, now) {
+            report.kept += 1;
+            continue;
+        }
+
+        if !options.dry_run {
+            fs::remove_file(&path).map_err(OgitError::io(format!("Failed to remove {id}")))?;
+            // La directory di sharding resta solo se contiene altro
+            if let Some(dir) = path.parent() {
+                let _ = fs::remove_dir(dir);
+            }
+        }
+        report.pruned.push(id);
+    }
+    Ok(report)
+}
+
+fn modified(path: &Path) -> Result<SystemTime, OgitError> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(OgitError::io(format!("Failed to read time of {}", path.display())))
+}
+
+fn is_expired(modified: SystemTime, expire: Option<Duration>, now: SystemTime) -> bool {
+    // Un file "dal futuro" (orologio spostato) ha età zero
+    expire.is_some_and(|expire| now.duration_since(modified).unwrap_or_default() >= expire)
+}
@@ -0,0 +1,221 @@
+//! Interrogazioni sul grafo dei commit (DAG dei `parent`).
+//!
+//! ```text
+//! A ← B ← C ← M        is_ancestor(B, M)   → true
+//!      ↖     ↙         merge_base(C, D)    → B
+//!        D             RevWalk da M        → M, D, C, B, A (per data)
+//! ```
+//!
+//! Le visite leggono i commit dallo store man mano che servono; gli id già
+//! visitati sono tenuti in un `HashSet` così ogni commit compare una sola volta
+//! anche quando è raggiungibile da più parent di un merge.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::error::OgitError;
+use crate::object::{Commit, OObjectId};
+use crate::object_store::ObjectStore;
+
+/// Ordine in cui `RevWalk` restituisce i commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkOrder {
+    /// Dal più recente per data del committer (come `git log`)
+    Date,
+    /// Nessun parent prima di tutti i suoi figli (come `git log --topo-order`);
+    /// fra i commit pronti vince comunque il più recente
+    Topological,
+}
+
+/// Commit in attesa in `RevWalk`: il massimo è il più recente per data del
+/// committer e, a parità, il primo inserito (`seq` più basso).
+struct Pending {
+    seq: u64,
+    id: OObjectId,
+    commit: Commit,
+}
+
+impl Pending {
+    fn key(&self) -> (i64, Reverse<u64>) {
+        (self.commit.committer.time, Reverse(self.seq))
+    }
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.seq == other.seq
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Visita dei commit raggiungibili da uno o più punti di partenza.
+///
+/// Ogni elemento è `(id, commit)`; un errore di lettura interrompe la visita.
+pub struct RevWalk<'a> {
+    objects: &'a dyn ObjectStore,
+    /// Commit pronti per essere restituiti, il più recente in cima
+    pending: BinaryHeap<Pending>,
+    /// Contatore di inserimento, per restituire in ordine i commit con la stessa data
+    next_seq: u64,
+    seen: HashSet<OObjectId>,
+    /// Solo per `Topological`: figli non ancora restituiti di ogni commit
+    children_left: Option<HashMap<OObjectId, usize>>,
+}
+
+impl<'a> RevWalk<'a> {
+    /// Prepara la visita a partire da `starts` (i duplicati sono ignorati).
+    ///
+    /// Con `WalkOrder::Topological` l'intero grafo raggiungibile viene letto
+    /// subito per contare i figli di ogni commit.
+    pub fn new(objects: &'a dyn ObjectStore, starts: &[OObjectId], order: WalkOrder) -> Result<Self, OgitError> {
+        let mut walk = Self {
+            objects,
+            pending: BinaryHeap::new(),
+            next_seq: 0,
+            seen: HashSet::new(),
+            children_left: None,
+        };
+
+        if order == WalkOrder::Topological {
+            let mut counts: HashMap<OObjectId, usize> = HashMap::new();
+            let mut visited = HashSet::new();
+            let mut stack = starts.to_vec();
+            while let Some(id) = stack.pop() {
+                if !visited.insert(id.clone()) {
+                    continue;
+                }
+                for parent in objects.read_commit(&id)?.parents {
+                    *counts.entry(parent.clone()).or_insert(0) += 1;
+                    stack.push(parent);
+                }
+            }
+            walk.children_left = Some(counts);
+        }
+
+        for id in starts {
+            let is_root = walk
+                .children_left
+                .as_ref()
+                .is_none_or(|counts| !counts.contains_key(id));
+            if is_root && walk.seen.insert(id.clone()) {
+                let commit = objects.read_commit(id)?;
+                walk.push_pending(id.clone(), commit);
+            }
+        }
+        Ok(walk)
+    }
+
+    fn push_pending(&mut self, id: OObjectId, commit: Commit) {
+        self.pending.push(Pending { seq: self.next_seq, id, commit });
+        self.next_seq += 1;
+    }
+
+    /// Accoda i parent di `commit` che sono diventati visitabili.
+    fn push_parents(&mut self, commit: &Commit) -> Result<(), OgitError> {
+        for parent in &commit.parents {
+            let ready = match self.children_left.as_mut() {
+                Some(counts) => {
+                    let left = counts
+                        .get_mut(parent)
+                        .ok_or_else(|| OgitError::corrupt("Commit graph changed during walk"))?;
+                    *left -= 1;
+                    *left == 0
+                }
+                None => true,
+            };
+            if ready && self.seen.insert(parent.clone()) {
+                let commit = self.objects.read_commit(parent)?;
+                self.push_pending(parent.clone(), commit);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for RevWalk<'_> {
+    type Item = Result<(OObjectId, Commit), OgitError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Il più recente fra quelli in attesa; a parità vince il primo inserito
+        let Pending { id, commit, .. } = self.pending.pop()?;
+        if let Err(e) = self.push_parents(&commit) {
+            self.pending.clear();
+            return Some(Err(e));
+        }
+        Some(Ok((id, commit)))
+    }
+}
+
+/// Tutti i commit raggiungibili da `starts`, partenze comprese.
+pub fn ancestors(objects: &dyn ObjectStore, starts: &[OObjectId]) -> Result<HashSet<OObjectId>, OgitError> {
+    let mut seen = HashSet::new();
+    let mut pending = starts.to_vec();
+    while let Some(id) = pending.pop() {
+        if seen.insert(id.clone()) {
+            pending.extend(objects.read_commit(&id)?.parents);
+        }
+    }
+    Ok(seen)
+}
+
+/// `true` se `ancestor` è raggiungibile da `descendant` (o coincide con esso).
+pub fn is_ancestor(objects: &dyn ObjectStore, ancestor: &OObjectId, descendant: &OObjectId) -> Result<bool, OgitError> {
+    let mut pending = vec![descendant.clone()];
+    let mut seen = HashSet::new();
+
+    while let Some(id) = pending.pop() {
+        if &id == ancestor {
+            return Ok(true);
+        }
+        if seen.insert(id.clone()) {
+            pending.extend(objects.read_commit(&id)?.parents);
+        }
+    }
+    Ok(false)
+}
+
+/// Tutti i migliori antenati comuni di `a` e `b` (come `git merge-base --all`).
+///
+/// Algoritmo:
+/// 1. antenati comuni = antenati di `a` ∩ antenati di `b`
+/// 2. scarta quelli raggiungibili da un altro antenato comune
+/// 3. ordina i rimanenti dal più recente (data del committer, poi hash)
+///
+/// Con più di un risultato (criss-cross merge) il primo è quello usato da
+/// `merge_base`.
+pub fn merge_bases(objects: &dyn ObjectStore, a: &OObjectId, b: &OObjectId) -> Result<Vec<OObjectId>, OgitError> {
+    let ancestors_a = ancestors(objects, std::slice::from_ref(a))?;
+    let ancestors_b = ancestors(objects, std::slice::from_ref(b))?;
+
+    let mut common = Vec::new();
+    for id in ancestors_a.intersection(&ancestors_b) {
+        common.push((id.clone(), objects.read_commit(id)?));
+    }
+
+    // Ogni antenato di un antenato comune è comune a sua volta
+    let parents: Vec<OObjectId> = common.iter().flat_map(|(_, c)| c.parents.iter().cloned()).collect();
+    let redundant = ancestors(objects, &parents)?;
+
+    let mut best: Vec<(OObjectId, Commit)> =
+        common.into_iter().filter(|(id, _)| !redundant.contains(id)).collect();
+    best.sort_by(|(id_x, x), (id_y, y)| y.committer.time.cmp(&x.committer.time).then_with(|| id_x.cmp(id_y)));
+    Ok(best.into_iter().map(|(id, _)| id).collect())
+}
+
+/// Il miglior antenato comune di `a` e `b`, `None` se le storie sono disgiunte.
+pub fn merge_base(objects: &dyn ObjectStore, a: &OObjectId, b: &OObjectId) -> Result<Option<OObjectId>, OgitError> {
+    Ok(merge_bases(objects, a, b)?.into_iter().next())
+}
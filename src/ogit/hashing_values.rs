@@ -1,6 +1,9 @@
+use sha1::Sha1;
 use sha2::{Digest, Sha256};
 use std::fmt::Write;
 
+use crate::error::OgitError;
+
 #[must_use]
 pub fn hash_bytes(bytes: &[u8]) -> [u8; 32] {
     // create a Sha256 object
@@ -13,6 +16,52 @@ pub fn hash_bytes(bytes: &[u8]) -> [u8; 32] {
     result.into()
 }
 
+/// SHA-1, usato solo dal formato oggetti compatibile con Git.
+#[must_use]
+pub fn hash_bytes_sha1(bytes: &[u8]) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+
+    hasher.update(bytes);
+
+    hasher.finalize().into()
+}
+
+/// Hash calcolato a blocchi, per dati che non stanno tutti in memoria.
+#[derive(Debug, Clone)]
+pub enum StreamHasher {
+    Sha256(Sha256),
+    /// Solo per il formato oggetti compatibile con Git
+    Sha1(Sha1),
+}
+
+impl StreamHasher {
+    #[must_use]
+    pub fn sha256() -> Self {
+        Self::Sha256(Sha256::new())
+    }
+
+    #[must_use]
+    pub fn sha1() -> Self {
+        Self::Sha1(Sha1::new())
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(bytes),
+            Self::Sha1(hasher) => hasher.update(bytes),
+        }
+    }
+
+    /// Byte dell'hash: 32 per SHA-256, 20 per SHA-1.
+    #[must_use]
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Sha256(hasher) => hasher.finalize().to_vec(),
+            Self::Sha1(hasher) => hasher.finalize().to_vec(),
+        }
+    }
+}
+
 #[must_use]
 pub fn bytes_to_hex(bytes: &[u8]) -> String {
     // Per ogni byte in bytes:
@@ -29,4 +78,21 @@ pub fn bytes_to_hex(bytes: &[u8]) -> String {
         write!(&mut acc, "{b:02x}").unwrap();
         acc
     })
-}
\ No newline at end of file
+}
+
+/// Inverso di `bytes_to_hex`: `"abcd12"` → `[0xab, 0xcd, 0x12]`.
+pub fn hex_to_bytes(hex: &str) -> Result<Vec<u8>, OgitError> {
+    // `from_str_radix` accetterebbe anche un segno iniziale (`+a`)
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(OgitError::InvalidInput(format!("Invalid hex string: {hex}")));
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| OgitError::InvalidInput(format!("Invalid hex string: {hex}")))
+        })
+        .collect()
+}
+
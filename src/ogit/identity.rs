@@ -0,0 +1,89 @@
+//! Identità di autore e committer per i nuovi commit.
+//!
+//! ```text
+//! nome   : OGIT_<RUOLO>_NAME  → user.name
+//! email  : OGIT_<RUOLO>_EMAIL → user.email → EMAIL
+//! data   : OGIT_<RUOLO>_DATE ("<timestamp> <tz>") → ora corrente, +0000
+//! ```
+//!
+//! `<RUOLO>` è `AUTHOR` o `COMMITTER`, come le variabili `GIT_*` di Git.
+
+use std::env;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+use crate::error::OgitError;
+use crate::object::{parse_date, Signature};
+
+pub const USER_NAME_KEY: &str = "user.name";
+pub const USER_EMAIL_KEY: &str = "user.email";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Author,
+    Committer,
+}
+
+impl Role {
+    #[must_use]
+    pub const fn env_prefix(&self) -> &'static str {
+        match self {
+            Self::Author => "OGIT_AUTHOR",
+            Self::Committer => "OGIT_COMMITTER",
+        }
+    }
+}
+
+/// Secondi dall'epoch Unix secondo l'orologio di sistema.
+#[must_use]
+pub fn now_seconds() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_secs()).unwrap_or(0))
+}
+
+/// Risolve l'identità a partire da config, variabili d'ambiente (`env`)
+/// e ora corrente (`now`); separata da `identity` per poterla testare.
+pub fn resolve_identity(
+    config: &Config,
+    role: Role,
+    env: impl Fn(&str) -> Option<String>,
+    now: i64,
+) -> Result<Signature, OgitError> {
+    let prefix = role.env_prefix();
+    let non_empty = |v: Option<String>| v.filter(|s| !s.trim().is_empty());
+
+    let name = non_empty(env(&format!("{prefix}_NAME")))
+        .or_else(|| non_empty(config.get(USER_NAME_KEY).map(str::to_string)));
+    let email = non_empty(env(&format!("{prefix}_EMAIL")))
+        .or_else(|| non_empty(config.get(USER_EMAIL_KEY).map(str::to_string)))
+        .or_else(|| non_empty(env("EMAIL")));
+
+    let (Some(name), Some(email)) = (name, email) else {
+        return Err(OgitError::InvalidInput(format!(
+            "{} identity unknown: set it with\n    ogit config {USER_NAME_KEY} \"Your Name\"\n    ogit config {USER_EMAIL_KEY} \"you@example.com\"",
+            match role {
+                Role::Author => "Author",
+                Role::Committer => "Committer",
+            }
+        )));
+    };
+
+    if name.contains(['<', '>', '\n']) || email.contains(['<', '>', '\n']) {
+        return Err(OgitError::InvalidInput(format!("Invalid identity: {name} <{email}>")));
+    }
+
+    let (time, tz_offset) = match env(&format!("{prefix}_DATE")) {
+        Some(date) => parse_date(date.trim())?,
+        None => (now, 0),
+    };
+
+    Ok(Signature::new(name.trim(), email.trim(), time, tz_offset))
+}
+
+/// Identità del ruolo indicato per il repository in `store_path`.
+pub fn identity(store_path: &Path, role: Role) -> Result<Signature, OgitError> {
+    let config = Config::load(store_path)?;
+    resolve_identity(&config, role, |key| env::var(key).ok(), now_seconds())
+}
@@ -0,0 +1,262 @@
+//! Regole di esclusione in stile `.gitignore`.
+//!
+//! ```text
+//! .ogit/info/exclude     → regole locali, non versionate
+//! .ogitignore            → regole della root
+//! src/.ogitignore        → regole valide solo sotto src/
+//! ```
+//!
+//! Sintassi supportata:
+//! - righe vuote e commenti (`#`) ignorati, `\#` e `\!` per i caratteri letterali
+//! - `*`, `?`, `[a-z]`, `[!a-z]` non attraversano `/`; `**` sì
+//! - `!pattern` re-include un path escluso da una regola precedente
+//! - `pattern/` vale solo per le directory
+//! - un pattern con `/` (non finale) è ancorato alla directory del file
+//!   che lo contiene, altrimenti confronta solo il nome a qualsiasi livello
+//!
+//! Vince l'ultima regola che corrisponde. Come in Git, i file dentro una
+//! directory esclusa non possono essere re-inclusi: la directory non
+//! viene proprio visitata.
+
+use std::fs::read_to_string;
+use std::path::Path;
+
+use crate::error::OgitError;
+
+pub const IGNORE_FILE: &str = ".ogitignore";
+const EXCLUDE_FILE: &str = "info/exclude";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IgnoreRule {
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+    /// Directory (relativa alla working directory) del file che contiene la regola
+    base: String,
+}
+
+/// Insieme ordinato di regole; si estende scendendo nelle sottodirectory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// Regole della root: `.ogit/info/exclude` seguito da `.ogitignore`.
+    pub fn load(store_path: &Path, work_dir: &Path) -> Result<Self, OgitError> {
+        let mut rules = Self::default();
+        rules.add_file(&store_path.join(EXCLUDE_FILE), "")?;
+        rules.add_file(&work_dir.join(IGNORE_FILE), "")?;
+        Ok(rules)
+    }
+
+    /// Aggiunge le regole di un testo in formato `.ogitignore`,
+    /// relative alla directory `base` ("" = root).
+    pub fn add_patterns(&mut self, content: &str, base: &str) {
+        self.rules.extend(content.lines().filter_map(|line| parse_rule(line, base)));
+    }
+
+    fn add_file(&mut self, path: &Path, base: &str) -> Result<(), OgitError> {
+        if !path.is_file() {
+            return Ok(());
+        }
+        let content = read_to_string(path)
+            .map_err(OgitError::io(format!("Failed to read {}", path.display())))?;
+        self.add_patterns(&content, base);
+        Ok(())
+    }
+
+    /// Regole valide dentro la directory `dir_path`, il cui path relativo
+    /// alla working directory è `rel`: queste più il suo `.ogitignore`.
+    pub fn child(&self, dir_path: &Path, rel: &str) -> Result<Self, OgitError> {
+        let mut rules = self.clone();
+        rules.add_file(&dir_path.join(IGNORE_FILE), rel)?;
+        Ok(rules)
+    }
+
+    /// Regole valide dentro `dir`, caricando i `.ogitignore` di ogni antenato.
+    pub fn descend(&self, work_dir: &Path, dir: &str) -> Result<Self, OgitError> {
+        let mut rules = self.clone();
+        let mut current = String::new();
+        for part in dir.split('/').filter(|p| !p.is_empty()) {
+            if !current.is_empty() {
+                current.push('/');
+            }
+            current.push_str(part);
+            rules = rules.child(&work_dir.join(&current), &current)?;
+        }
+        Ok(rules)
+    }
+
+    /// Come `is_ignored` per un path qualsiasi della working directory: vale
+    /// anche se è esclusa una directory che lo contiene, e applica i
+    /// `.ogitignore` di ogni antenato (le regole di `self` sono quelle della root).
+    pub fn is_excluded(&self, work_dir: &Path, path: &str, is_dir: bool) -> Result<bool, OgitError> {
+        let parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
+        let mut rules = self.clone();
+        let mut current = String::new();
+        for (i, part) in parts.iter().enumerate() {
+            if !current.is_empty() {
+                current.push('/');
+            }
+            current.push_str(part);
+            let last = i + 1 == parts.len();
+            if rules.is_ignored(&current, is_dir || !last) {
+                return Ok(true);
+            }
+            if !last {
+                rules = rules.child(&work_dir.join(&current), &current)?;
+            }
+        }
+        Ok(false)
+    }
+
+    /// `true` se `path` (relativo alla working directory, separatore `/`) è escluso.
+    #[must_use]
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(path, is_dir))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+impl IgnoreRule {
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+
+        let rel = if self.base.is_empty() {
+            path
+        } else {
+            match path.strip_prefix(&self.base).and_then(|r| r.strip_prefix('/')) {
+                Some(rel) => rel,
+                None => return false,
+            }
+        };
+
+        if self.anchored {
+            glob_match(&self.pattern, rel)
+        } else {
+            let name = rel.rsplit('/').next().unwrap_or(rel);
+            glob_match(&self.pattern, name)
+        }
+    }
+}
+
+fn parse_rule(line: &str, base: &str) -> Option<IgnoreRule> {
+    // Gli spazi finali non contano, a meno che siano preceduti da `\`
+    let mut line = line.trim_end_matches(['\r', '\n']);
+    while line.ends_with(' ') && !line.ends_with("\\ ") {
+        line = &line[..line.len() - 1];
+    }
+
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').filter(|r| r.starts_with(['#', '!'])).unwrap_or(line)),
+    };
+
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+
+    let anchored = line.contains('/');
+    let pattern = line.strip_prefix('/').unwrap_or(line);
+    if pattern.is_empty() {
+        return None;
+    }
+
+    Some(IgnoreRule {
+        pattern: pattern.to_string(),
+        negated,
+        dir_only,
+        anchored,
+        base: base.to_string(),
+    })
+}
+
+/// Confronto glob in stile Git: `*`, `?` e le classi non attraversano `/`,
+/// `**` corrisponde a qualsiasi sequenza (anche vuota) di directory.
+#[must_use]
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    match_from(&pattern, &text)
+}
+
+fn match_from(p: &[char], t: &[char]) -> bool {
+    match p.first() {
+        None => t.is_empty(),
+        Some('*') if p.get(1) == Some(&'*') => {
+            let rest = &p[2..];
+            match rest.first() {
+                // `**` finale: tutto il resto
+                None => true,
+                // `**/`: zero o più directory intere
+                Some('/') => {
+                    let rest = &rest[1..];
+                    (0..=t.len())
+                        .filter(|&i| i == 0 || t[i - 1] == '/')
+                        .any(|i| match_from(rest, &t[i..]))
+                }
+                // `**` dentro un nome: come `*` ma attraversa `/`
+                Some(_) => (0..=t.len()).any(|i| match_from(rest, &t[i..])),
+            }
+        }
+        Some('*') => {
+            let rest = &p[1..];
+            let limit = t.iter().position(|&c| c == '/').unwrap_or(t.len());
+            (0..=limit).any(|i| match_from(rest, &t[i..]))
+        }
+        Some('?') => t.first().is_some_and(|&c| c != '/') && match_from(&p[1..], &t[1..]),
+        Some('[') => match (t.first(), parse_class(&p[1..])) {
+            (Some(&c), Some((matched, len))) => {
+                c != '/' && matched(c) && match_from(&p[1 + len..], &t[1..])
+            }
+            // Classe non chiusa: `[` letterale
+            (Some(&c), None) => c == '[' && match_from(&p[1..], &t[1..]),
+            (None, _) => false,
+        },
+        Some('\\') if p.len() > 1 => t.first() == Some(&p[1]) && match_from(&p[2..], &t[1..]),
+        Some(&c) => t.first() == Some(&c) && match_from(&p[1..], &t[1..]),
+    }
+}
+
+/// Interpreta una classe `[...]` (senza la `[` iniziale). Restituisce il
+/// predicato e il numero di caratteri consumati, `]` compresa.
+fn parse_class(p: &[char]) -> Option<(impl Fn(char) -> bool, usize)> {
+    let mut i = 0;
+    let negated = matches!(p.first(), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let start = i;
+    while i < p.len() {
+        // `]` come primo carattere è letterale
+        if p[i] == ']' && i > start {
+            return Some((
+                move |c: char| ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != negated,
+                i + 1,
+            ));
+        }
+        let lo = p[i];
+        if p.get(i + 1) == Some(&'-') && p.get(i + 2).is_some_and(|&c| c != ']') {
+            ranges.push((lo, p[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((lo, lo));
+            i += 1;
+        }
+    }
+    None
+}
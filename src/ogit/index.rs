@@ -0,0 +1,486 @@
+//! Staging area: il file `.ogit/index`.
+//!
+//! Formato testuale, una riga per file, ordinate per path:
+//! ```text
+//! <mtime_ns> <size> <mode> <hash> <path>
+//! ```
+//! Il path è l'ultimo campo così può contenere spazi (come nel formato tree).
+//!
+//! `add`/`rm`/`reset` modificano l'indice, `write_tree_from_index`
+//! costruisce il tree che verrà committato.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, read_to_string};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::error::OgitError;
+use crate::lockfile::{write_locked, LockFile};
+use crate::ignore::IgnoreRules;
+use crate::merge::mark_resolved;
+use crate::object_store::{LooseStore, ObjectStore};
+use crate::object::{parse_stored_id, FileMode, OObjectId, OObjectType, TreeEntry};
+use crate::status::head_tree_modes;
+use crate::store::object_format;
+use crate::tree::{hash_worktree_file, store_worktree_file, worktree_mode};
+
+const INDEX_FILE: &str = "index";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub path: String,
+    pub hash: OObjectId,
+    pub mode: FileMode,
+    pub size: u64,
+    /// Ultima modifica in nanosecondi dalla Unix epoch (0 = sconosciuta)
+    pub mtime: u64,
+}
+
+impl IndexEntry {
+    pub fn to_line(&self) -> String {
+        format!("{} {} {} {} {}", self.mtime, self.size, self.mode.as_str(), self.hash, self.path)
+    }
+
+    /// `true` se size e mtime coincidono con quelli del file su disco:
+    /// in quel caso si può evitare di ricalcolare l'hash.
+    /// `metadata` deve venire da `symlink_metadata`.
+    pub fn matches_stat(&self, metadata: &fs::Metadata) -> bool {
+        self.mtime != 0
+            && self.size == metadata.len()
+            && self.mtime == mtime_nanos(metadata)
+            && self.mode == worktree_mode(metadata)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Index {
+    pub entries: BTreeMap<String, IndexEntry>,
+}
+
+impl Index {
+    /// Carica `.ogit/index`; un indice mancante equivale a un indice vuoto.
+    pub fn load(store_path: &Path) -> Result<Self, OgitError> {
+        let path = store_path.join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = read_to_string(&path)
+            .map_err(OgitError::io("Failed to read index"))?;
+        Self::deserialize(&content)
+    }
+
+    /// Come `load`, ma un indice mancante equivale al tree di HEAD, come per
+    /// `status` e `diff`: chi modifica l'indice e lo salva (o ne fa un commit)
+    /// non deve perdere i file tracciati solo perché `.ogit/index` non esiste.
+    pub fn load_or_head(store_path: &Path) -> Result<Self, OgitError> {
+        if Self::exists(store_path) {
+            return Self::load(store_path);
+        }
+
+        let mut index = Self::default();
+        for (path, (hash, mode)) in head_tree_modes(store_path)? {
+            // size e mtime a zero: il primo `status` ri-hasha il file
+            index.insert(IndexEntry { path, hash, mode, size: 0, mtime: 0 });
+        }
+        Ok(index)
+    }
+
+    pub fn save(&self, store_path: &Path) -> Result<(), OgitError> {
+        write_locked(&store_path.join(INDEX_FILE), self.serialize())
+    }
+
+    /// Prende `index.lock` per un ciclo load → modifica → `save_locked`:
+    /// finché il lock è tenuto un secondo processo fallisce con `Locked`
+    /// invece di leggere l'indice vecchio e sovrascrivere le modifiche.
+    pub fn lock(store_path: &Path) -> Result<LockFile, OgitError> {
+        LockFile::acquire(&store_path.join(INDEX_FILE))
+    }
+
+    /// Scrive l'indice nel lock preso con `Index::lock` e lo rilascia.
+    pub fn save_locked(&self, mut lock: LockFile) -> Result<(), OgitError> {
+        lock.write_all(self.serialize().as_bytes())?;
+        lock.commit()
+    }
+
+    #[must_use]
+    pub fn exists(store_path: &Path) -> bool {
+        store_path.join(INDEX_FILE).exists()
+    }
+
+    #[must_use]
+    pub fn serialize(&self) -> String {
+        self.entries
+            .values()
+            .map(|e| e.to_line() + "\n")
+            .collect()
+    }
+
+    pub fn deserialize(content: &str) -> Result<Self, OgitError> {
+        let mut entries = BTreeMap::new();
+
+        for line in content.lines() {
+            if line.is_empty() {
+                continue;
+            }
+
+            let parts: Vec<&str> = line.splitn(5, ' ').collect();
+            if parts.len() != 5 {
+                return Err(OgitError::corrupt(format!("Invalid index entry: {line}")));
+            }
+
+            let mtime = parts[0].parse().map_err(|_| OgitError::corrupt(format!("Invalid mtime: {line}")))?;
+            let size = parts[1].parse().map_err(|_| OgitError::corrupt(format!("Invalid size: {line}")))?;
+            let mode = FileMode::parse(parts[2])?;
+            let hash = parse_stored_id(parts[3])?;
+            let path = parts[4].to_string();
+
+            entries.insert(path.clone(), IndexEntry { path, hash, mode, size, mtime });
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Registra (o aggiorna) un path con hash e metadati.
+    pub fn insert(&mut self, entry: IndexEntry) {
+        self.entries.insert(entry.path.clone(), entry);
+    }
+
+    pub fn remove(&mut self, path: &str) -> Option<IndexEntry> {
+        self.entries.remove(path)
+    }
+
+    #[must_use]
+    pub fn get(&self, path: &str) -> Option<&IndexEntry> {
+        self.entries.get(path)
+    }
+
+    /// Mappa `path → hash`, confrontabile con `flatten_tree`/`hash_worktree`.
+    #[must_use]
+    pub fn to_map(&self) -> BTreeMap<String, OObjectId> {
+        self.entries
+            .iter()
+            .map(|(p, e)| (p.clone(), e.hash.clone()))
+            .collect()
+    }
+
+    /// Path nell'indice che stanno sotto `dir` ("" = tutti).
+    fn paths_under(&self, dir: &str) -> Vec<String> {
+        self.entries
+            .keys()
+            .filter(|p| dir.is_empty() || p.as_str() == dir || p.starts_with(&format!("{dir}/")))
+            .cloned()
+            .collect()
+    }
+}
+
+#[must_use]
+pub fn mtime_nanos(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| u64::try_from(d.as_nanos()).unwrap_or(0))
+}
+
+/// Normalizza un path relativo alla working directory:
+/// `./src//main.rs` → `src/main.rs`, `.` → `""`. Rifiuta `..`.
+pub fn normalize_path(path: &str) -> Result<String, OgitError> {
+    let mut parts = Vec::new();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return Err(OgitError::InvalidInput(format!("Path outside repository: {path}"))),
+            _ => parts.push(part),
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+/// `true` se il path è un file o un symlink (senza seguire il link).
+fn is_worktree_file(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| !m.is_dir())
+}
+
+/// Crea la entry per un file della working directory scrivendone il blob.
+pub fn stage_file(objects: &dyn ObjectStore, work_dir: &Path, path: &str) -> Result<IndexEntry, OgitError> {
+    let file_path = work_dir.join(path);
+    let metadata = fs::symlink_metadata(&file_path)
+        .map_err(OgitError::io(format!("Failed to stat {path}")))?;
+    let (mode, hash) = store_worktree_file(objects, &file_path)?;
+
+    Ok(IndexEntry {
+        path: path.to_string(),
+        hash,
+        mode,
+        size: metadata.len(),
+        mtime: mtime_nanos(&metadata),
+    })
+}
+
+/// `ogit add <path>...`: registra file e directory (ricorsivamente).
+/// I file tracciati ma spariti dal disco vengono rimossi dall'indice;
+/// durante un merge i path aggiunti contano come conflitti risolti.
+/// Un path non tracciato ed escluso da `.ogitignore` viene rifiutato
+/// (`OgitError::Rejected`, senza toccare l'indice) a meno di `force`.
+/// Restituisce i path effettivamente aggiornati.
+pub fn add_paths(
+    store_path: &Path,
+    work_dir: &Path,
+    paths: &[String],
+    force: bool,
+) -> Result<Vec<String>, OgitError> {
+    let lock = Index::lock(store_path)?;
+    let mut index = Index::load_or_head(store_path)?;
+    let objects = LooseStore::open(store_path)?;
+    let root_ignore = IgnoreRules::load(store_path, work_dir)?;
+    let mut normalized = Vec::new();
+    let mut ignored = Vec::new();
+    for raw in paths {
+        let path = normalize_path(raw)?;
+        let full = work_dir.join(&path);
+        let tracked = !index.paths_under(&path).is_empty();
+        if !force
+            && !tracked
+            && full.symlink_metadata().is_ok()
+            && root_ignore.is_excluded(work_dir, &path, full.is_dir())?
+        {
+            ignored.push(path.clone());
+        }
+        normalized.push((raw, path));
+    }
+    if !ignored.is_empty() {
+        return Err(OgitError::Rejected(format!(
+            "The following paths are ignored by one of your .ogitignore files:\n{}\nUse -f if you really want to add them.",
+            ignored.join("\n")
+        )));
+    }
+
+    let mut changed = Vec::new();
+    let mut resolved = Vec::new();
+
+    for (raw, path) in normalized {
+        resolved.push(path.clone());
+        let full = work_dir.join(&path);
+
+        if is_worktree_file(&full) {
+            let entry = stage_file(&objects, work_dir, &path)?;
+            if index.get(&path).map(|e| (&e.hash, e.mode)) != Some((&entry.hash, entry.mode)) {
+                changed.push(path);
+            }
+            index.insert(entry);
+        } else if full.is_dir() {
+            // I file esclusi non vengono aggiunti, ma quelli già tracciati sì;
+            // con `force` si aggiunge tutto
+            let ignore = if force {
+                IgnoreRules::default()
+            } else {
+                root_ignore.descend(work_dir, &path)?
+            };
+            let mut files = Vec::new();
+            collect_files(work_dir, &path, &ignore, &mut files)?;
+            let collected: BTreeSet<String> = files.iter().cloned().collect();
+            for tracked in index.paths_under(&path) {
+                if !collected.contains(&tracked) && is_worktree_file(&work_dir.join(&tracked)) {
+                    files.push(tracked);
+                }
+            }
+
+            for file in files {
+                let entry = stage_file(&objects, work_dir, &file)?;
+                if index.get(&file).map(|e| (&e.hash, e.mode)) != Some((&entry.hash, entry.mode)) {
+                    changed.push(file);
+                }
+                index.insert(entry);
+            }
+            // Cancellazioni sotto la directory
+            for tracked in index.paths_under(&path) {
+                if !is_worktree_file(&work_dir.join(&tracked)) {
+                    index.remove(&tracked);
+                    changed.push(tracked);
+                }
+            }
+        } else if index.remove(&path).is_some() {
+            changed.push(path);
+        } else {
+            return Err(OgitError::InvalidInput(format!("Pathspec did not match any files: {raw}")));
+        }
+    }
+
+    index.save_locked(lock)?;
+    mark_resolved(store_path, &resolved)?;
+    Ok(changed)
+}
+
+/// Elenca i file sotto `dir` (relativo a `work_dir`), ignorando `.ogit`
+/// e i path esclusi da `ignore` (le regole valide dentro `dir`).
+pub fn collect_files(
+    work_dir: &Path,
+    dir: &str,
+    ignore: &IgnoreRules,
+    files: &mut Vec<String>,
+) -> Result<(), OgitError> {
+    let read_dir = fs::read_dir(work_dir.join(dir))
+        .map_err(OgitError::io("Failed to read dir"))?;
+
+    for entry_result in read_dir {
+        let entry = entry_result.map_err(OgitError::io("Failed to read entry"))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if name == ".ogit" {
+            continue;
+        }
+
+        let rel = if dir.is_empty() { name } else { format!("{dir}/{name}") };
+        // `file_type` non segue i symlink: un link a una directory è un file
+        let file_type = entry.file_type().map_err(OgitError::io(format!("Failed to stat {rel}")))?;
+        if ignore.is_ignored(&rel, file_type.is_dir()) {
+            continue;
+        }
+        if file_type.is_dir() {
+            let child = ignore.child(&entry.path(), &rel)?;
+            collect_files(work_dir, &rel, &child, files)?;
+        } else if file_type.is_file() || file_type.is_symlink() {
+            files.push(rel);
+        }
+    }
+    Ok(())
+}
+
+/// `ogit rm [--cached] <path>...`: toglie i path dall'indice e,
+/// senza `cached`, anche dal disco. Rifiuta file con modifiche non registrate.
+pub fn remove_paths(
+    store_path: &Path,
+    work_dir: &Path,
+    paths: &[String],
+    cached: bool,
+) -> Result<Vec<String>, OgitError> {
+    let lock = Index::lock(store_path)?;
+    let mut index = Index::load_or_head(store_path)?;
+    let mut removed = Vec::new();
+    let mut resolved = Vec::new();
+    let format = object_format(store_path)?;
+
+    for raw in paths {
+        let path = normalize_path(raw)?;
+        resolved.push(path.clone());
+        let matched = index.paths_under(&path);
+        if matched.is_empty() {
+            return Err(OgitError::InvalidInput(format!("Pathspec did not match any tracked files: {raw}")));
+        }
+
+        for tracked in matched {
+            let file_path = work_dir.join(&tracked);
+
+            if !cached && is_worktree_file(&file_path) {
+                let (_, on_disk) = hash_worktree_file(&file_path, format)?;
+                if index.get(&tracked).map(|e| &e.hash) != Some(&on_disk) {
+                    return Err(OgitError::Rejected(format!(
+                        "{tracked} has local modifications (use --cached to keep it)"
+                    )));
+                }
+                fs::remove_file(&file_path)
+                    .map_err(OgitError::io(format!("Failed to remove {tracked}")))?;
+            }
+
+            index.remove(&tracked);
+            removed.push(tracked);
+        }
+    }
+
+    index.save_locked(lock)?;
+    mark_resolved(store_path, &resolved)?;
+    Ok(removed)
+}
+
+/// `ogit reset [<path>...]`: riporta le entry dell'indice allo stato di HEAD
+/// (tutte se `paths` è vuoto). La working directory non viene toccata.
+pub fn reset_paths(store_path: &Path, paths: &[String]) -> Result<Vec<String>, OgitError> {
+    let lock = Index::lock(store_path)?;
+    let mut index = Index::load_or_head(store_path)?;
+    let head_files = head_tree_modes(store_path)?;
+
+    let prefixes = if paths.is_empty() {
+        vec![String::new()]
+    } else {
+        paths.iter().map(|p| normalize_path(p)).collect::<Result<Vec<_>, _>>()?
+    };
+
+    let under = |p: &str| {
+        prefixes
+            .iter()
+            .any(|d| d.is_empty() || p == d || p.starts_with(&format!("{d}/")))
+    };
+
+    let mut changed = Vec::new();
+    let mut candidates: Vec<String> = index.entries.keys().filter(|p| under(p)).cloned().collect();
+    candidates.extend(head_files.keys().filter(|p| under(p)).cloned());
+    candidates.sort();
+    candidates.dedup();
+
+    for path in candidates {
+        let staged = index.get(&path).map(|e| e.hash.clone());
+        let committed = head_files.get(&path).cloned();
+        let staged_mode = index.get(&path).map(|e| e.mode);
+        if staged == committed.as_ref().map(|(h, _)| h.clone()) && staged_mode == committed.as_ref().map(|(_, m)| *m) {
+            continue;
+        }
+
+        match committed {
+            // mtime 0: la prossima status ricalcolerà l'hash del file
+            Some((hash, mode)) => index.insert(IndexEntry { path: path.clone(), hash, mode, size: 0, mtime: 0 }),
+            None => {
+                index.remove(&path);
+            }
+        }
+        changed.push(path);
+    }
+
+    index.save_locked(lock)?;
+    Ok(changed)
+}
+
+/// Costruisce (e salva) la gerarchia di tree a partire dall'indice.
+///
+/// ```text
+/// a.txt, src/lib.rs, src/bin/x.rs
+///   → tree { a.txt, src → tree { lib.rs, bin → tree { x.rs } } }
+/// ```
+pub fn write_tree_from_index(store_path: &Path, index: &Index) -> Result<OObjectId, OgitError> {
+    let entries: Vec<(&str, &IndexEntry)> = index
+        .entries
+        .values()
+        .map(|e| (e.path.as_str(), e))
+        .collect();
+    write_subtree(&LooseStore::open(store_path)?, &entries)
+}
+
+/// `entries` è ordinato per path: i file di una stessa sottodirectory
+/// sono quindi contigui e condividono il prefisso `<dir>/`.
+fn write_subtree(objects: &dyn ObjectStore, entries: &[(&str, &IndexEntry)]) -> Result<OObjectId, OgitError> {
+    let mut tree_entries = Vec::new();
+    let mut i = 0;
+
+    while i < entries.len() {
+        let (path, entry) = entries[i];
+
+        match path.split_once('/') {
+            None => {
+                tree_entries.push(TreeEntry::with_mode(entry.mode, entry.hash.clone(), path.to_string()));
+                i += 1;
+            }
+            Some((dir, _)) => {
+                let prefix = format!("{dir}/");
+                let mut children = Vec::new();
+                while i < entries.len() && entries[i].0.starts_with(&prefix) {
+                    children.push((&entries[i].0[prefix.len()..], entries[i].1));
+                    i += 1;
+                }
+                let sub_id = write_subtree(objects, &children)?;
+                tree_entries.push(TreeEntry::new(OObjectType::Tree, sub_id, dir.to_string()));
+            }
+        }
+    }
+
+    objects.write_tree(&tree_entries)
+}
@@ -1,34 +1,54 @@
 use std::{
     fs::{self, File},
-    io::{Error, Write},
+    io::Write,
     path::{Path},
 };
 
-pub fn init_repo() -> Result<(), Error> {
-    let root = Path::new(".ogit");
+use crate::error::OgitError;
+use crate::object::ObjectFormat;
+use crate::repository::STORE_DIR;
 
+pub fn init_repo() -> Result<(), OgitError> {
+    init_repo_with_format(ObjectFormat::Ogit)
+}
+
+/// Come `init_repo`, scegliendo il formato degli oggetti.
+/// Il formato è fissato alla creazione: cambiarlo dopo mescolerebbe hash diversi.
+pub fn init_repo_with_format(format: ObjectFormat) -> Result<(), OgitError> {
+    init_repo_at(Path::new(STORE_DIR), format)
+}
+
+/// Come `init_repo_with_format`, creando il repository in `root`
+/// invece che in `./.ogit`.
+pub fn init_repo_at(root: &Path, format: ObjectFormat) -> Result<(), OgitError> {
     if root.exists() {
         eprintln!("Cartella già presente.");
         return Ok(());
     }
 
-    fs::create_dir_all(root)?;
+    let create_dir = |path: &Path| {
+        fs::create_dir_all(path).map_err(OgitError::io(format!("Failed to create {}", path.display())))
+    };
+    create_dir(root)?;
 
     // objects
-    fs::create_dir_all(root.join("objects"))?;
+    create_dir(&root.join("objects"))?;
 
     // refs/heads
     let heads_path = root.join("refs/heads");
-    fs::create_dir_all(&heads_path)?;
+    create_dir(&heads_path)?;
 
     // HEAD file
     let head_file = root.join("HEAD");
     create_head(&head_file)?;
 
+    // config: i nuovi repository salvano gli oggetti compressi
+    create_config(&root.join("config"), format)?;
+
     Ok(())
 }
 
-fn create_head(path: &Path) -> Result<(), Error> {
+fn create_head(path: &Path) -> Result<(), OgitError> {
     let content = b"ref: refs/heads/master\n";
 
     if path.exists() {
@@ -36,7 +56,24 @@ fn create_head(path: &Path) -> Result<(), Error> {
         return Ok(());
     }
 
-    let mut f = File::create(path)?;
-    f.write_all(content)?;
-    Ok(())
+    File::create(path)
+        .and_then(|mut f| f.write_all(content))
+        .map_err(OgitError::io("Failed to write HEAD"))
+}
+
+fn create_config(path: &Path, format: ObjectFormat) -> Result<(), OgitError> {
+    // In formato Git la compressione è implicita; `core.compression` resta
+    // fuori dal file perché per git è un livello numerico, non un booleano
+    let content = match format {
+        ObjectFormat::Ogit => String::from("[core]\n\tcompression = true\n"),
+        ObjectFormat::Git => format!("[core]\n\tobjectformat = {}\n", format.as_str()),
+    };
+
+    if path.exists() {
+        return Ok(());
+    }
+
+    File::create(path)
+        .and_then(|mut f| f.write_all(content.as_bytes()))
+        .map_err(OgitError::io("Failed to write config"))
 }
@@ -0,0 +1,180 @@
+//! Scritture atomiche e lock file.
+//!
+//! Nessun file dello store viene sovrascritto sul posto: un crash a metà
+//! lascerebbe un oggetto troncato o un ref vuoto. Si scrive un file accanto,
+//! lo si porta su disco (`fsync`) e lo si rinomina sopra quello vero; `rename`
+//! nella stessa directory è atomico, quindi chi legge vede il contenuto
+//! vecchio o quello nuovo, mai uno a metà.
+//!
+//! - `write_atomic` / `TempFile`: file temporaneo con nome univoco, per i
+//!   file che non cambiano una volta scritti (oggetti, pack); `TempFile` si
+//!   scrive a blocchi, per i blob troppo grandi da tenere in memoria
+//! - `LockFile` / `write_locked`: il file accanto è `<nome>.lock`, creato in
+//!   modo esclusivo, per i file che più processi possono aggiornare (ref,
+//!   indice, config). Finché il lock esiste un secondo processo fallisce con
+//!   `OgitError::Locked` invece di sovrascrivere.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::OgitError;
+
+/// Suffisso dei lock file (`HEAD.lock`, `refs/heads/master.lock`).
+pub const LOCK_SUFFIX: &str = ".lock";
+
+/// Lock esclusivo su un file dello store.
+///
+/// Il contenuto nuovo si scrive nel lock file; `commit` lo rende definitivo,
+/// altrimenti il lock viene rimosso quando il valore esce di scope e il file
+/// originale resta intatto.
+#[derive(Debug)]
+pub struct LockFile {
+    target: PathBuf,
+    lock_path: PathBuf,
+    file: File,
+    done: bool,
+}
+
+impl LockFile {
+    /// Crea `<target>.lock`; errore `Locked` se esiste già.
+    pub fn acquire(target: &Path) -> Result<Self, OgitError> {
+        let mut name = target.file_name().unwrap_or_default().to_os_string();
+        name.push(LOCK_SUFFIX);
+        let lock_path = target.with_file_name(name);
+
+        match OpenOptions::new().write(true).create_new(true).open(&lock_path) {
+            Ok(file) => Ok(Self { target: target.to_path_buf(), lock_path, file, done: false }),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => Err(OgitError::Locked(lock_path)),
+            Err(source) => Err(OgitError::Io { context: format!("Failed to create {}", lock_path.display()), source }),
+        }
+    }
+
+    pub fn write_all(&mut self, content: &[u8]) -> Result<(), OgitError> {
+        self.file
+            .write_all(content)
+            .map_err(OgitError::io(format!("Failed to write {}", self.lock_path.display())))
+    }
+
+    /// Porta il contenuto su disco e lo rinomina sopra il file protetto.
+    pub fn commit(mut self) -> Result<(), OgitError> {
+        self.file
+            .sync_all()
+            .map_err(OgitError::io(format!("Failed to sync {}", self.lock_path.display())))?;
+        fs::rename(&self.lock_path, &self.target)
+            .map_err(OgitError::io(format!("Failed to rename {}", self.lock_path.display())))?;
+        // Da qui il lock non esiste più: il drop non deve toccare un eventuale
+        // lock nuovo di un altro processo
+        self.done = true;
+        sync_parent(&self.target)
+    }
+
+    /// Elimina il file protetto (se c'è) e poi rilascia il lock.
+    pub fn remove_target(self) -> Result<(), OgitError> {
+        match fs::remove_file(&self.target) {
+            Ok(()) => sync_parent(&self.target),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(source) => Err(OgitError::Io { context: format!("Failed to remove {}", self.target.display()), source }),
+        }
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = fs::remove_file(&self.lock_path);
+        }
+    }
+}
+
+/// Sostituisce `path` con `content` tenendo il lock per tutta la scrittura.
+pub fn write_locked(path: &Path, content: impl AsRef<[u8]>) -> Result<(), OgitError> {
+    let mut lock = LockFile::acquire(path)?;
+    lock.write_all(content.as_ref())?;
+    lock.commit()
+}
+
+/// Scrive `path` in modo atomico senza lock: file temporaneo univoco nella
+/// stessa directory, `fsync`, `rename`.
+///
+/// Adatto ai file il cui contenuto è determinato dal nome (oggetti, pack):
+/// se due processi scrivono lo stesso file, vince l'ultimo e il risultato è identico.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<(), OgitError> {
+    let mut temp = TempFile::create(path)?;
+    temp.write_all(content)
+        .map_err(OgitError::io(format!("Failed to write {}", path.display())))?;
+    temp.persist(path)
+}
+
+/// File temporaneo `<nome>.tmp-<pid>-<n>` accanto a un file da scrivere.
+///
+/// Si riempie con `Write`; `persist` lo porta su disco e lo rinomina sul file
+/// definitivo, altrimenti viene rimosso quando il valore esce di scope.
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+    file: File,
+    done: bool,
+}
+
+impl TempFile {
+    /// Crea il temporaneo nella directory di `target`, che deve esistere.
+    pub fn create(target: &Path) -> Result<Self, OgitError> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let mut name = target.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".tmp-{}-{}", process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+        let path = target.with_file_name(name);
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(OgitError::io(format!("Failed to create {}", path.display())))?;
+        Ok(Self { path, file, done: false })
+    }
+
+    /// `fsync` e `rename` sopra `target` (nella stessa directory del temporaneo).
+    pub fn persist(mut self, target: &Path) -> Result<(), OgitError> {
+        self.file
+            .sync_all()
+            .and_then(|()| fs::rename(&self.path, target))
+            .map_err(OgitError::io(format!("Failed to write {}", target.display())))?;
+        self.done = true;
+        sync_parent(target)
+    }
+}
+
+impl Write for TempFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Porta su disco la directory di `path`, così il `rename` sopravvive a un crash.
+fn sync_parent(path: &Path) -> Result<(), OgitError> {
+    // Su Windows una directory non si apre come file; NTFS registra il rename da sé
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        File::open(dir)
+            .and_then(|dir| dir.sync_all())
+            .map_err(OgitError::io(format!("Failed to sync {}", dir.display())))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
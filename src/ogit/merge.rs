@@ -0,0 +1,436 @@
+//! Merge a tre vie fra HEAD e un altro branch (o commit).
+//!
+//! ```text
+//!         base
+//!        /    \
+//!     HEAD    theirs        → merge commit con parent [HEAD, theirs]
+//!        \    /
+//!        merge
+//! ```
+//!
+//! Algoritmo:
+//! 1. trova la merge base (antenato comune più recente)
+//! 2. base == theirs → già aggiornato; base == HEAD → fast-forward
+//! 3. altrimenti merge ricorsivo dei tree, entry per entry:
+//!    - un solo lato cambiato rispetto alla base → vince quel lato
+//!    - entrambi cambiati → merge riga per riga del contenuto (diff3)
+//! 4. senza conflitti: commit di merge; con conflitti: marker nei file,
+//!    stato salvato in `.ogit/MERGE_HEAD` finché `ogit commit` non conclude
+//!
+//! Un path in conflitto si considera risolto quando viene ripassato a `ogit add`
+//! (o `ogit rm`).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, read_to_string};
+use std::path::Path;
+
+use crate::checkout::{resolve_checkout_target, update_worktree, CheckoutTarget};
+use crate::diff::{matching_lines, split_lines};
+use crate::error::OgitError;
+use crate::graph::merge_base;
+use crate::index::Index;
+use crate::lockfile::write_locked;
+use crate::object::{parse_stored_id, Commit, FileMode, OObject, OObjectId, OObjectType, Signature, TreeEntry};
+use crate::object_store::{LooseStore, ObjectStore};
+use crate::refs::{resolve_ref, update_ref_checked, HEAD};
+use crate::tree::flatten_tree_in;
+
+const MERGE_HEAD: &str = "MERGE_HEAD";
+const MERGE_MSG: &str = "MERGE_MSG";
+const MERGE_CONFLICTS: &str = "MERGE_CONFLICTS";
+
+/// Esito di `merge`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeOutcome {
+    /// `theirs` è già contenuto in HEAD
+    UpToDate,
+    /// HEAD è stato spostato in avanti su `theirs`, senza commit di merge
+    FastForward(OObjectId),
+    /// Creato il commit di merge
+    Merged(OObjectId),
+    /// Path con conflitti da risolvere prima di `ogit commit`
+    Conflicts(Vec<String>),
+}
+
+/// Merge in corso, salvato fra `ogit merge` e `ogit commit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeState {
+    /// Il secondo parent del futuro commit di merge
+    pub head: OObjectId,
+    pub message: String,
+    /// Path ancora da risolvere
+    pub conflicts: Vec<String>,
+}
+
+/// Risultato del merge di un file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentMerge {
+    pub data: Vec<u8>,
+    /// Numero di blocchi in conflitto (0 = merge pulito)
+    pub conflicts: usize,
+}
+
+/// Risultato del merge di due tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeMerge {
+    pub tree: OObjectId,
+    pub conflicts: Vec<String>,
+}
+
+/// Nomi mostrati nei marker di conflitto.
+struct Labels<'a> {
+    ours: &'a str,
+    theirs: &'a str,
+}
+
+/// Esegue `ogit merge <target>` su `work_dir`.
+pub fn merge(
+    store_path: &Path,
+    work_dir: &Path,
+    target: &str,
+    author: &Signature,
+    committer: &Signature,
+) -> Result<MergeOutcome, OgitError> {
+    if read_merge_state(store_path)?.is_some() {
+        return Err(OgitError::Rejected("You have not concluded your merge (MERGE_HEAD exists): commit first".into()));
+    }
+
+    // 1. Commit coinvolti
+    let objects = LooseStore::open(store_path)?;
+    let (resolved, theirs) = resolve_checkout_target(store_path, target)?;
+    let theirs_tree = objects.read_commit(&theirs)?.tree;
+    let Some(ours) = resolve_ref(store_path, HEAD)? else {
+        // Branch senza commit: il merge è un fast-forward da un tree vuoto
+        update_worktree(store_path, work_dir, None, &theirs_tree, "merge")?;
+        update_ref_checked(store_path, HEAD, &theirs, None)?;
+        return Ok(MergeOutcome::FastForward(theirs));
+    };
+    let ours_tree = objects.read_commit(&ours)?.tree;
+
+    // 2. Casi banali
+    let base = merge_base(&objects, &ours, &theirs)?;
+    if base.as_ref() == Some(&theirs) {
+        return Ok(MergeOutcome::UpToDate);
+    }
+    if base.as_ref() == Some(&ours) {
+        update_worktree(store_path, work_dir, Some(&ours_tree), &theirs_tree, "merge")?;
+        update_ref_checked(store_path, HEAD, &theirs, Some(&ours))?;
+        return Ok(MergeOutcome::FastForward(theirs));
+    }
+
+    // 3. L'indice deve coincidere con HEAD: il commit di merge lo sostituisce
+    let staged: BTreeMap<String, (OObjectId, FileMode)> = Index::load_or_head(store_path)?
+        .entries
+        .into_iter()
+        .map(|(path, entry)| (path, (entry.hash, entry.mode)))
+        .collect();
+    if staged != flatten_tree_in(&objects, &ours_tree)? {
+        return Err(OgitError::Rejected("Your index contains uncommitted changes: commit them before merging".into()));
+    }
+
+    let name = match &resolved {
+        CheckoutTarget::Branch(name) => name.clone(),
+        CheckoutTarget::Detached(id) => id.to_string(),
+    };
+    let message = match &resolved {
+        CheckoutTarget::Branch(name) => format!("Merge branch '{name}'"),
+        CheckoutTarget::Detached(id) => format!("Merge commit '{}'", id),
+    };
+
+    // 4. Merge dei tree e aggiornamento della working directory
+    let base_tree = match &base {
+        Some(id) => Some(objects.read_commit(id)?.tree),
+        None => None,
+    };
+    let merged = merge_trees(&objects, base_tree.as_ref(), &ours_tree, &theirs_tree, "HEAD", &name)?;
+    update_worktree(store_path, work_dir, Some(&ours_tree), &merged.tree, "merge")?;
+
+    if !merged.conflicts.is_empty() {
+        let state = MergeState { head: theirs, message, conflicts: merged.conflicts.clone() };
+        write_merge_state(store_path, &state)?;
+        return Ok(MergeOutcome::Conflicts(merged.conflicts));
+    }
+
+    // 5. Commit di merge con due parent
+    let commit = Commit {
+        tree: merged.tree,
+        parents: vec![ours, theirs],
+        author: author.clone(),
+        committer: committer.clone(),
+        message,
+    };
+    let id = objects.write_commit(&commit)?;
+    update_ref_checked(store_path, HEAD, &id, commit.parents.first())?;
+    Ok(MergeOutcome::Merged(id))
+}
+
+/// Merge a tre vie di due tree; i file in conflitto contengono i marker.
+pub fn merge_trees(
+    objects: &dyn ObjectStore,
+    base: Option<&OObjectId>,
+    ours: &OObjectId,
+    theirs: &OObjectId,
+    ours_label: &str,
+    theirs_label: &str,
+) -> Result<TreeMerge, OgitError> {
+    let labels = Labels { ours: ours_label, theirs: theirs_label };
+    let mut conflicts = Vec::new();
+    let entries = merge_entries(objects, base, Some(ours), Some(theirs), "", &labels, &mut conflicts)?;
+    Ok(TreeMerge { tree: objects.write_tree(&entries)?, conflicts })
+}
+
+fn tree_map(objects: &dyn ObjectStore, tree: Option<&OObjectId>) -> Result<BTreeMap<String, TreeEntry>, OgitError> {
+    Ok(match tree {
+        Some(id) => objects.read_tree(id)?
+            .into_iter()
+            .map(|e| (e.name.clone(), e))
+            .collect(),
+        None => BTreeMap::new(),
+    })
+}
+
+fn same_entry(a: Option<&TreeEntry>, b: Option<&TreeEntry>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.hash == b.hash && a.mode == b.mode,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn merge_entries(
+    objects: &dyn ObjectStore,
+    base: Option<&OObjectId>,
+    ours: Option<&OObjectId>,
+    theirs: Option<&OObjectId>,
+    prefix: &str,
+    labels: &Labels,
+    conflicts: &mut Vec<String>,
+) -> Result<Vec<TreeEntry>, OgitError> {
+    let base = tree_map(objects, base)?;
+    let ours = tree_map(objects, ours)?;
+    let theirs = tree_map(objects, theirs)?;
+
+    let names: BTreeSet<&String> = base.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+    let mut entries = Vec::new();
+
+    for name in names {
+        let (b, o, t) = (base.get(name), ours.get(name), theirs.get(name));
+        let path = format!("{prefix}{name}");
+
+        // Un solo lato (o nessuno) ha cambiato l'entry
+        if same_entry(o, t) || same_entry(b, t) {
+            entries.extend(o.cloned());
+            continue;
+        }
+        if same_entry(b, o) {
+            entries.extend(t.cloned());
+            continue;
+        }
+
+        match (o, t) {
+            // Directory modificata da entrambi: si scende
+            (Some(o), Some(t)) if o.kind == OObjectType::Tree && t.kind == OObjectType::Tree => {
+                let base_tree = b.filter(|e| e.kind == OObjectType::Tree).map(|e| &e.hash);
+                let sub = merge_entries(
+                    objects,
+                    base_tree,
+                    Some(&o.hash),
+                    Some(&t.hash),
+                    &format!("{path}/"),
+                    labels,
+                    conflicts,
+                )?;
+                if !sub.is_empty() {
+                    let hash = objects.write_tree(&sub)?;
+                    entries.push(TreeEntry::new(OObjectType::Tree, hash, name.clone()));
+                }
+            }
+            // File modificato da entrambi: merge del contenuto
+            (Some(o), Some(t)) if is_text_like(o) && is_text_like(t) => {
+                let base_blob = b.filter(|e| e.kind == OObjectType::Blob);
+                let base_data = match base_blob {
+                    Some(e) => objects.read(&e.hash)?.data,
+                    None => Vec::new(),
+                };
+                let ours_data = objects.read(&o.hash)?.data;
+                let theirs_data = objects.read(&t.hash)?.data;
+                let result = merge_content(&base_data, &ours_data, &theirs_data, labels.ours, labels.theirs);
+
+                let base_mode = base_blob.map(|e| e.mode);
+                let mode = if o.mode == t.mode || base_mode == Some(t.mode) {
+                    o.mode
+                } else if base_mode == Some(o.mode) {
+                    t.mode
+                } else {
+                    conflicts.push(path.clone());
+                    o.mode
+                };
+
+                if result.conflicts > 0 && conflicts.last() != Some(&path) {
+                    conflicts.push(path);
+                }
+                let hash = objects.write(&OObject::new_blob(result.data))?;
+                entries.push(TreeEntry::with_mode(mode, hash, name.clone()));
+            }
+            // Modifica/cancellazione, file/directory, symlink: si tiene la
+            // versione presente (la nostra se esistono entrambe) e si segnala
+            (o, t) => {
+                conflicts.push(path);
+                entries.extend(o.or(t).cloned());
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn is_text_like(entry: &TreeEntry) -> bool {
+    matches!(entry.mode, FileMode::Regular | FileMode::Executable)
+}
+
+fn push_lines(out: &mut Vec<u8>, lines: &[&[u8]]) {
+    for line in lines {
+        out.extend_from_slice(line);
+    }
+}
+
+/// Merge a tre vie riga per riga (diff3).
+///
+/// ```text
+/// base:   a b c        le righe allineate in tutti e tre i file sono stabili;
+/// ours:   a B c        fra due righe stabili: se un lato coincide con la base
+/// theirs: a b c d      vince l'altro, se coincidono fra loro si prende quello,
+/// merge:  a B c d      altrimenti conflitto con i marker <<<<<<< ======= >>>>>>>
+/// ```
+///
+/// Un contenuto binario (con byte `\0`) non si fonde: resta `ours` con un conflitto.
+#[must_use]
+pub fn merge_content(base: &[u8], ours: &[u8], theirs: &[u8], ours_label: &str, theirs_label: &str) -> ContentMerge {
+    if [base, ours, theirs].iter().any(|d| d.contains(&0)) {
+        return ContentMerge { data: ours.to_vec(), conflicts: 1 };
+    }
+
+    let (b, a, c) = (split_lines(base), split_lines(ours), split_lines(theirs));
+    let mut match_a = vec![None; b.len()];
+    for (i, j) in matching_lines(&b, &a) {
+        match_a[i] = Some(j);
+    }
+    let mut match_c = vec![None; b.len()];
+    for (i, j) in matching_lines(&b, &c) {
+        match_c[i] = Some(j);
+    }
+
+    let mut out = Vec::new();
+    let mut conflicts = 0;
+    let (mut ia, mut ib, mut ic) = (0, 0, 0);
+
+    loop {
+        // 1. Righe stabili
+        while ib < b.len() && match_a[ib] == Some(ia) && match_c[ib] == Some(ic) {
+            out.extend_from_slice(b[ib]);
+            ia += 1;
+            ib += 1;
+            ic += 1;
+        }
+
+        // 2. Prossima riga della base presente in entrambi i lati
+        let next = (ib..b.len()).find(|&k| match_a[k].is_some() && match_c[k].is_some());
+        let (ea, eb, ec) = match next {
+            Some(k) => (match_a[k].unwrap_or(a.len()), k, match_c[k].unwrap_or(c.len())),
+            None => (a.len(), b.len(), c.len()),
+        };
+
+        // 3. Blocco instabile
+        let (chunk_a, chunk_b, chunk_c) = (&a[ia..ea], &b[ib..eb], &c[ic..ec]);
+        if chunk_a == chunk_b {
+            push_lines(&mut out, chunk_c);
+        } else if chunk_c == chunk_b || chunk_a == chunk_c {
+            push_lines(&mut out, chunk_a);
+        } else {
+            conflicts += 1;
+            push_conflict(&mut out, chunk_a, chunk_c, ours_label, theirs_label);
+        }
+
+        (ia, ib, ic) = (ea, eb, ec);
+        if next.is_none() {
+            break;
+        }
+    }
+
+    ContentMerge { data: out, conflicts }
+}
+
+fn push_conflict(out: &mut Vec<u8>, ours: &[&[u8]], theirs: &[&[u8]], ours_label: &str, theirs_label: &str) {
+    let ensure_newline = |out: &mut Vec<u8>| {
+        if out.last().is_some_and(|&b| b != b'\n') {
+            out.push(b'\n');
+        }
+    };
+
+    ensure_newline(out);
+    out.extend_from_slice(format!("<<<<<<< {ours_label}\n").as_bytes());
+    push_lines(out, ours);
+    ensure_newline(out);
+    out.extend_from_slice(b"=======\n");
+    push_lines(out, theirs);
+    ensure_newline(out);
+    out.extend_from_slice(format!(">>>>>>> {theirs_label}\n").as_bytes());
+}
+
+/// Merge in corso, se esiste `.ogit/MERGE_HEAD`.
+pub fn read_merge_state(store_path: &Path) -> Result<Option<MergeState>, OgitError> {
+    let head_path = store_path.join(MERGE_HEAD);
+    if !head_path.exists() {
+        return Ok(None);
+    }
+
+    let read = |name: &str| -> Result<String, OgitError> {
+        let path = store_path.join(name);
+        if !path.exists() {
+            return Ok(String::new());
+        }
+        read_to_string(&path).map_err(OgitError::io(format!("Failed to read {name}")))
+    };
+
+    Ok(Some(MergeState {
+        head: parse_stored_id(read(MERGE_HEAD)?.trim())?,
+        message: read(MERGE_MSG)?.trim_end().to_string(),
+        conflicts: read(MERGE_CONFLICTS)?.lines().map(str::to_string).collect(),
+    }))
+}
+
+fn write_merge_state(store_path: &Path, state: &MergeState) -> Result<(), OgitError> {
+    let files = [
+        (MERGE_HEAD, format!("{}\n", state.head)),
+        (MERGE_MSG, format!("{}\n", state.message)),
+        (MERGE_CONFLICTS, state.conflicts.iter().map(|p| format!("{p}\n")).collect()),
+    ];
+    for (name, content) in files {
+        write_locked(&store_path.join(name), content)?;
+    }
+    Ok(())
+}
+
+/// Segna come risolti i conflitti sotto i path indicati ("" = tutti).
+pub fn mark_resolved(store_path: &Path, paths: &[String]) -> Result<(), OgitError> {
+    let Some(mut state) = read_merge_state(store_path)? else {
+        return Ok(());
+    };
+
+    state.conflicts.retain(|conflict| {
+        !paths.iter().any(|p| {
+            p.is_empty() || conflict == p || conflict.strip_prefix(p.as_str()).is_some_and(|r| r.starts_with('/'))
+        })
+    });
+    write_merge_state(store_path, &state)
+}
+
+/// Chiude il merge in corso (dopo il commit di merge).
+pub fn clear_merge_state(store_path: &Path) -> Result<(), OgitError> {
+    for name in [MERGE_HEAD, MERGE_MSG, MERGE_CONFLICTS] {
+        let path = store_path.join(name);
+        if path.exists() {
+            fs::remove_file(&path).map_err(OgitError::io(format!("Failed to remove {name}")))?;
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,25 @@
+pub mod branch;
+pub mod checkout;
+pub mod compression;
+pub mod config;
+pub mod delta;
+pub mod diff;
+pub mod error;
+pub mod fsck;
+pub mod gc;
+pub mod graph;
 pub mod hashing_values;
+pub mod identity;
+pub mod ignore;
+pub mod index;
 pub mod initialize_repository;
+pub mod lockfile;
+pub mod merge;
 pub mod object;
+pub mod object_store;
+pub mod pack;
+pub mod refs;
+pub mod repository;
+pub mod status;
 pub mod store;
 pub mod tree;
\ No newline at end of file
@@ -1,21 +1,170 @@
 //!`OObjectType`   — enum con varianti Blob, Tree, Commit
-//!`OObjectId`     — newtype su String (hex hash)
+//!`OObjectId`     — hash validato di 32 byte (SHA-256) o 20 byte (SHA-1)
 //!`OObject`       — struct con kind: `OObjectType`, data: Vec<u8>
+//!`ObjectFormat`  — formato nativo (SHA-256, testo) o compatibile con Git (SHA-1, binario)
+//!`ObjectHasher`  — hash di un oggetto calcolato a blocchi, per i blob grandi
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+use crate::error::OgitError;
+use crate::hashing_values::{bytes_to_hex, hash_bytes, hash_bytes_sha1, hex_to_bytes, StreamHasher};
+
+/// Formato degli oggetti di un repository, scelto con `ogit init --object-format`.
+///
+/// ```text
+///            hash     tree                              commit
+/// Ogit       SHA-256  "<type> <hash> <name>\n"          tree/parent/author/committer, riga vuota, messaggio
+/// Git        SHA-1    "<mode> <name>\0<20 byte sha1>"    layout di git (committer, riga vuota, messaggio)
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ObjectFormat {
+    #[default]
+    Ogit,
+    Git,
+}
+
+impl ObjectFormat {
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ogit => "ogit",
+            Self::Git => "git",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, OgitError> {
+        match name {
+            "ogit" | "sha256" => Ok(Self::Ogit),
+            "git" | "sha1" => Ok(Self::Git),
+            _ => Err(OgitError::InvalidInput(format!("Unknown object format: {name}"))),
+        }
+    }
+
+    /// Lunghezza in caratteri hex di un hash
+    #[must_use]
+    pub const fn hex_len(&self) -> usize {
+        match self {
+            Self::Ogit => 64,
+            Self::Git => 40,
+        }
+    }
+
+    /// Il formato si deduce dalla lunghezza dell'hash: 20 byte → SHA-1 (Git).
+    #[must_use]
+    pub fn from_id(id: &OObjectId) -> Self {
+        if id.as_bytes().len() == Self::Git.hex_len() / 2 {
+            Self::Git
+        } else {
+            Self::Ogit
+        }
+    }
+
+    /// Hash esadecimale dei byte serializzati di un oggetto.
+    #[must_use]
+    pub fn hash_hex(&self, bytes: &[u8]) -> String {
+        match self {
+            Self::Ogit => bytes_to_hex(&hash_bytes(bytes)),
+            Self::Git => bytes_to_hex(&hash_bytes_sha1(bytes)),
+        }
+    }
+
+    /// Id dei byte serializzati di un oggetto.
+    #[must_use]
+    pub fn hash_id(&self, bytes: &[u8]) -> OObjectId {
+        match self {
+            Self::Ogit => OObjectId::from_raw(&hash_bytes(bytes)),
+            Self::Git => OObjectId::from_raw(&hash_bytes_sha1(bytes)),
+        }
+    }
+
+    /// Id di un oggetto di `size` byte (header escluso) letti a blocchi da `reader`.
+    pub fn hash_reader(&self, kind: OObjectType, mut reader: impl Read, size: u64) -> Result<OObjectId, OgitError> {
+        let mut hasher = ObjectHasher::new(*self, kind, size);
+        io::copy(&mut reader, &mut hasher).map_err(OgitError::io("Failed to read object data"))?;
+        hasher.finish()
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TreeEntry {
     pub kind: OObjectType,    // Blob o Tree
     pub hash: OObjectId,      // hash dell'oggetto
     pub name: String,         // nome file/directory
+    pub mode: FileMode,       // file normale, eseguibile, symlink o directory
+}
+
+/// Modo di una entry, con i valori ottali usati da Git.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileMode {
+    Regular,
+    Executable,
+    /// Il blob contiene il path di destinazione del link
+    Symlink,
+    Directory,
+}
+
+impl FileMode {
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Regular => "100644",
+            Self::Executable => "100755",
+            Self::Symlink => "120000",
+            Self::Directory => "40000",
+        }
+    }
+
+    pub fn parse(mode: &str) -> Result<Self, OgitError> {
+        match mode {
+            "100644" => Ok(Self::Regular),
+            "100755" => Ok(Self::Executable),
+            "120000" => Ok(Self::Symlink),
+            "40000" | "040000" => Ok(Self::Directory),
+            _ => Err(OgitError::corrupt(format!("Unknown mode: {mode}"))),
+        }
+    }
+
+    /// Modo implicito quando non è scritto esplicitamente nel tree.
+    #[must_use]
+    pub const fn default_for(kind: OObjectType) -> Self {
+        match kind {
+            OObjectType::Tree => Self::Directory,
+            _ => Self::Regular,
+        }
+    }
+
+    /// Tipo di oggetto puntato da una entry con questo modo.
+    #[must_use]
+    pub const fn kind(&self) -> OObjectType {
+        match self {
+            Self::Directory => OObjectType::Tree,
+            _ => OObjectType::Blob,
+        }
+    }
 }
 
 impl TreeEntry {
     pub fn new(kind: OObjectType, hash: OObjectId, name: String) -> Self {
-        Self { kind, hash, name }
+        Self { kind, hash, name, mode: FileMode::default_for(kind) }
+    }
+
+    /// Entry con modo esplicito (il tipo si ricava dal modo).
+    pub fn with_mode(mode: FileMode, hash: OObjectId, name: String) -> Self {
+        Self { kind: mode.kind(), hash, name, mode }
     }
+
     pub fn to_line(&self) -> String {
         // formato: "<type> <hash> <name>"
-        format!("{} {} {}", self.kind.as_str(), self.hash.as_str(), self.name)
+        // con modo non standard (eseguibile, symlink): "<mode> <type> <hash> <name>"
+        // così i tree già esistenti mantengono lo stesso hash
+        if self.mode == FileMode::default_for(self.kind) {
+            format!("{} {} {}", self.kind.as_str(), self.hash, self.name)
+        } else {
+            format!("{} {} {} {}", self.mode.as_str(), self.kind.as_str(), self.hash, self.name)
+        }
     }
     pub fn serialize_tree(entries: &[TreeEntry]) -> Vec<u8> {
         // 1. Ordina entries per nome (alfabetico)
@@ -30,19 +179,95 @@ impl TreeEntry {
             .join("\n")
             .into_bytes()
     }
+    /// Serializza nel formato richiesto (testuale o binario Git).
+    pub fn serialize_tree_as(entries: &[TreeEntry], format: ObjectFormat) -> Result<Vec<u8>, OgitError> {
+        match format {
+            ObjectFormat::Ogit => Ok(Self::serialize_tree(entries)),
+            ObjectFormat::Git => Self::serialize_git_tree(entries),
+        }
+    }
+
+    pub fn deserialize_tree_as(bytes: &[u8], format: ObjectFormat) -> Result<Vec<TreeEntry>, OgitError> {
+        match format {
+            ObjectFormat::Ogit => Self::deserialize_tree(bytes),
+            ObjectFormat::Git => Self::deserialize_git_tree(bytes),
+        }
+    }
+
+    /// Formato binario di Git, entry concatenate senza separatori:
+    /// ```text
+    /// <mode> <name>\0<20 byte sha1><mode> <name>\0<20 byte sha1>...
+    /// ```
+    /// Git ordina le directory come se il nome finisse con `/`.
+    fn serialize_git_tree(entries: &[TreeEntry]) -> Result<Vec<u8>, OgitError> {
+        let sort_key = |e: &TreeEntry| {
+            let mut key = e.name.clone().into_bytes();
+            if e.kind == OObjectType::Tree {
+                key.push(b'/');
+            }
+            key
+        };
+
+        let mut sorted = entries.to_vec();
+        sorted.sort_by_key(sort_key);
+
+        let mut out = Vec::new();
+        for e in &sorted {
+            if ObjectFormat::from_id(&e.hash) != ObjectFormat::Git {
+                return Err(OgitError::InvalidInput(format!("Not a SHA-1 hash: {}", e.hash)));
+            }
+            out.extend_from_slice(format!("{} {}\0", e.mode.as_str(), e.name).as_bytes());
+            out.extend_from_slice(e.hash.as_bytes());
+        }
+        Ok(out)
+    }
+
+    fn deserialize_git_tree(bytes: &[u8]) -> Result<Vec<TreeEntry>, OgitError> {
+        let mut entries = Vec::new();
+        let mut rest = bytes;
+
+        while !rest.is_empty() {
+            // "<mode> <name>" fino al byte nullo, poi 20 byte di hash
+            let null_pos = rest
+                .iter()
+                .position(|&b| b == 0)
+                .ok_or_else(|| OgitError::corrupt("Missing null byte in tree entry"))?;
+            if rest.len() < null_pos + 21 {
+                return Err(OgitError::corrupt("Truncated tree entry"));
+            }
+
+            let header = std::str::from_utf8(&rest[..null_pos])
+                .map_err(|_| OgitError::corrupt("Invalid UTF-8 in tree"))?;
+            let (mode, name) = header
+                .split_once(' ')
+                .ok_or_else(|| OgitError::corrupt(format!("Invalid tree entry: {header}")))?;
+
+            let mode = FileMode::parse(mode)?;
+            let hash = OObjectId::from_raw(&rest[null_pos + 1..null_pos + 21]);
+
+            entries.push(TreeEntry::with_mode(mode, hash, name.to_string()));
+            rest = &rest[null_pos + 21..];
+        }
+
+        Ok(entries)
+    }
+
     /// **Algoritmo per parsare una singola linea:**
     ///        "blob a1b2c3... readme.txt"
     ///        │     │         │
     ///        │     │         └── name (tutto dopo secondo spazio)
     ///        │     └── hash (secondo token)
     ///        └── type (primo token) 
-    pub fn deserialize_tree(bytes: &[u8]) -> Result<Vec<TreeEntry>, String> {
+    ///
+    /// Se il primo token è numerico è il modo (`100755 blob a1b2c3... run.sh`)
+    /// e il resto della linea segue lo stesso schema.
+    pub fn deserialize_tree(bytes: &[u8]) -> Result<Vec<TreeEntry>, OgitError> {
         // 1. Converti bytes in stringa UTF-8
         // 2. Splitta per \n
         // 3. Per ogni linea, parsa in TreeEntry
         // 4. Raccogli in Vec
         let content = std::str::from_utf8(bytes)
-            .map_err(|_| "Invalid UTF-8 in tree")?;
+            .map_err(|_| OgitError::corrupt("Invalid UTF-8 in tree"))?;
         
         let mut entries = Vec::new();
         
@@ -51,21 +276,34 @@ impl TreeEntry {
                 continue;
             }
             
-            let parts: Vec<&str> = line.splitn(3, ' ').collect();
+            // Modo esplicito opzionale in testa
+            let (mode, rest) = match line.split_once(' ') {
+                Some((first, rest)) if first.bytes().all(|b| b.is_ascii_digit()) => {
+                    (Some(FileMode::parse(first)?), rest)
+                }
+                _ => (None, line),
+            };
+            
+            let parts: Vec<&str> = rest.splitn(3, ' ').collect();
             if parts.len() != 3 {
-                return Err(format!("Invalid tree entry: {}", line));
+                return Err(OgitError::corrupt(format!("Invalid tree entry: {}", line)));
             }
             
             let kind = match parts[0] {
                 "blob" => OObjectType::Blob,
                 "tree" => OObjectType::Tree,
-                _ => return Err(format!("Unknown type: {}", parts[0])),
+                _ => return Err(OgitError::corrupt(format!("Unknown type: {}", parts[0]))),
             };
             
-            let hash = OObjectId(parts[1].to_string());
+            let hash = parse_stored_id(parts[1])?;
             let name = parts[2].to_string();
+            let mode = mode.unwrap_or(FileMode::default_for(kind));
             
-            entries.push(TreeEntry { kind, hash, name });
+            if mode.kind() != kind {
+                return Err(OgitError::corrupt(format!("Mode {} does not match type {}", mode.as_str(), kind.as_str())));
+            }
+            
+            entries.push(TreeEntry { kind, hash, name, mode });
     }
     
     Ok(entries)
@@ -90,7 +328,7 @@ impl OObjectType {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 //<type> <size>\0<data>
 // example blob 5\0Hello
 pub struct OObject {
@@ -115,48 +353,28 @@ impl OObject {
     }
     #[must_use]
     pub fn serialize(&self) -> Vec<u8> {
-        let header = format!("{} {}\0", self.kind.as_str(), self.data.len());
-        let mut result = header.into_bytes();
+        let mut result = object_header(self.kind, self.data.len() as u64);
         result.extend_from_slice(&self.data);
         result
     }
 
-    pub fn deserialize(bytes: &[u8]) -> Result<Self, String> {
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, OgitError> {
         // 1. Trova posizione di \0
         let null_pos = bytes
             .iter()
             .position(|&b| b == 0)
-            .ok_or("Missing null byte")?;
+            .ok_or_else(|| OgitError::corrupt("Missing null byte"))?;
         
         // 2. Splitta header e data
         let header_bytes = &bytes[..null_pos];
         let data = &bytes[null_pos + 1..];
         
-        // 3. Parsa header come UTF-8
-        let header = std::str::from_utf8(header_bytes)
-            .map_err(|_| "Invalid UTF-8 in header")?;
-        
-        // 4. Splitta su spazio: "blob 5" → ["blob", "5"]
-        let parts: Vec<&str> = header.split(' ').collect();
-        if parts.len() != 2 {
-            return Err("Invalid header format".into());
-        }
-        
-        // 5. Parsa tipo e size
-        let kind = match parts[0] {
-            "blob" => OObjectType::Blob,
-            "tree" => OObjectType::Tree,
-            "commit" => OObjectType::Commit,
-            _ => return Err("Unknown object type".into()),
-        };
-        
-        let size: usize = parts[1]
-            .parse()
-            .map_err(|_| "Invalid size")?;
+        // 3-5. Tipo e size dall'header
+        let (kind, size) = parse_object_header(header_bytes)?;
         
         // 6. Valida size
-        if data.len() != size {
-            return Err("Size mismatch".into());
+        if data.len() as u64 != size {
+            return Err(OgitError::corrupt("Size mismatch"));
         }
         
         // 7. Costruisci OObject
@@ -167,74 +385,418 @@ impl OObject {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct OObjectId(pub String);
+/// Header di un oggetto serializzato: `<type> <size>\0`.
+#[must_use]
+pub fn object_header(kind: OObjectType, size: u64) -> Vec<u8> {
+    format!("{} {size}\0", kind.as_str()).into_bytes()
+}
+
+/// Interpreta un header senza il `\0` finale: `"blob 5"` → `(Blob, 5)`.
+pub fn parse_object_header(header_bytes: &[u8]) -> Result<(OObjectType, u64), OgitError> {
+    // 1. Parsa header come UTF-8
+    let header = std::str::from_utf8(header_bytes)
+        .map_err(|_| OgitError::corrupt("Invalid UTF-8 in header"))?;
+    
+    // 2. Splitta su spazio: "blob 5" → ["blob", "5"]
+    let parts: Vec<&str> = header.split(' ').collect();
+    if parts.len() != 2 {
+        return Err(OgitError::corrupt("Invalid header format"));
+    }
+    
+    // 3. Parsa tipo e size
+    let kind = match parts[0] {
+        "blob" => OObjectType::Blob,
+        "tree" => OObjectType::Tree,
+        "commit" => OObjectType::Commit,
+        _ => return Err(OgitError::corrupt("Unknown object type")),
+    };
+    
+    let size = parts[1]
+        .parse()
+        .map_err(|_| OgitError::corrupt("Invalid size"))?;
+    
+    Ok((kind, size))
+}
+
+/// Hash di un oggetto calcolato a blocchi, senza averne i dati in memoria:
+/// l'header `<type> <size>\0` entra alla creazione, i dati con `update` (o
+/// `Write`, quindi anche con `io::copy`). `finish` controlla che siano
+/// arrivati esattamente `size` byte.
+#[derive(Debug, Clone)]
+pub struct ObjectHasher {
+    hasher: StreamHasher,
+    size: u64,
+    received: u64,
+}
+
+impl ObjectHasher {
+    #[must_use]
+    pub fn new(format: ObjectFormat, kind: OObjectType, size: u64) -> Self {
+        let mut hasher = match format {
+            ObjectFormat::Ogit => StreamHasher::sha256(),
+            ObjectFormat::Git => StreamHasher::sha1(),
+        };
+        hasher.update(&object_header(kind, size));
+        Self { hasher, size, received: 0 }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.hasher.update(bytes);
+        self.received += bytes.len() as u64;
+    }
+
+    pub fn finish(self) -> Result<OObjectId, OgitError> {
+        if self.received != self.size {
+            return Err(OgitError::corrupt(format!(
+                "Size mismatch: expected {} bytes, got {}",
+                self.size, self.received
+            )));
+        }
+        Ok(OObjectId::from_raw(&self.hasher.finalize()))
+    }
+}
+
+impl Write for ObjectHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hash di un oggetto. Lo spazio è sempre di 32 byte; un id SHA-1 (formato
+/// Git) ne usa i primi 20. Si costruisce solo da byte o hex validi, quindi
+/// un `OObjectId` ha sempre una lunghezza ammessa.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct OObjectId {
+    bytes: [u8; 32],
+    len: u8,
+}
 
 impl OObjectId {
+    /// Lunghezza minima di un prefisso abbreviato, in caratteri hex.
+    pub const MIN_PREFIX_LEN: usize = 4;
+    /// Caratteri hex mostrati dalla forma breve (`short`).
+    pub const SHORT_LEN: usize = 7;
+
+    /// `raw` deve essere lungo 32 o 20 byte.
+    pub fn from_bytes(raw: &[u8]) -> Result<Self, OgitError> {
+        match raw.len() {
+            20 | 32 => Ok(Self::from_raw(raw)),
+            n => Err(OgitError::InvalidInput(format!("Invalid object id length: {n} bytes"))),
+        }
+    }
+
+    fn from_raw(raw: &[u8]) -> Self {
+        let mut bytes = [0u8; 32];
+        bytes[..raw.len()].copy_from_slice(raw);
+        Self { bytes, len: raw.len() as u8 }
+    }
+
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..usize::from(self.len)]
+    }
+
+    #[must_use]
+    pub fn to_hex(&self) -> String {
+        bytes_to_hex(self.as_bytes())
+    }
+
+    /// Forma abbreviata per l'output: i primi `SHORT_LEN` caratteri hex.
+    #[must_use]
+    pub fn short(&self) -> String {
+        let mut hex = self.to_hex();
+        hex.truncate(Self::SHORT_LEN);
+        hex
+    }
+
+    /// `true` se l'hex dell'id inizia con `prefix` (senza distinzione maiuscole).
+    #[must_use]
+    pub fn matches_prefix(&self, prefix: &str) -> bool {
+        self.to_hex().starts_with(&prefix.to_ascii_lowercase())
+    }
+}
+
+/// Hash letto da un oggetto o da un file dello store: se non è valido lo
+/// store è corrotto, non l'input dell'utente.
+pub fn parse_stored_id(hex: &str) -> Result<OObjectId, OgitError> {
+    hex.parse()
+        .map_err(|_| OgitError::corrupt(format!("Invalid object id: {hex}")))
+}
+
+/// `"<64 hex>"` o `"<40 hex>"`; maiuscole ammesse.
+impl FromStr for OObjectId {
+    type Err = OgitError;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        let invalid = || OgitError::InvalidInput(format!("Invalid object id: {hex}"));
+        if hex.len() != ObjectFormat::Ogit.hex_len() && hex.len() != ObjectFormat::Git.hex_len() {
+            return Err(invalid());
+        }
+        Self::from_bytes(&hex_to_bytes(hex).map_err(|_| invalid())?)
+    }
+}
+
+impl fmt::Display for OObjectId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl fmt::Debug for OObjectId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "OObjectId({self})")
+    }
+}
+
+/// Stesso ordine dell'hex: byte per byte.
+impl Ord for OObjectId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl PartialOrd for OObjectId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Identità di autore o committer: `Nome <email> <timestamp> <tz>`.
+///
+/// `time` è in secondi dall'epoch Unix, `tz_offset` in minuti rispetto a UTC
+/// (`+0100` → 60).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub name: String,
+    pub email: String,
+    pub time: i64,
+    pub tz_offset: i32,
+}
+
+impl Signature {
+    #[must_use]
+    pub fn new(name: &str, email: &str, time: i64, tz_offset: i32) -> Self {
+        Self { name: name.to_string(), email: email.to_string(), time, tz_offset }
+    }
+
+    /// Formato usato negli header del commit: `Mario <mario@example.com> 1700000000 +0100`.
+    #[must_use]
+    pub fn to_ident(&self) -> String {
+        format!("{} <{}> {} {}", self.name, self.email, self.time, format_tz(self.tz_offset))
+    }
+
+    /// Interpreta `Nome <email> <timestamp> <tz>`.
+    pub fn parse(ident: &str) -> Result<Self, OgitError> {
+        let invalid = || OgitError::InvalidInput(format!("Invalid identity: {ident}"));
+
+        let (person, date) = ident.rsplit_once('>').ok_or_else(invalid)?;
+        let (name, email) = person.split_once('<').ok_or_else(invalid)?;
+        let (time, tz_offset) = parse_date(date.trim()).map_err(|_| invalid())?;
+
+        Ok(Self::new(name.trim_end(), email, time, tz_offset))
+    }
+
+    /// Come `parse`, ma un'identità senza email e data (commit creati prima
+    /// dei metadati completi, es. `Default Author`) diventa `Nome <> 0 +0000`.
     #[must_use]
-    pub const fn as_str(&self) -> &str {
-        self.0.as_str()
+    pub fn parse_lenient(ident: &str) -> Self {
+        Self::parse(ident).unwrap_or_else(|_| Self::new(ident, "", 0, 0))
     }
+
+    /// Data leggibile nel fuso del commit: `Tue Nov 14 23:13:20 2023 +0100`.
+    #[must_use]
+    pub fn format_date(&self) -> String {
+        const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+        const MONTHS: [&str; 12] =
+            ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+        let local = self.time + i64::from(self.tz_offset) * 60;
+        let days = local.div_euclid(86_400);
+        let secs = local.rem_euclid(86_400);
+        let (year, month, day) = civil_from_days(days);
+        let weekday = (days + 4).rem_euclid(7); // 1970-01-01 era un giovedì
+
+        format!(
+            "{} {} {} {:02}:{:02}:{:02} {} {}",
+            DAYS[weekday as usize],
+            MONTHS[(month - 1) as usize],
+            day,
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60,
+            year,
+            format_tz(self.tz_offset)
+        )
+    }
+}
+
+/// `60` → `+0100`, `-330` → `-0530`.
+#[must_use]
+pub fn format_tz(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let abs = offset.unsigned_abs();
+    format!("{sign}{:02}{:02}", abs / 60, abs % 60)
+}
+
+/// Interpreta `<timestamp> <tz>` (es. `1700000000 +0100`, anche `@1700000000 +0100`).
+pub fn parse_date(date: &str) -> Result<(i64, i32), OgitError> {
+    let invalid = || OgitError::InvalidInput(format!("Invalid date: {date}"));
+
+    let (time, tz) = date.split_once(' ').ok_or_else(invalid)?;
+    let time = time.strip_prefix('@').unwrap_or(time).parse().map_err(|_| invalid())?;
+
+    let tz = tz.trim();
+    let (sign, digits) = match tz.split_at_checked(1) {
+        Some(("+", d)) => (1, d),
+        Some(("-", d)) => (-1, d),
+        _ => return Err(invalid()),
+    };
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let hours: i32 = digits[..2].parse().map_err(|_| invalid())?;
+    let minutes: i32 = digits[2..].parse().map_err(|_| invalid())?;
+
+    Ok((time, sign * (hours * 60 + minutes)))
+}
+
+/// Giorni dall'epoch → (anno, mese, giorno) nel calendario gregoriano
+/// (algoritmo `civil_from_days` di Howard Hinnant).
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
 }
 
 /// Formato payload
 /// ```text
 /// tree <tree_hash>
-/// parent <parent_hash>    ← opzionale
-/// author <name>
-/// message <text>
+/// parent <parent_hash>                       ← zero, uno o più (merge)
+/// author <nome> <email> <timestamp> <tz>
+/// committer <nome> <email> <timestamp> <tz>
+///
+/// <messaggio, anche su più righe>
 /// ```
-
+///
+/// I commit più vecchi avevano solo `author <nome>` e una riga
+/// `message <testo>`: vengono ancora letti, con committer = author.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Commit {
     pub tree: OObjectId,
-    pub parent: Option<OObjectId>,
-    pub author: String,
+    /// Vuoto per il primo commit, due o più per un merge
+    pub parents: Vec<OObjectId>,
+    pub author: Signature,
+    pub committer: Signature,
     pub message: String,
 }
 
 impl Commit {
-    pub fn serialize(&self) -> Vec<u8> {
-        let mut lines = Vec::new();
-        
-        lines.push(format!("tree {}", self.tree.as_str()));
-        
-        if let Some(ref parent) = self.parent {
-            lines.push(format!("parent {}", parent.as_str()));
+    /// Il primo parent (la linea principale seguita da `log`).
+    #[must_use]
+    pub fn first_parent(&self) -> Option<&OObjectId> {
+        self.parents.first()
+    }
+
+    #[must_use]
+    pub fn is_merge(&self) -> bool {
+        self.parents.len() > 1
+    }
+
+    pub fn serialize_as(&self, format: ObjectFormat) -> Vec<u8> {
+        match format {
+            ObjectFormat::Ogit => self.serialize(),
+            ObjectFormat::Git => self.serialize_git(),
         }
-        
-        lines.push(format!("author {}", self.author));
-        lines.push(format!("message {}", self.message));
-        
-        lines.join("\n").into_bytes()
     }
 
-    pub fn deserialize(bytes: &[u8]) -> Result<Self, String> {
+    pub fn deserialize_as(bytes: &[u8], format: ObjectFormat) -> Result<Self, OgitError> {
+        match format {
+            ObjectFormat::Ogit => Self::deserialize(bytes),
+            ObjectFormat::Git => Self::deserialize_git(bytes),
+        }
+    }
+
+    /// Header comuni ai due formati, senza la riga vuota finale.
+    fn headers(&self) -> String {
+        let mut out = format!("tree {}\n", self.tree);
+        for parent in &self.parents {
+            out.push_str(&format!("parent {parent}\n"));
+        }
+        out.push_str(&format!("author {}\n", self.author.to_ident()));
+        out.push_str(&format!("committer {}\n", self.committer.to_ident()));
+        out
+    }
+
+    /// Layout di Git: come quello nativo, ma il messaggio termina sempre con `\n`.
+    fn serialize_git(&self) -> Vec<u8> {
+        let mut out = format!("{}\n{}", self.headers(), self.message);
+        if !self.message.ends_with('\n') {
+            out.push('\n');
+        }
+        out.into_bytes()
+    }
+
+    fn deserialize_git(bytes: &[u8]) -> Result<Self, OgitError> {
+        let mut commit = Self::deserialize(bytes)?;
+        if commit.message.ends_with('\n') {
+            commit.message.pop();
+        }
+        Ok(commit)
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        format!("{}\n{}", self.headers(), self.message).into_bytes()
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, OgitError> {
         let content = std::str::from_utf8(bytes)
-            .map_err(|_| "Invalid UTF-8 in commit")?;
-        
+            .map_err(|_| OgitError::corrupt("Invalid UTF-8 in commit"))?;
+
+        // Header e messaggio sono separati dalla prima riga vuota;
+        // nel formato precedente il messaggio era l'header `message`
+        let (headers, mut message) = match content.split_once("\n\n") {
+            Some((headers, message)) => (headers, Some(message.to_string())),
+            None => (content, None),
+        };
+
         let mut tree: Option<OObjectId> = None;
-        let mut parent: Option<OObjectId> = None;
-        let mut author: Option<String> = None;
-        let mut message: Option<String> = None;
-        
-        for line in content.lines() {
+        let mut parents: Vec<OObjectId> = Vec::new();
+        let mut author: Option<Signature> = None;
+        let mut committer: Option<Signature> = None;
+
+        for line in headers.lines() {
             if let Some(hash) = line.strip_prefix("tree ") {
-                tree = Some(OObjectId(hash.to_string()));
+                tree = Some(parse_stored_id(hash)?);
             } else if let Some(hash) = line.strip_prefix("parent ") {
-                parent = Some(OObjectId(hash.to_string()));
-            } else if let Some(name) = line.strip_prefix("author ") {
-                author = Some(name.to_string());
+                parents.push(parse_stored_id(hash)?);
+            } else if let Some(ident) = line.strip_prefix("author ") {
+                author = Some(Signature::parse_lenient(ident));
+            } else if let Some(ident) = line.strip_prefix("committer ") {
+                committer = Some(Signature::parse_lenient(ident));
             } else if let Some(msg) = line.strip_prefix("message ") {
-                message = Some(msg.to_string());
+                message.get_or_insert_with(|| msg.to_string());
             }
         }
-        
+
+        let author = author.ok_or_else(|| OgitError::corrupt("Missing author"))?;
         Ok(Commit {
-            tree: tree.ok_or("Missing tree")?,
-            parent,
-            author: author.ok_or("Missing author")?,
-            message: message.ok_or("Missing message")?,
+            tree: tree.ok_or_else(|| OgitError::corrupt("Missing tree"))?,
+            parents,
+            committer: committer.unwrap_or_else(|| author.clone()),
+            author,
+            message: message.ok_or_else(|| OgitError::corrupt("Missing message"))?,
         })
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,254 @@
+//! Astrazione dello storage degli oggetti.
+//!
+//! `ObjectStore` è l'interfaccia minima (leggi, scrivi, esiste, elenca);
+//! sopra di essa i metodi forniti leggono e scrivono commit e tree.
+//!
+//! - `LooseStore`: lo store su disco `.ogit/objects` (file loose più i pack),
+//!   cioè le funzioni di `store` legate a un path
+//! - `MemoryStore`: oggetti in una mappa, per i test e per chi incorpora ogit
+//!   senza toccare il filesystem
+
+use std::cell::{Ref, RefCell};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::error::OgitError;
+use crate::object::{Commit, OObject, OObjectId, OObjectType, ObjectFormat, Signature, TreeEntry};
+use crate::pack::PackSet;
+use crate::store::{
+    list_objects, object_id_as, object_path, read_object_in, read_object_stream_in, store_settings, write_blob_stream_in,
+    write_object_in, ObjectReader,
+};
+
+/// Backend in cui vivono gli oggetti di un repository.
+pub trait ObjectStore {
+    /// Formato (algoritmo di hash) con cui lo store identifica gli oggetti.
+    fn format(&self) -> ObjectFormat;
+
+    /// Legge un oggetto; errore se non esiste.
+    fn read(&self, id: &OObjectId) -> Result<OObject, OgitError>;
+
+    /// Salva un oggetto (se non c'è già) e ne restituisce l'hash.
+    fn write(&self, obj: &OObject) -> Result<OObjectId, OgitError>;
+
+    fn exists(&self, id: &OObjectId) -> Result<bool, OgitError>;
+
+    /// Salva un blob di `size` byte letti da `reader`. L'implementazione
+    /// predefinita li raccoglie in memoria; `LooseStore` li copia a blocchi.
+    fn write_blob_stream(&self, reader: &mut dyn Read, size: u64) -> Result<OObjectId, OgitError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).map_err(OgitError::io("Failed to read blob data"))?;
+        if data.len() as u64 != size {
+            return Err(OgitError::corrupt(format!("Size mismatch: expected {size} bytes, got {}", data.len())));
+        }
+        self.write(&OObject::new_blob(data))
+    }
+
+    /// Hash di tutti gli oggetti, ordinati e senza duplicati.
+    fn ids(&self) -> Result<Vec<OObjectId>, OgitError>;
+
+    /// Risolve un hash completo o abbreviato (almeno `OObjectId::MIN_PREFIX_LEN`
+    /// caratteri hex) nell'unico oggetto che inizia così.
+    ///
+    /// Errori: `InvalidInput` per un prefisso non hex o troppo corto,
+    /// `ObjectNotFound` per un hash completo assente, `InvalidRef` per un
+    /// prefisso senza corrispondenze, `AmbiguousId` se ne ha più di una.
+    fn resolve_prefix(&self, prefix: &str) -> Result<OObjectId, OgitError> {
+        if let Ok(id) = prefix.parse::<OObjectId>() {
+            return if self.exists(&id)? { Ok(id) } else { Err(OgitError::ObjectNotFound(id)) };
+        }
+        if prefix.len() < OObjectId::MIN_PREFIX_LEN
+            || prefix.len() > self.format().hex_len()
+            || !prefix.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            return Err(OgitError::InvalidInput(format!("Not a valid object name: {prefix}")));
+        }
+
+        let mut candidates: Vec<OObjectId> = self.ids()?.into_iter().filter(|id| id.matches_prefix(prefix)).collect();
+        match candidates.len() {
+            0 => Err(OgitError::InvalidRef(format!("No object matches {prefix}"))),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(OgitError::AmbiguousId { prefix: prefix.to_string(), candidates }),
+        }
+    }
+
+    /// Legge un oggetto e lo interpreta come commit.
+    fn read_commit(&self, id: &OObjectId) -> Result<Commit, OgitError> {
+        let obj = self.read(id)?;
+        if obj.kind != OObjectType::Commit {
+            return Err(OgitError::WrongType { id: id.clone(), expected: OObjectType::Commit });
+        }
+        Commit::deserialize_as(&obj.data, ObjectFormat::from_id(id))
+    }
+
+    /// Serializza e salva un commit già costruito (autore e committer distinti).
+    fn write_commit(&self, commit: &Commit) -> Result<OObjectId, OgitError> {
+        self.write(&OObject::new_commit(commit.serialize_as(self.format())))
+    }
+
+    /// Crea e salva un commit con committer = autore.
+    fn create_commit(
+        &self,
+        tree: &OObjectId,
+        parents: &[OObjectId],
+        author: &Signature,
+        message: &str,
+    ) -> Result<OObjectId, OgitError> {
+        self.write_commit(&Commit {
+            tree: tree.clone(),
+            parents: parents.to_vec(),
+            author: author.clone(),
+            committer: author.clone(),
+            message: message.to_string(),
+        })
+    }
+
+    /// Legge un oggetto e lo interpreta come tree.
+    fn read_tree(&self, id: &OObjectId) -> Result<Vec<TreeEntry>, OgitError> {
+        let obj = self.read(id)?;
+        if obj.kind != OObjectType::Tree {
+            return Err(OgitError::WrongType { id: id.clone(), expected: OObjectType::Tree });
+        }
+        TreeEntry::deserialize_tree_as(&obj.data, ObjectFormat::from_id(id))
+    }
+
+    /// Serializza le entry nel formato dello store e salva il tree.
+    fn write_tree(&self, entries: &[TreeEntry]) -> Result<OObjectId, OgitError> {
+        let data = TreeEntry::serialize_tree_as(entries, self.format())?;
+        self.write(&OObject::new_tree(data))
+    }
+}
+
+/// Store su disco: oggetti loose in `<store>/objects/<aa>/<resto>` e pack.
+#[derive(Debug, Clone)]
+pub struct LooseStore {
+    store_path: PathBuf,
+    format: ObjectFormat,
+    compression: bool,
+    packs: RefCell<PackSet>,
+}
+
+impl LooseStore {
+    /// Apre lo store di `.ogit` leggendo, una volta sola, formato e
+    /// compressione dalla config e gli indici dei pack. Un comando apre lo
+    /// store all'inizio e lo passa a chi legge o scrive oggetti, invece di
+    /// rileggere la config e tutti i `.idx` per ognuno.
+    pub fn open(store_path: &Path) -> Result<Self, OgitError> {
+        let (format, compression) = store_settings(store_path)?;
+        let packs = PackSet::load(store_path)?;
+        Ok(Self { store_path: store_path.to_path_buf(), format, compression, packs: RefCell::new(packs) })
+    }
+
+    #[must_use]
+    pub fn store_path(&self) -> &Path {
+        &self.store_path
+    }
+
+    /// `true` se i nuovi oggetti loose vengono compressi.
+    #[must_use]
+    pub fn compression(&self) -> bool {
+        self.compression
+    }
+
+    /// Gli indici dei pack, riletti solo se nel frattempo `objects/pack` è cambiata.
+    pub fn packs(&self) -> Result<Ref<'_, PackSet>, OgitError> {
+        if !self.packs.borrow().is_current(&self.store_path) {
+            *self.packs.borrow_mut() = PackSet::load(&self.store_path)?;
+        }
+        Ok(self.packs.borrow())
+    }
+
+    /// Come `store::read_object_stream`, cercando nei pack già aperti.
+    pub fn read_stream(&self, id: &OObjectId) -> Result<ObjectReader, OgitError> {
+        read_object_stream_in(self, id)
+    }
+}
+
+impl ObjectStore for LooseStore {
+    fn format(&self) -> ObjectFormat {
+        self.format
+    }
+
+    fn read(&self, id: &OObjectId) -> Result<OObject, OgitError> {
+        read_object_in(self, id)
+    }
+
+    fn write(&self, obj: &OObject) -> Result<OObjectId, OgitError> {
+        write_object_in(self, obj)
+    }
+
+    fn write_blob_stream(&self, reader: &mut dyn Read, size: u64) -> Result<OObjectId, OgitError> {
+        write_blob_stream_in(self, reader, size)
+    }
+
+    fn exists(&self, id: &OObjectId) -> Result<bool, OgitError> {
+        if object_path(&self.store_path, id).is_file() {
+            return Ok(true);
+        }
+        self.packs()?.contains(id)
+    }
+
+    fn ids(&self) -> Result<Vec<OObjectId>, OgitError> {
+        let mut ids = list_objects(&self.store_path)?;
+        ids.extend(self.packs()?.ids()?);
+        ids.sort();
+        ids.dedup();
+        Ok(ids)
+    }
+}
+
+/// Store in memoria: nessun file, gli oggetti vivono finché vive lo store.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    format: ObjectFormat,
+    objects: RefCell<BTreeMap<OObjectId, OObject>>,
+}
+
+impl MemoryStore {
+    #[must_use]
+    pub fn new(format: ObjectFormat) -> Self {
+        Self { format, objects: RefCell::default() }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.objects.borrow().len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.objects.borrow().is_empty()
+    }
+}
+
+impl ObjectStore for MemoryStore {
+    fn format(&self) -> ObjectFormat {
+        self.format
+    }
+
+    fn read(&self, id: &OObjectId) -> Result<OObject, OgitError> {
+        self.objects
+            .borrow()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| OgitError::ObjectNotFound(id.clone()))
+    }
+
+    fn write(&self, obj: &OObject) -> Result<OObjectId, OgitError> {
+        let id = object_id_as(obj, self.format);
+        self.objects
+            .borrow_mut()
+            .entry(id.clone())
+            .or_insert_with(|| obj.clone());
+        Ok(id)
+    }
+
+    fn exists(&self, id: &OObjectId) -> Result<bool, OgitError> {
+        Ok(self.objects.borrow().contains_key(id))
+    }
+
+    fn ids(&self) -> Result<Vec<OObjectId>, OgitError> {
+        Ok(self.objects.borrow().keys().cloned().collect())
+    }
+}
@@ -0,0 +1,758 @@
+//! Packfile: molti oggetti in un unico file, con un indice ordinato per hash.
+//!
+//! ```text
+//! .ogit/objects/pack/pack-<checksum>.pack
+//!   "OPCK" | versione u32 | numero oggetti u32
+//!   per ogni oggetto:
+//!     intero: 1 u8 | lunghezza varint | zlib(<type> <size>\0<data>)
+//!     delta:  2 u8 | distanza dalla base varint | lunghezza varint | zlib(delta)
+//!   checksum di tutto quanto precede
+//!
+//! .ogit/objects/pack/pack-<checksum>.idx
+//!   "OIDX" | versione u32 | numero oggetti u32 | lunghezza hash u8
+//!   per ogni oggetto, ordinati per hash: hash (byte) | offset nel .pack u64
+//!   checksum del .pack
+//! ```
+//!
+//! Gli interi sono big-endian. Le entry dell'indice hanno lunghezza fissa,
+//! così la ricerca di un hash è binaria sulla tabella, letta una volta sola.
+//! Il `.idx` viene scritto dopo il `.pack`: un pack senza indice è ignorato.
+//!
+//! Un'entry delta (vedi `delta`) ricostruisce i dati del blob a partire da
+//! un'altra entry dello stesso pack, che la precede: la distanza è l'offset
+//! dell'entry meno quello della base, quindi sempre positiva. Il tipo è
+//! quello della base. Le catene di delta sono lunghe al massimo `pack.depth`.
+
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::compression::{compress, decoder, decompress};
+use crate::config::Config;
+use crate::delta::{apply_delta, create_delta};
+use crate::error::OgitError;
+use crate::lockfile::{write_atomic, TempFile};
+use crate::hashing_values::{bytes_to_hex, StreamHasher};
+use crate::object::{parse_object_header, OObject, OObjectId, OObjectType, ObjectFormat};
+use crate::object_store::{LooseStore, ObjectStore};
+use crate::store::{list_objects, object_path};
+
+/// Directory dei pack, relativa allo store.
+pub const PACK_DIR: &str = "objects/pack";
+
+const PACK_MAGIC: &[u8; 4] = b"OPCK";
+const INDEX_MAGIC: &[u8; 4] = b"OIDX";
+const VERSION: u32 = 1;
+/// Byte di intestazione dell'indice: magic, versione, numero oggetti, lunghezza hash
+const INDEX_HEADER_LEN: u64 = 13;
+
+/// Tipo di entry: oggetto intero (serializzato con header)
+const ENTRY_FULL: u8 = 1;
+/// Tipo di entry: delta rispetto a un'entry precedente dello stesso pack
+const ENTRY_DELTA: u8 = 2;
+
+/// Chiave di configurazione: quanti blob precedenti provare come base di un delta.
+pub const PACK_WINDOW_KEY: &str = "pack.window";
+/// Chiave di configurazione: lunghezza massima di una catena di delta.
+pub const PACK_DEPTH_KEY: &str = "pack.depth";
+/// Lunghezza massima dell'header `<tipo> <dimensione>` di un oggetto intero.
+const MAX_HEADER_LEN: usize = 32;
+/// Blob più piccoli non vengono deltificati: l'header costerebbe più del risparmio.
+const MIN_DELTA_SIZE: usize = 64;
+
+/// Parametri della ricerca dei delta in `write_pack`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackOptions {
+    /// Candidati base per ogni blob; 0 disabilita i delta
+    pub window: usize,
+    /// Lunghezza massima delle catene; 0 disabilita i delta
+    pub depth: usize,
+}
+
+impl Default for PackOptions {
+    /// Stessi valori predefiniti di Git.
+    fn default() -> Self {
+        Self { window: 10, depth: 50 }
+    }
+}
+
+impl PackOptions {
+    /// Legge `pack.window` e `pack.depth`, con i predefiniti per le chiavi assenti.
+    pub fn load(store_path: &Path) -> Result<Self, OgitError> {
+        let config = Config::load(store_path)?;
+        let number = |key: &str, default: usize| {
+            config.get(key).map_or(Ok(default), |v| {
+                v.parse().map_err(|_| OgitError::InvalidInput(format!("Invalid number for {key}: {v}")))
+            })
+        };
+        let defaults = Self::default();
+        Ok(Self {
+            window: number(PACK_WINDOW_KEY, defaults.window)?,
+            depth: number(PACK_DEPTH_KEY, defaults.depth)?,
+        })
+    }
+}
+
+/// Risultato di `repack`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepackStats {
+    /// Oggetti nel nuovo pack
+    pub objects: usize,
+    /// File loose rimossi perché ora nel pack
+    pub loose_removed: usize,
+    /// Pack precedenti sostituiti dal nuovo
+    pub packs_removed: usize,
+    /// Oggetti salvati come delta nel nuovo pack
+    pub deltas: usize,
+}
+
+/// Indice di un pack aperto, tenuto in memoria: le ricerche non rileggono
+/// il `.idx`; dal `.pack` si leggono solo le entry che servono.
+#[derive(Debug, Clone)]
+pub struct PackIndex {
+    index_path: PathBuf,
+    pack_path: PathBuf,
+    count: u64,
+    id_len: usize,
+    /// Le entry `hash | offset`, ordinate per hash
+    records: Vec<u8>,
+    /// Checksum del `.pack` registrato in coda al `.idx`
+    checksum: Vec<u8>,
+}
+
+impl PackIndex {
+    /// Apre `pack-<x>.idx` e lo carica con una sola lettura.
+    pub fn open(index_path: &Path) -> Result<Self, OgitError> {
+        let mut bytes = fs::read(index_path).map_err(OgitError::io("Failed to read pack index"))?;
+        let header = bytes
+            .get(..INDEX_HEADER_LEN as usize)
+            .ok_or_else(|| OgitError::corrupt(format!("Truncated pack index: {}", index_path.display())))?;
+
+        if &header[..4] != INDEX_MAGIC {
+            return Err(OgitError::corrupt(format!("Not a pack index: {}", index_path.display())));
+        }
+        let version = u32::from_be_bytes(header[4..8].try_into().unwrap_or_default());
+        if version != VERSION {
+            return Err(OgitError::corrupt(format!("Unsupported pack index version: {version}")));
+        }
+        let count = u64::from(u32::from_be_bytes(header[8..12].try_into().unwrap_or_default()));
+        let id_len = usize::from(header[12]);
+
+        // Intestazione, entry di lunghezza fissa, checksum: niente di più, niente di meno
+        let records_len = usize::try_from(count).unwrap_or(usize::MAX).saturating_mul(id_len + 8);
+        if bytes.len() != INDEX_HEADER_LEN as usize + records_len + id_len {
+            return Err(OgitError::corrupt(format!("Truncated pack index: {}", index_path.display())));
+        }
+        let checksum = bytes.split_off(bytes.len() - id_len);
+        let records = bytes.split_off(INDEX_HEADER_LEN as usize);
+
+        Ok(Self {
+            index_path: index_path.to_path_buf(),
+            pack_path: index_path.with_extension("pack"),
+            count,
+            id_len,
+            records,
+            checksum,
+        })
+    }
+
+    #[must_use]
+    pub fn pack_path(&self) -> &Path {
+        &self.pack_path
+    }
+
+    #[must_use]
+    pub fn index_path(&self) -> &Path {
+        &self.index_path
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> u64 {
+        self.count
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Hash e offset dell'entry `i`.
+    fn record(&self, i: usize) -> (&[u8], u64) {
+        let record = &self.records[i * (self.id_len + 8)..(i + 1) * (self.id_len + 8)];
+        let (id, offset) = record.split_at(self.id_len);
+        (id, u64::from_be_bytes(offset.try_into().unwrap_or_default()))
+    }
+
+    /// Offset di `id` nel `.pack`, con ricerca binaria sulle entry ordinate.
+    pub fn find(&self, id: &OObjectId) -> Result<Option<u64>, OgitError> {
+        let target = id.as_bytes();
+        if target.len() != self.id_len {
+            return Ok(None);
+        }
+
+        let (mut low, mut high) = (0, self.records.len() / (self.id_len + 8));
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let (key, offset) = self.record(mid);
+            match key.cmp(target) {
+                std::cmp::Ordering::Equal => return Ok(Some(offset)),
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+            }
+        }
+        Ok(None)
+    }
+
+    /// Tutti gli hash del pack, in ordine.
+    pub fn ids(&self) -> Result<Vec<OObjectId>, OgitError> {
+        (0..self.records.len() / (self.id_len + 8))
+            .map(|i| {
+                OObjectId::from_bytes(self.record(i).0)
+                    .map_err(|_| OgitError::corrupt("Corrupt pack index: invalid object id"))
+            })
+            .collect()
+    }
+
+    /// Legge l'oggetto che inizia a `offset` nel `.pack`, risolvendo i delta.
+    pub fn read_at(&self, offset: u64) -> Result<OObject, OgitError> {
+        let file = File::open(&self.pack_path).map_err(OgitError::io("Failed to open pack"))?;
+        let mut reader = BufReader::new(file);
+
+        // Risale la catena fino a un oggetto intero, poi applica i delta a ritroso
+        let mut deltas = Vec::new();
+        let mut offset = offset;
+        let base = loop {
+            match read_entry(&mut reader, offset)? {
+                Entry::Full(object) => break object,
+                Entry::Delta { base_offset, delta } => {
+                    deltas.push(delta);
+                    offset = base_offset;
+                }
+            }
+        };
+
+        let mut data = base.data;
+        for delta in deltas.iter().rev() {
+            data = apply_delta(&data, delta)?;
+        }
+        Ok(OObject { kind: base.kind, data })
+    }
+
+    /// Tipo e dimensione dell'oggetto a `offset`, senza ricostruirlo: di un
+    /// delta si legge solo la dimensione del risultato, il tipo è quello
+    /// dell'oggetto intero in fondo alla catena.
+    pub fn header_at(&self, offset: u64) -> Result<(OObjectType, u64), OgitError> {
+        let file = File::open(&self.pack_path).map_err(OgitError::io("Failed to open pack"))?;
+        let mut reader = BufReader::new(file);
+
+        let Some(mut base_offset) = read_base_offset(&mut reader, offset)? else {
+            return read_full_header(&mut reader);
+        };
+        let len = read_varint(&mut reader)?;
+        let mut delta = decoder((&mut reader).take(len));
+        read_varint(&mut delta)?;
+        let size = read_varint(&mut delta)?;
+
+        while let Some(next) = read_base_offset(&mut reader, base_offset)? {
+            base_offset = next;
+        }
+        let (kind, _) = read_full_header(&mut reader)?;
+        Ok((kind, size))
+    }
+
+    /// Ricalcola il checksum del `.pack` a blocchi e lo confronta con quello
+    /// in coda al `.pack` e con quello in coda al `.idx`.
+    pub fn verify_checksum(&self) -> Result<(), OgitError> {
+        let mut file = File::open(&self.pack_path).map_err(OgitError::io("Failed to open pack"))?;
+        let mut stored = vec![0u8; self.id_len];
+        let len = file
+            .seek(SeekFrom::End(0))
+            .map_err(OgitError::io("Failed to read pack"))?
+            .checked_sub(self.id_len as u64)
+            .ok_or_else(|| OgitError::corrupt(format!("Truncated pack file: {}", self.pack_path.display())))?;
+        file.seek(SeekFrom::Start(len))
+            .and_then(|_| file.read_exact(&mut stored))
+            .and_then(|()| file.rewind())
+            .map_err(OgitError::io("Failed to read pack"))?;
+
+        let mut hasher = checksum_hasher(self.id_len);
+        let mut reader = file.take(len);
+        let mut buf = [0u8; 64 * 1024];
+        loop {
+            let n = reader.read(&mut buf).map_err(OgitError::io("Failed to read pack"))?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        let actual = hasher.finalize();
+        if actual != stored {
+            return Err(OgitError::corrupt(format!(
+                "Pack checksum mismatch: expected {}, found {}",
+                bytes_to_hex(&stored),
+                bytes_to_hex(&actual)
+            )));
+        }
+        if self.checksum != stored {
+            return Err(OgitError::corrupt(format!(
+                "Pack index checksum {} does not match the pack",
+                bytes_to_hex(&self.checksum)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Numero di delta da applicare per ricostruire l'oggetto a `offset`
+    /// (0 per un oggetto intero).
+    pub fn delta_depth(&self, offset: u64) -> Result<usize, OgitError> {
+        let file = File::open(&self.pack_path).map_err(OgitError::io("Failed to open pack"))?;
+        let mut reader = BufReader::new(file);
+        let mut depth = 0;
+        let mut offset = offset;
+        while let Some(base_offset) = read_base_offset(&mut reader, offset)? {
+            depth += 1;
+            offset = base_offset;
+        }
+        Ok(depth)
+    }
+}
+
+enum Entry {
+    Full(OObject),
+    Delta { base_offset: u64, delta: Vec<u8> },
+}
+
+/// Legge tipo ed eventuale base dell'entry a `offset`, lasciando il reader
+/// all'inizio della lunghezza del payload.
+fn read_base_offset(reader: &mut BufReader<File>, offset: u64) -> Result<Option<u64>, OgitError> {
+    reader
+        .seek(SeekFrom::Start(offset))
+        .map_err(OgitError::io("Failed to read pack"))?;
+
+    let mut kind = [0u8; 1];
+    reader.read_exact(&mut kind).map_err(OgitError::io("Failed to read pack"))?;
+    match kind[0] {
+        ENTRY_FULL => Ok(None),
+        ENTRY_DELTA => {
+            let distance = read_varint(reader)?;
+            // La base precede sempre l'entry: niente cicli
+            if distance == 0 || distance > offset {
+                return Err(OgitError::corrupt(format!("Corrupt pack: invalid delta base at offset {offset}")));
+            }
+            Ok(Some(offset - distance))
+        }
+        other => Err(OgitError::corrupt(format!("Unknown pack entry type: {other}"))),
+    }
+}
+
+/// Tipo e dimensione dall'header di un oggetto intero, con il reader
+/// all'inizio della lunghezza del payload.
+fn read_full_header(reader: &mut BufReader<File>) -> Result<(OObjectType, u64), OgitError> {
+    let len = read_varint(reader)?;
+    let mut payload = decoder(reader.take(len));
+    let mut header = Vec::new();
+    let mut byte = [0u8];
+    loop {
+        payload
+            .read_exact(&mut byte)
+            .map_err(OgitError::corrupt_io("Failed to read pack entry header"))?;
+        if byte[0] == 0 {
+            break;
+        }
+        if header.len() == MAX_HEADER_LEN {
+            return Err(OgitError::corrupt("Invalid header format"));
+        }
+        header.push(byte[0]);
+    }
+    parse_object_header(&header)
+}
+
+fn read_entry(reader: &mut BufReader<File>, offset: u64) -> Result<Entry, OgitError> {
+    let base_offset = read_base_offset(reader, offset)?;
+
+    let len = read_varint(reader)?;
+    let mut payload = vec![0u8; usize::try_from(len).map_err(|_| OgitError::corrupt("Pack entry too large"))?];
+    reader
+        .read_exact(&mut payload)
+        .map_err(OgitError::io("Failed to read pack"))?;
+    let payload = decompress(&payload)?;
+
+    Ok(match base_offset {
+        None => Entry::Full(OObject::deserialize(&payload)?),
+        Some(base_offset) => Entry::Delta { base_offset, delta: payload },
+    })
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn read_varint(reader: &mut impl Read) -> Result<u64, OgitError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8; 1];
+        reader
+            .read_exact(&mut byte)
+            .map_err(OgitError::io("Failed to read pack"))?;
+        value |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(OgitError::corrupt("Corrupt pack: varint too long"))
+}
+
+/// Indici di tutti i pack dello store, in ordine di nome.
+pub fn pack_indexes(store_path: &Path) -> Result<Vec<PackIndex>, OgitError> {
+    let pack_dir = store_path.join(PACK_DIR);
+    let Ok(entries) = fs::read_dir(&pack_dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "idx"))
+        .collect();
+    paths.sort();
+    paths.iter().map(|p| PackIndex::open(p)).collect()
+}
+
+/// Gli indici di tutti i pack di uno store, aperti una volta sola.
+///
+/// `LooseStore` ne tiene uno per tutte le ricerche e lo ricarica solo quando
+/// cambia l'mtime di `objects/pack`, cioè quando un pack viene aggiunto o tolto.
+#[derive(Debug, Clone, Default)]
+pub struct PackSet {
+    indexes: Vec<PackIndex>,
+    /// mtime di `objects/pack` letto prima di elencarla (`None` se non esiste)
+    dir_mtime: Option<SystemTime>,
+}
+
+impl PackSet {
+    /// Elenca `objects/pack` e carica ogni `.idx`.
+    pub fn load(store_path: &Path) -> Result<Self, OgitError> {
+        // L'mtime si legge prima: un pack aggiunto durante l'elenco fa ricaricare
+        let dir_mtime = pack_dir_mtime(store_path);
+        Ok(Self { indexes: pack_indexes(store_path)?, dir_mtime })
+    }
+
+    /// `true` se `objects/pack` non è cambiata dopo `load`.
+    #[must_use]
+    pub fn is_current(&self, store_path: &Path) -> bool {
+        pack_dir_mtime(store_path) == self.dir_mtime
+    }
+
+    #[must_use]
+    pub fn indexes(&self) -> &[PackIndex] {
+        &self.indexes
+    }
+
+    /// Il primo pack che contiene `id`, con l'offset dell'entry.
+    pub fn find(&self, id: &OObjectId) -> Result<Option<(&PackIndex, u64)>, OgitError> {
+        for index in &self.indexes {
+            if let Some(offset) = index.find(id)? {
+                return Ok(Some((index, offset)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Cerca `id` nei pack; `None` se nessun pack lo contiene.
+    pub fn read(&self, id: &OObjectId) -> Result<Option<OObject>, OgitError> {
+        match self.find(id)? {
+            Some((index, offset)) => index.read_at(offset).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Tipo e dimensione di `id` senza ricostruirlo; `None` se non è nei pack.
+    pub fn header(&self, id: &OObjectId) -> Result<Option<(OObjectType, u64)>, OgitError> {
+        match self.find(id)? {
+            Some((index, offset)) => index.header_at(offset).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// `true` se almeno un pack contiene `id`.
+    pub fn contains(&self, id: &OObjectId) -> Result<bool, OgitError> {
+        Ok(self.find(id)?.is_some())
+    }
+
+    /// Hash di tutti gli oggetti nei pack, ordinati e senza duplicati.
+    pub fn ids(&self) -> Result<Vec<OObjectId>, OgitError> {
+        let mut ids = Vec::new();
+        for index in &self.indexes {
+            ids.extend(index.ids()?);
+        }
+        ids.sort();
+        ids.dedup();
+        Ok(ids)
+    }
+}
+
+fn pack_dir_mtime(store_path: &Path) -> Option<SystemTime> {
+    fs::metadata(store_path.join(PACK_DIR)).and_then(|m| m.modified()).ok()
+}
+
+/// Cerca `id` nei pack; `None` se nessun pack lo contiene.
+/// Rilegge gli indici: per molte ricerche conviene un `PackSet` (o `LooseStore`).
+pub fn read_packed(store_path: &Path, id: &OObjectId) -> Result<Option<OObject>, OgitError> {
+    PackSet::load(store_path)?.read(id)
+}
+
+/// `true` se almeno un pack contiene `id`.
+pub fn has_packed(store_path: &Path, id: &OObjectId) -> Result<bool, OgitError> {
+    PackSet::load(store_path)?.contains(id)
+}
+
+/// Hash di tutti gli oggetti nei pack, ordinati e senza duplicati.
+pub fn list_packed(store_path: &Path) -> Result<Vec<OObjectId>, OgitError> {
+    PackSet::load(store_path)?.ids()
+}
+
+/// Scrive un nuovo pack con gli oggetti `ids` (letti dallo store) e
+/// restituisce il path del `.pack`.
+///
+/// Algoritmo:
+/// 1. ordina gli oggetti: prima quelli non blob, poi i blob dal più grande,
+///    così le versioni di uno stesso file finiscono vicine
+/// 2. serializza ogni oggetto in un file temporaneo; un blob diventa delta
+///    se uno degli ultimi `window` blob fa da base con un delta lungo meno
+///    di metà del blob e senza superare `depth`
+/// 3. checksum dell'intero contenuto, calcolato durante la scrittura → nome `pack-<checksum>`
+/// 4. rinomina il `.pack`, poi scrive `.idx` con `(hash, offset)` ordinati per hash
+pub fn write_pack(store_path: &Path, ids: &[OObjectId], options: &PackOptions) -> Result<PathBuf, OgitError> {
+    build_pack(store_path, ids, options).map(|(path, _)| path)
+}
+
+/// Base candidata per i delta: un blob già scritto nel pack.
+struct Candidate {
+    offset: u64,
+    depth: usize,
+    data: Vec<u8>,
+}
+
+/// Come `write_pack`, ma restituisce anche il numero di entry delta.
+fn build_pack(store_path: &Path, ids: &[OObjectId], options: &PackOptions) -> Result<(PathBuf, usize), OgitError> {
+    let objects = LooseStore::open(store_path)?;
+    let id_len = objects.format().hex_len() / 2;
+
+    // 1. Ordine di scrittura: bastano tipo e dimensione, senza leggere i dati
+    let mut order = Vec::with_capacity(ids.len());
+    for id in ids {
+        let (kind, size) = object_info(&objects, id)?;
+        let size = if kind == OObjectType::Blob { size } else { 0 };
+        order.push((kind == OObjectType::Blob, std::cmp::Reverse(size), id.clone()));
+    }
+    order.sort();
+    order.dedup();
+
+    // 2. Entry, scritte direttamente nel file temporaneo
+    let count = u32::try_from(order.len())
+        .map_err(|_| OgitError::InvalidInput("Too many objects for one pack".into()))?;
+    let pack_dir = store_path.join(PACK_DIR);
+    fs::create_dir_all(&pack_dir).map_err(OgitError::io("Failed to create dir"))?;
+    let mut pack = PackWriter {
+        file: TempFile::create(&pack_dir.join("pack"))?,
+        hasher: checksum_hasher(id_len),
+        offset: 0,
+    };
+    pack.write(PACK_MAGIC)?;
+    pack.write(&VERSION.to_be_bytes())?;
+    pack.write(&count.to_be_bytes())?;
+
+    let use_deltas = options.window > 0 && options.depth > 0;
+    let mut window: VecDeque<Candidate> = VecDeque::new();
+    let mut records = Vec::with_capacity(order.len());
+    let mut deltas = 0;
+    for (_, _, id) in order {
+        let raw = id.as_bytes().to_vec();
+        if raw.len() != id_len {
+            return Err(OgitError::InvalidInput(format!("Object {id} does not match the repository format")));
+        }
+        let offset = pack.offset;
+        records.push((raw, offset));
+
+        let object = objects.read(&id)?;
+        if !use_deltas || object.kind != OObjectType::Blob || object.data.len() < MIN_DELTA_SIZE {
+            pack.write_entry(ENTRY_FULL, None, &compress(&object.serialize())?)?;
+            continue;
+        }
+
+        // Il delta più corto tra le basi della finestra, purché conveniente
+        let mut best: Option<(&Candidate, Vec<u8>)> = None;
+        for candidate in window.iter().rev().filter(|c| c.depth < options.depth) {
+            let limit = best.as_ref().map_or(object.data.len() / 2, |(_, d)| d.len());
+            if let Some(delta) = create_delta(&candidate.data, &object.data)
+                && delta.len() < limit
+            {
+                best = Some((candidate, delta));
+            }
+        }
+
+        let depth = match best {
+            Some((base, delta)) => {
+                pack.write_entry(ENTRY_DELTA, Some(offset - base.offset), &compress(&delta)?)?;
+                deltas += 1;
+                base.depth + 1
+            }
+            None => {
+                pack.write_entry(ENTRY_FULL, None, &compress(&object.serialize())?)?;
+                0
+            }
+        };
+        window.push_back(Candidate { offset, depth, data: object.data });
+        if window.len() > options.window {
+            window.pop_front();
+        }
+    }
+
+    // 3. Checksum, calcolato mentre si scriveva
+    let PackWriter { mut file, hasher, .. } = pack;
+    let checksum = hasher.finalize();
+    file.write_all(&checksum).map_err(OgitError::io("Failed to write pack"))?;
+
+    // 4. File
+    records.sort();
+    let mut index = Vec::with_capacity(INDEX_HEADER_LEN as usize + records.len() * (id_len + 8));
+    index.extend_from_slice(INDEX_MAGIC);
+    index.extend_from_slice(&VERSION.to_be_bytes());
+    index.extend_from_slice(&count.to_be_bytes());
+    index.push(id_len as u8);
+    for (raw, offset) in &records {
+        index.extend_from_slice(raw);
+        index.extend_from_slice(&offset.to_be_bytes());
+    }
+    index.extend_from_slice(&checksum);
+
+    let pack_path = pack_dir.join(format!("pack-{}.pack", bytes_to_hex(&checksum)));
+    // Prima il .pack, poi il .idx: un pack è visibile solo quando ha l'indice
+    file.persist(&pack_path)?;
+    write_atomic(&pack_path.with_extension("idx"), &index)?;
+    Ok((pack_path, deltas))
+}
+
+/// Tipo e dimensione di un oggetto: dall'header dell'entry se è in un pack,
+/// altrimenti dall'header del file loose.
+fn object_info(objects: &LooseStore, id: &OObjectId) -> Result<(OObjectType, u64), OgitError> {
+    if let Some(header) = objects.packs()?.header(id)? {
+        return Ok(header);
+    }
+    let reader = objects.read_stream(id)?;
+    Ok((reader.kind, reader.size))
+}
+
+/// Il `.pack` in scrittura: tiene l'offset della prossima entry e aggiorna
+/// il checksum con ogni byte scritto.
+struct PackWriter {
+    file: TempFile,
+    hasher: StreamHasher,
+    offset: u64,
+}
+
+impl PackWriter {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), OgitError> {
+        self.file
+            .write_all(bytes)
+            .map_err(OgitError::io("Failed to write pack"))?;
+        self.hasher.update(bytes);
+        self.offset += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn write_entry(&mut self, kind: u8, distance: Option<u64>, payload: &[u8]) -> Result<(), OgitError> {
+        let mut header = vec![kind];
+        if let Some(distance) = distance {
+            write_varint(&mut header, distance);
+        }
+        write_varint(&mut header, payload.len() as u64);
+        self.write(&header)?;
+        self.write(payload)
+    }
+}
+
+/// Hash del checksum di pack e indice: lo stesso degli oggetti che contengono.
+fn checksum_hasher(id_len: usize) -> StreamHasher {
+    if id_len == ObjectFormat::Git.hex_len() / 2 {
+        StreamHasher::sha1()
+    } else {
+        StreamHasher::sha256()
+    }
+}
+
+/// Riunisce oggetti loose e pack esistenti in un solo pack nuovo.
+///
+/// Algoritmo:
+/// 1. raccoglie gli hash loose e quelli già nei pack
+/// 2. scrive il nuovo pack (se c'è qualcosa da riunire), ricalcolando i delta
+/// 3. solo dopo, rimuove i pack precedenti e i file loose
+pub fn repack(store_path: &Path, options: &PackOptions) -> Result<RepackStats, OgitError> {
+    repack_filtered(store_path, options, |_| true)
+}
+
+/// Come `repack`, ma nel nuovo pack entrano solo gli oggetti per cui `keep`
+/// è vero: quelli loose esclusi restano loose, quelli esclusi che stavano
+/// nei vecchi pack vengono persi (è così che `gc` elimina gli oggetti
+/// non raggiungibili già impacchettati).
+pub fn repack_filtered(
+    store_path: &Path,
+    options: &PackOptions,
+    keep: impl Fn(&OObjectId) -> bool,
+) -> Result<RepackStats, OgitError> {
+    // 1. Oggetti
+    let loose: Vec<OObjectId> = list_objects(store_path)?.into_iter().filter(|id| keep(id)).collect();
+    let old_packs = PackSet::load(store_path)?;
+    let packed = old_packs.ids()?;
+    let dropped = packed.iter().any(|id| !keep(id));
+    if loose.is_empty() && old_packs.indexes().len() <= 1 && !dropped {
+        return Ok(RepackStats {
+            objects: old_packs.indexes().first().map_or(0, |p| p.len() as usize),
+            ..RepackStats::default()
+        });
+    }
+
+    let mut ids = loose.clone();
+    ids.extend(packed.into_iter().filter(|id| keep(id)));
+
+    // 2. Nuovo pack (nessuno se non resta niente da impacchettare)
+    let (new_pack, objects, deltas) = if ids.is_empty() {
+        (None, 0, 0)
+    } else {
+        let (path, deltas) = build_pack(store_path, &ids, options)?;
+        let objects = PackIndex::open(&path.with_extension("idx"))?.len() as usize;
+        (Some(path), objects, deltas)
+    };
+
+    // 3. Pulizia
+    let mut packs_removed = 0;
+    for old in old_packs.indexes() {
+        if new_pack.as_deref() == Some(old.pack_path()) {
+            continue;
+        }
+        fs::remove_file(old.index_path()).map_err(OgitError::io("Failed to remove pack index"))?;
+        fs::remove_file(old.pack_path()).map_err(OgitError::io("Failed to remove pack"))?;
+        packs_removed += 1;
+    }
+
+    for id in &loose {
+        let path = object_path(store_path, id);
+        fs::remove_file(&path).map_err(OgitError::io(format!("Failed to remove {id}")))?;
+        // La directory di sharding resta solo se contiene altro
+        if let Some(dir) = path.parent() {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+
+    Ok(RepackStats { objects, loose_removed: loose.len(), packs_removed, deltas })
+}
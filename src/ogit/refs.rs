@@ -0,0 +1,222 @@
+//! Gestione dei riferimenti (refs) sotto `.ogit/`.
+//!
+//! Un ref è un file di testo che contiene:
+//! - un hash di commit (`a1b2c3...`)  → ref diretto
+//! - `ref: refs/heads/<branch>`       → ref simbolico (tipicamente HEAD)
+//!
+//! ```text
+//! .ogit/HEAD                → "ref: refs/heads/master\n"
+//! .ogit/refs/heads/master   → "<commit_hash>\n"
+//! ```
+
+use std::fs::{self, create_dir_all, read_to_string};
+use std::path::{Path, PathBuf};
+
+use crate::error::OgitError;
+use crate::lockfile::{write_locked, LockFile, LOCK_SUFFIX};
+use crate::object::OObjectId;
+
+pub const HEAD: &str = "HEAD";
+pub const HEADS_PREFIX: &str = "refs/heads/";
+const SYMREF_PREFIX: &str = "ref: ";
+
+/// Profondità massima di ref simbolici annidati (evita cicli infiniti)
+const MAX_SYMREF_DEPTH: usize = 5;
+
+/// Contenuto di un file ref una volta parsato.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefValue {
+    /// `ref: refs/heads/master`
+    Symbolic(String),
+    /// hash di un commit
+    Direct(OObjectId),
+}
+
+fn ref_path(store_path: &Path, name: &str) -> PathBuf {
+    store_path.join(name)
+}
+
+/// Normalizza un nome di ref: `HEAD` e `refs/...` restano invariati,
+/// un nome corto (`master`) diventa `refs/heads/master`.
+#[must_use]
+pub fn full_ref_name(name: &str) -> String {
+    if name == HEAD || name.starts_with("refs/") {
+        name.to_string()
+    } else {
+        format!("{HEADS_PREFIX}{name}")
+    }
+}
+
+/// Legge un singolo file ref senza seguire i ref simbolici.
+/// Restituisce `None` se il file non esiste (es. branch non ancora nato).
+pub fn read_ref(store_path: &Path, name: &str) -> Result<Option<RefValue>, OgitError> {
+    let path = ref_path(store_path, name);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = read_to_string(&path)
+        .map_err(OgitError::io(format!("Failed to read {name}")))?;
+    let trimmed = content.trim();
+
+    if let Some(target) = trimmed.strip_prefix(SYMREF_PREFIX) {
+        return Ok(Some(RefValue::Symbolic(target.trim().to_string())));
+    }
+
+    if let Ok(id) = trimmed.parse::<OObjectId>() {
+        return Ok(Some(RefValue::Direct(id)));
+    }
+
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+
+    Err(OgitError::InvalidRef(format!("Invalid ref {name}: {trimmed}")))
+}
+
+/// Segue la catena di ref simbolici partendo da `name` e restituisce
+/// il nome del ref finale (quello che contiene, o conterrà, un hash).
+pub fn resolve_symbolic(store_path: &Path, name: &str) -> Result<String, OgitError> {
+    let mut current = full_ref_name(name);
+
+    for _ in 0..MAX_SYMREF_DEPTH {
+        match read_ref(store_path, &current)? {
+            Some(RefValue::Symbolic(target)) => current = target,
+            _ => return Ok(current),
+        }
+    }
+
+    Err(OgitError::InvalidRef(format!("Symbolic ref loop while resolving {name}")))
+}
+
+/// Risolve un ref (`HEAD`, `master`, `refs/heads/master`) fino a un hash di commit.
+///
+/// ```text
+/// HEAD → ref: refs/heads/master → <hash>
+/// ```
+///
+/// Restituisce `None` se il ref punta a un branch che non ha ancora commit.
+pub fn resolve_ref(store_path: &Path, name: &str) -> Result<Option<OObjectId>, OgitError> {
+    let target = resolve_symbolic(store_path, name)?;
+
+    match read_ref(store_path, &target)? {
+        Some(RefValue::Direct(id)) => Ok(Some(id)),
+        Some(RefValue::Symbolic(_)) => Err(OgitError::InvalidRef(format!("Symbolic ref loop while resolving {name}"))),
+        None => Ok(None),
+    }
+}
+
+/// Aggiorna un ref facendolo puntare a `id`.
+///
+/// Se `name` è simbolico (es. HEAD → refs/heads/master) viene aggiornato
+/// il ref finale, così HEAD resta attaccato al branch.
+pub fn update_ref(store_path: &Path, name: &str, id: &OObjectId) -> Result<(), OgitError> {
+    let target = resolve_symbolic(store_path, name)?;
+    write_ref(store_path, &target, &RefValue::Direct(id.clone()))
+}
+
+/// Come `update_ref`, ma solo se il ref vale ancora `old` (`None`: il ref non
+/// deve avere ancora un commit), come `git update-ref <ref> <new> <old>`.
+///
+/// Il lock viene preso prima di leggere il valore corrente: di due processi
+/// partiti dallo stesso `old` uno solo riesce, l'altro trova il lock
+/// (`Locked`) o un valore diverso (`Rejected`) invece di scartare il commit
+/// appena scritto dal primo.
+pub fn update_ref_checked(
+    store_path: &Path,
+    name: &str,
+    id: &OObjectId,
+    old: Option<&OObjectId>,
+) -> Result<(), OgitError> {
+    let target = resolve_symbolic(store_path, name)?;
+    let path = ref_path(store_path, &target);
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)
+            .map_err(OgitError::io(format!("Failed to create dir for {target}")))?;
+    }
+
+    let mut lock = LockFile::acquire(&path)?;
+    let current = match read_ref(store_path, &target)? {
+        Some(RefValue::Direct(current)) => Some(current),
+        Some(RefValue::Symbolic(_)) => {
+            return Err(OgitError::InvalidRef(format!("Symbolic ref loop while resolving {name}")));
+        }
+        None => None,
+    };
+    if current.as_ref() != old {
+        let show = |id: Option<&OObjectId>| id.map_or_else(|| "nothing".to_string(), ToString::to_string);
+        return Err(OgitError::Rejected(format!(
+            "Cannot update {target}: expected {}, found {}",
+            show(old),
+            show(current.as_ref())
+        )));
+    }
+    lock.write_all(format!("{id}\n").as_bytes())?;
+    lock.commit()
+}
+
+/// Scrive direttamente un file ref, senza seguire i simbolici.
+///
+/// La scrittura passa per `<ref>.lock`: se un altro processo sta aggiornando
+/// lo stesso ref l'operazione fallisce con `Locked` invece di sovrascriverlo.
+pub fn write_ref(store_path: &Path, name: &str, value: &RefValue) -> Result<(), OgitError> {
+    let path = ref_path(store_path, name);
+
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)
+            .map_err(OgitError::io(format!("Failed to create dir for {name}")))?;
+    }
+
+    let content = match value {
+        RefValue::Symbolic(target) => format!("{SYMREF_PREFIX}{target}\n"),
+        RefValue::Direct(id) => format!("{}\n", id),
+    };
+
+    write_locked(&path, content)
+}
+
+/// Elimina un file ref tenendone il lock.
+pub fn delete_ref(store_path: &Path, name: &str) -> Result<(), OgitError> {
+    LockFile::acquire(&ref_path(store_path, name))?.remove_target()
+}
+
+/// Nome corto del branch corrente (`master`), oppure `None` se HEAD è staccato.
+pub fn current_branch(store_path: &Path) -> Result<Option<String>, OgitError> {
+    match read_ref(store_path, HEAD)? {
+        Some(RefValue::Symbolic(target)) => Ok(target
+            .strip_prefix(HEADS_PREFIX)
+            .map(str::to_string)),
+        _ => Ok(None),
+    }
+}
+
+/// Nomi completi di tutti i ref sotto `refs/` (`refs/heads/master`...), in ordine.
+pub fn list_refs(store_path: &Path) -> Result<Vec<String>, OgitError> {
+    let mut names = Vec::new();
+    let refs_dir = store_path.join("refs");
+    if refs_dir.is_dir() {
+        collect_ref_names(&refs_dir, "refs/", &mut names)?;
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Visita ricorsiva di una directory di ref (i nomi possono contenere `/`,
+/// es. `feature/x`); i lock file in corso di scrittura sono esclusi.
+pub(crate) fn collect_ref_names(dir: &Path, prefix: &str, names: &mut Vec<String>) -> Result<(), OgitError> {
+    let read_dir = fs::read_dir(dir)
+        .map_err(OgitError::io(format!("Failed to read {}", dir.display())))?;
+
+    for entry_result in read_dir {
+        let entry = entry_result.map_err(OgitError::io("Failed to read entry"))?;
+        let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_ref_names(&path, &format!("{name}/"), names)?;
+        } else if !name.ends_with(LOCK_SUFFIX) {
+            names.push(name);
+        }
+    }
+    Ok(())
+}
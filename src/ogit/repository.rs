@@ -0,0 +1,142 @@
+//! Individuazione del repository e della sua working directory.
+//!
+//! Come Git, i comandi funzionano da qualunque sottodirectory: `discover`
+//! risale dalla directory corrente fino a trovare `.ogit`, e la directory che
+//! lo contiene è la radice della working directory. Le variabili d'ambiente
+//! `OGIT_DIR` e `OGIT_WORK_TREE` (gli equivalenti di `GIT_DIR` e
+//! `GIT_WORK_TREE`) scavalcano la ricerca.
+
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::OgitError;
+use crate::index::normalize_path;
+use crate::object_store::LooseStore;
+
+/// Nome della directory del repository dentro la working directory.
+pub const STORE_DIR: &str = ".ogit";
+/// Path del repository, al posto della ricerca di `.ogit`.
+pub const OGIT_DIR_ENV: &str = "OGIT_DIR";
+/// Radice della working directory, al posto di quella dedotta.
+pub const OGIT_WORK_TREE_ENV: &str = "OGIT_WORK_TREE";
+
+/// Un repository aperto: dove stanno gli oggetti e dove sta la working directory.
+/// Entrambi i path sono assoluti e canonici.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repository {
+    store_path: PathBuf,
+    work_tree: PathBuf,
+}
+
+impl Repository {
+    /// Apre il repository in `store_path` con working directory `work_tree`.
+    ///
+    /// Errore `NotARepository` se `store_path` non ha `objects` e `HEAD`.
+    pub fn open(store_path: &Path, work_tree: &Path) -> Result<Self, OgitError> {
+        if !is_repository(store_path) {
+            return Err(OgitError::NotARepository(store_path.to_path_buf()));
+        }
+        Ok(Self {
+            store_path: canonical(store_path)?,
+            work_tree: canonical(work_tree)?,
+        })
+    }
+
+    /// Cerca `.ogit` in `start` e poi in ogni directory superiore.
+    ///
+    /// Algoritmo:
+    /// 1. rende `start` assoluto e canonico (niente `..` né symlink)
+    /// 2. per ogni antenato, dal più vicino: se contiene un `.ogit` valido,
+    ///    quello è lo store e l'antenato la working directory
+    /// 3. arrivati alla radice del filesystem senza trovarlo → `NotARepository`
+    pub fn discover(start: &Path) -> Result<Self, OgitError> {
+        // 1.
+        let start = canonical(start)?;
+
+        // 2.
+        for dir in start.ancestors() {
+            let store_path = dir.join(STORE_DIR);
+            if is_repository(&store_path) {
+                return Ok(Self { store_path, work_tree: dir.to_path_buf() });
+            }
+        }
+
+        // 3.
+        Err(OgitError::NotARepository(start.join(STORE_DIR)))
+    }
+
+    /// Repository della directory corrente, tenendo conto di `OGIT_DIR` e
+    /// `OGIT_WORK_TREE` (path relativi rispetto alla directory corrente).
+    ///
+    /// Con `OGIT_DIR` e senza `OGIT_WORK_TREE` la working directory è la
+    /// directory corrente, come fa Git con `GIT_DIR`.
+    pub fn from_env() -> Result<Self, OgitError> {
+        let cwd = env::current_dir().map_err(OgitError::io("Failed to read current directory"))?;
+        let work_tree = env::var_os(OGIT_WORK_TREE_ENV).map(|path| cwd.join(path));
+
+        match env::var_os(OGIT_DIR_ENV) {
+            Some(store_path) => Self::open(&cwd.join(store_path), work_tree.as_deref().unwrap_or(&cwd)),
+            None => {
+                let found = Self::discover(&cwd)?;
+                match work_tree {
+                    Some(work_tree) => Self::open(&found.store_path, &work_tree),
+                    None => Ok(found),
+                }
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn store_path(&self) -> &Path {
+        &self.store_path
+    }
+
+    #[must_use]
+    pub fn work_tree(&self) -> &Path {
+        &self.work_tree
+    }
+
+    /// Store degli oggetti del repository.
+    pub fn objects(&self) -> Result<LooseStore, OgitError> {
+        LooseStore::open(&self.store_path)
+    }
+
+    /// Converte un path scritto dall'utente (relativo a `cwd`, o assoluto) nel
+    /// path relativo alla radice della working directory usato dall'indice.
+    ///
+    /// Da `<radice>/src`, `main.rs` diventa `src/main.rs` e `..` diventa `""`
+    /// (tutta la working directory). Errore se il path esce dalla working directory.
+    pub fn worktree_path(&self, cwd: &Path, path: &str) -> Result<String, OgitError> {
+        let outside = || OgitError::InvalidInput(format!("Path outside repository: {path}"));
+
+        // Normalizzazione lessicale: il path può non esistere (file già cancellati)
+        let mut absolute = PathBuf::new();
+        for component in canonical(cwd)?.join(path).components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if !absolute.pop() {
+                        return Err(outside());
+                    }
+                }
+                other => absolute.push(other),
+            }
+        }
+
+        let relative = absolute.strip_prefix(&self.work_tree).map_err(|_| outside())?;
+        let relative = relative
+            .to_str()
+            .ok_or_else(|| OgitError::InvalidInput(format!("Path is not valid UTF-8: {path}")))?;
+        normalize_path(relative)
+    }
+}
+
+/// Una directory è un repository se ha almeno `objects` e `HEAD`.
+fn is_repository(store_path: &Path) -> bool {
+    store_path.join("objects").is_dir() && store_path.join("HEAD").is_file()
+}
+
+fn canonical(path: &Path) -> Result<PathBuf, OgitError> {
+    fs::canonicalize(path).map_err(OgitError::io(format!("Failed to resolve {}", path.display())))
+}
@@ -0,0 +1,238 @@
+//! Status: confronto fra tree di HEAD, indice e working directory.
+//!
+//! ```text
+//! HEAD tree ──(staged)──▶ index ──(unstaged)──▶ working directory
+//!                                               └─ non nell'indice → untracked
+//! ```
+//!
+//! Senza `.ogit/index` l'indice coincide con il tree di HEAD.
+//! I file esclusi da `.ogitignore` non compaiono fra gli untracked;
+//! quelli già tracciati continuano a essere confrontati.
+//! Un cambio del solo modo (eseguibile, symlink) è riportato come `modified`;
+//! fra HEAD e indice un file spostato compare come `renamed`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use crate::diff::{detect_renames, diff_files, RenameOptions};
+use crate::error::OgitError;
+use crate::ignore::IgnoreRules;
+use crate::index::{collect_files, Index};
+use crate::merge::read_merge_state;
+use crate::object::{FileMode, OObjectId};
+use crate::object_store::{LooseStore, ObjectStore};
+use crate::refs::{current_branch, resolve_ref, HEAD};
+use crate::tree::{flatten_tree_in, hash_worktree_file, worktree_mode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    /// `old_path` spostato in `path`, con contenuto uguale o simile
+    Renamed,
+    /// `path` nuovo, copiato da `old_path` che resta al suo posto
+    Copied,
+}
+
+impl ChangeKind {
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Added => "new file",
+            Self::Modified => "modified",
+            Self::Deleted => "deleted",
+            Self::Renamed => "renamed",
+            Self::Copied => "copied",
+        }
+    }
+
+    /// Lettera usata nell'output compatto (`status -s`)
+    #[must_use]
+    pub const fn code(&self) -> char {
+        match self {
+            Self::Added => 'A',
+            Self::Modified => 'M',
+            Self::Deleted => 'D',
+            Self::Renamed => 'R',
+            Self::Copied => 'C',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub path: String,
+    /// Path di origine per `Renamed` e `Copied`
+    pub old_path: Option<String>,
+    pub old: Option<OObjectId>,
+    pub new: Option<OObjectId>,
+}
+
+impl Change {
+    /// Path come lo mostra `status`: `vecchio -> nuovo` per rinomine e copie
+    #[must_use]
+    pub fn display_path(&self) -> String {
+        match &self.old_path {
+            Some(old) => format!("{old} -> {}", self.path),
+            None => self.path.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Status {
+    /// `None` se HEAD è staccato
+    pub branch: Option<String>,
+    /// HEAD → indice
+    pub staged: Vec<Change>,
+    /// indice → working directory
+    pub unstaged: Vec<Change>,
+    /// File presenti su disco ma non nell'indice, in ordine di path
+    pub untracked: Vec<String>,
+    /// Path ancora in conflitto durante un merge
+    pub conflicts: Vec<String>,
+}
+
+impl Status {
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.staged.is_empty()
+            && self.unstaged.is_empty()
+            && self.untracked.is_empty()
+            && self.conflicts.is_empty()
+    }
+}
+
+/// Confronta due mappe `path → hash` e restituisce le differenze ordinate per path.
+#[must_use]
+pub fn diff_maps(
+    old: &BTreeMap<String, OObjectId>,
+    new: &BTreeMap<String, OObjectId>,
+) -> Vec<Change> {
+    let paths: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let (o, n) = (old.get(path), new.get(path));
+            let kind = match (o, n) {
+                (None, Some(_)) => ChangeKind::Added,
+                (Some(_), None) => ChangeKind::Deleted,
+                (Some(a), Some(b)) if a != b => ChangeKind::Modified,
+                _ => return None,
+            };
+            Some(Change { kind, path: path.clone(), old_path: None, old: o.cloned(), new: n.cloned() })
+        })
+        .collect()
+}
+
+/// Come `diff_maps`, aggiungendo come `Modified` i path con lo stesso
+/// contenuto ma modo diverso.
+fn diff_with_modes(
+    old: &BTreeMap<String, (OObjectId, FileMode)>,
+    new: &BTreeMap<String, (OObjectId, FileMode)>,
+) -> Vec<Change> {
+    let hashes = |files: &BTreeMap<String, (OObjectId, FileMode)>| -> BTreeMap<String, OObjectId> {
+        files.iter().map(|(p, (h, _))| (p.clone(), h.clone())).collect()
+    };
+
+    let mut changes = diff_maps(&hashes(old), &hashes(new));
+    for (path, (hash, mode)) in new {
+        if let Some((old_hash, old_mode)) = old.get(path)
+            && old_hash == hash
+            && old_mode != mode
+        {
+            changes.push(Change {
+                kind: ChangeKind::Modified,
+                path: path.clone(),
+                old_path: None,
+                old: Some(old_hash.clone()),
+                new: Some(hash.clone()),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+/// Mappa `path → hash` del tree di HEAD (vuota se non ci sono commit).
+pub fn head_tree_files(store_path: &Path) -> Result<BTreeMap<String, OObjectId>, OgitError> {
+    Ok(head_tree_modes(store_path)?
+        .into_iter()
+        .map(|(path, (hash, _))| (path, hash))
+        .collect())
+}
+
+/// Come `head_tree_files`, con il modo di ogni file.
+pub fn head_tree_modes(store_path: &Path) -> Result<BTreeMap<String, (OObjectId, FileMode)>, OgitError> {
+    match resolve_ref(store_path, HEAD)? {
+        Some(head) => {
+            let objects = LooseStore::open(store_path)?;
+            flatten_tree_in(&objects, &objects.read_commit(&head)?.tree)
+        }
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+/// Calcola lo status del repository.
+pub fn status(store_path: &Path, work_dir: &Path) -> Result<Status, OgitError> {
+    // 1. HEAD e indice
+    let head_files = head_tree_modes(store_path)?;
+    let objects = LooseStore::open(store_path)?;
+    let format = objects.format();
+    let index = Index::load_or_head(store_path)?;
+    let index_files: BTreeMap<String, (OObjectId, FileMode)> =
+        index.entries.iter().map(|(p, e)| (p.clone(), (e.hash.clone(), e.mode))).collect();
+
+    // 2. Working directory: se size e mtime coincidono con l'indice
+    //    si riusa l'hash registrato, altrimenti si rilegge il file
+    let ignore = IgnoreRules::load(store_path, work_dir)?;
+    let mut files = Vec::new();
+    collect_files(work_dir, "", &ignore, &mut files)?;
+    let collected: BTreeSet<String> = files.iter().cloned().collect();
+    for path in index_files.keys() {
+        let on_disk = fs::symlink_metadata(work_dir.join(path)).is_ok_and(|m| !m.is_dir());
+        if on_disk && !collected.contains(path) {
+            files.push(path.clone());
+        }
+    }
+
+    let mut work_files = BTreeMap::new();
+    let mut untracked = Vec::new();
+
+    for path in files {
+        if !index_files.contains_key(&path) {
+            untracked.push(path);
+            continue;
+        }
+
+        let file_path = work_dir.join(&path);
+        let metadata = fs::symlink_metadata(&file_path)
+            .map_err(OgitError::io(format!("Failed to stat {path}")))?;
+
+        let hash = match index.get(&path) {
+            Some(entry) if entry.matches_stat(&metadata) => entry.hash.clone(),
+            _ => {
+                hash_worktree_file(&file_path, format)?.1
+            }
+        };
+        work_files.insert(path, (hash, worktree_mode(&metadata)));
+    }
+    // `read_dir` non garantisce un ordine
+    untracked.sort();
+
+    // 3. Differenze
+    Ok(Status {
+        branch: current_branch(store_path)?,
+        staged: detect_renames(&objects, diff_files(&head_files, &index_files), None, &RenameOptions::default())?
+            .into_iter()
+            .map(Change::from)
+            .collect(),
+        unstaged: diff_with_modes(&index_files, &work_files),
+        untracked,
+        conflicts: read_merge_state(store_path)?.map(|m| m.conflicts).unwrap_or_default(),
+    })
+}
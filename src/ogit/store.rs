@@ -1,74 +1,426 @@
-use std::path::Path;
-use std::fs::{create_dir_all, read, write};
+use std::path::{Path, PathBuf};
+use std::fs::{self, create_dir_all, read, File};
+use std::io::{self, BufRead, BufReader, Cursor, ErrorKind, Read, Write};
+use std::time::SystemTime;
 
-use crate::object::Commit;
+use crate::compression::{compress, decode_loose, decoder, encoder, is_compressed};
+use crate::config::Config;
+use crate::error::OgitError;
+use crate::lockfile::{write_atomic, TempFile};
+use crate::object::{
+    object_header, parse_object_header, parse_stored_id, Commit, OObjectType, ObjectFormat, ObjectHasher, Signature,
+    TreeEntry,
+};
+use crate::object_store::{LooseStore, ObjectStore};
+use crate::pack::read_packed;
 
 use super::object::{OObject, OObjectId};
-use super::hashing_values::{hash_bytes, bytes_to_hex};
 
-pub fn read_object(store_path: &Path, id: &OObjectId) -> Result<OObject, String> {
+/// Chiave di configurazione che abilita la compressione zlib degli oggetti.
+pub const COMPRESSION_KEY: &str = "core.compression";
+/// Chiave di configurazione con il formato degli oggetti (`ogit` o `git`).
+pub const OBJECT_FORMAT_KEY: &str = "core.objectformat";
+
+/// Byte letti per volta dalle funzioni in streaming.
+const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+/// Header più lungo possibile: `commit ` più 20 cifre di dimensione.
+const MAX_HEADER_LEN: usize = 32;
+
+pub(crate) fn object_path(store_path: &Path, id: &OObjectId) -> PathBuf {
+    let hex = id.to_hex();
+    let (subdir, filename) = hex.split_at(2);
+    store_path.join("objects").join(subdir).join(filename)
+}
+
+/// Formato degli oggetti e compressione attiva, con una sola lettura della
+/// config. Senza config (repository creati prima del supporto) gli oggetti
+/// restano non compressi; il formato Git richiede sempre oggetti compressi.
+pub(crate) fn store_settings(store_path: &Path) -> Result<(ObjectFormat, bool), OgitError> {
+    let config = Config::load(store_path)?;
+    let format = object_format_from(&config)?;
+    let compression = format == ObjectFormat::Git || config.get_bool(COMPRESSION_KEY)?.unwrap_or(false);
+    Ok((format, compression))
+}
+
+fn object_format_from(config: &Config) -> Result<ObjectFormat, OgitError> {
+    config
+        .get(OBJECT_FORMAT_KEY)
+        .map_or(Ok(ObjectFormat::Ogit), ObjectFormat::parse)
+}
+
+/// Formato con cui il repository scrive i nuovi oggetti.
+pub fn object_format(store_path: &Path) -> Result<ObjectFormat, OgitError> {
+    object_format_from(&Config::load(store_path)?)
+}
+
+pub fn read_object(store_path: &Path, id: &OObjectId) -> Result<OObject, OgitError> {
     /* Algoritmo */
     // Estrai hash hex da OObjectId
     // Splitta: primi 2 char = subdir, resto = filename
     // Costruisci path completo
     // Leggi bytes da file
+    // Decomprimi se è uno stream zlib
     // Deserializza in OObject
+    // Se il file non esiste, cerca l'oggetto nei pack
     // Restituisci
     
-    let hash_hex = id.as_str();
-    let (subdir, filename) = hash_hex.split_at(2);
-    let file_path = store_path.join("objects").join(subdir).join(filename);
-    let file_content = read(file_path)
-        .map_err(|e| format!("Failed to read file: {e}"))?;
-    OObject::deserialize(&file_content)
+    read_object_with(store_path, id, || read_packed(store_path, id))
+}
+
+/// `read_object` nello store già aperto `objects`, con i suoi indici dei pack.
+pub(crate) fn read_object_in(objects: &LooseStore, id: &OObjectId) -> Result<OObject, OgitError> {
+    read_object_with(objects.store_path(), id, || objects.packs()?.read(id))
+}
+
+/// Il file loose, o in sua assenza l'oggetto restituito da `packed`.
+fn read_object_with(
+    store_path: &Path,
+    id: &OObjectId,
+    packed: impl FnOnce() -> Result<Option<OObject>, OgitError>,
+) -> Result<OObject, OgitError> {
+    let file_path = object_path(store_path, id);
+    match read(file_path) {
+        Ok(file_content) => OObject::deserialize(&decode_loose(&file_content)?),
+        Err(e) if e.kind() == ErrorKind::NotFound => packed()?.ok_or_else(|| OgitError::ObjectNotFound(id.clone())),
+        Err(source) => Err(OgitError::Io { context: "Failed to read file".into(), source }),
+    }
 }
 
-pub fn write_object(store_path: &Path, obj: &OObject) -> Result<OObjectId, String> {
+/// Dati di un oggetto letti a blocchi, restituiti da `read_object_stream`.
+///
+/// Produce esattamente `size` byte: se lo store ne contiene meno la lettura
+/// fallisce con `UnexpectedEof` invece di restituire un oggetto troncato.
+pub struct ObjectReader {
+    pub kind: OObjectType,
+    pub size: u64,
+    remaining: u64,
+    inner: Box<dyn Read>,
+}
+
+impl ObjectReader {
+    fn new(kind: OObjectType, size: u64, inner: Box<dyn Read>) -> Self {
+        Self { kind, size, remaining: size, inner }
+    }
+}
+
+impl Read for ObjectReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        let max = buf.len().min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let n = self.inner.read(&mut buf[..max])?;
+        if n == 0 {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "Object data shorter than its header"));
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+/// Come `read_object`, ma senza caricare i dati: un blob loose di qualche GB
+/// si copia con memoria costante.
+///
+/// Algoritmo:
+/// 1. apre il file loose; se inizia come uno stream zlib lo legge attraverso il decompressore
+/// 2. legge l'header fino al `\0` (al massimo `MAX_HEADER_LEN` byte) e ne ricava tipo e dimensione
+/// 3. il resto del file sono i dati, che il lettore restituisce man mano
+///
+/// Un oggetto nei pack viene invece ricostruito in memoria: un delta ha
+/// bisogno della base intera.
+pub fn read_object_stream(store_path: &Path, id: &OObjectId) -> Result<ObjectReader, OgitError> {
+    read_object_stream_with(store_path, id, || read_packed(store_path, id))
+}
+
+/// `read_object_stream` nello store già aperto `objects`, con i suoi indici dei pack.
+pub(crate) fn read_object_stream_in(objects: &LooseStore, id: &OObjectId) -> Result<ObjectReader, OgitError> {
+    read_object_stream_with(objects.store_path(), id, || objects.packs()?.read(id))
+}
+
+fn read_object_stream_with(
+    store_path: &Path,
+    id: &OObjectId,
+    packed: impl FnOnce() -> Result<Option<OObject>, OgitError>,
+) -> Result<ObjectReader, OgitError> {
+    let file = match File::open(object_path(store_path, id)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let obj = packed()?.ok_or_else(|| OgitError::ObjectNotFound(id.clone()))?;
+            return Ok(ObjectReader::new(obj.kind, obj.data.len() as u64, Box::new(Cursor::new(obj.data))));
+        }
+        Err(source) => return Err(OgitError::Io { context: "Failed to read file".into(), source }),
+    };
+
+    // 1. Compresso o no
+    let mut input = BufReader::new(file);
+    let compressed = is_compressed(input.fill_buf().map_err(OgitError::io("Failed to read file"))?);
+    let mut inner: Box<dyn Read> = if compressed { Box::new(decoder(input)) } else { Box::new(input) };
+
+    // 2. Header
+    let mut header = Vec::new();
+    let mut byte = [0u8];
+    loop {
+        inner
+            .read_exact(&mut byte)
+            .map_err(OgitError::corrupt_io(format!("Failed to read header of {id}")))?;
+        if byte[0] == 0 {
+            break;
+        }
+        if header.len() == MAX_HEADER_LEN {
+            return Err(OgitError::corrupt("Invalid header format"));
+        }
+        header.push(byte[0]);
+    }
+    let (kind, size) = parse_object_header(&header)?;
+
+    // 3. Dati
+    Ok(ObjectReader::new(kind, size, inner))
+}
+
+/// Calcola l'hash di un oggetto senza scriverlo nello store.
+#[must_use]
+pub fn object_id(obj: &OObject) -> OObjectId {
+    object_id_as(obj, ObjectFormat::Ogit)
+}
+
+/// Come `object_id`, con l'algoritmo di hash del formato indicato.
+#[must_use]
+pub fn object_id_as(obj: &OObject, format: ObjectFormat) -> OObjectId {
+    format.hash_id(&obj.serialize())
+}
+
+/// Salva un oggetto nello store di `store_path` (se non c'è già) e ne
+/// restituisce l'hash. Chi scrive molti oggetti apre un `LooseStore` una
+/// volta sola invece di rileggere ogni volta la config.
+pub fn write_object(store_path: &Path, obj: &OObject) -> Result<OObjectId, OgitError> {
+    LooseStore::open(store_path)?.write(obj)
+}
+
+/// `write_object` nello store già aperto `objects`: formato, compressione e
+/// indici dei pack sono i suoi.
+pub(crate) fn write_object_in(objects: &LooseStore, obj: &OObject) -> Result<OObjectId, OgitError> {
     /* Algoritmo */
     // 1. Serializza l'oggetto → Vec<u8>
-    // 2. Calcola hash dei byte serializzati → [u8; 32]
-    // 3. Converti hash in hex string → String
+    // 2. Calcola hash dei byte serializzati (SHA-256, o SHA-1 in formato Git)
+    // 3. Costruisci l'OObjectId dai byte dell'hash
     // 4. Costruisci path: .ogit/objects/aa/bbccdd... (primi 2 char = subdirectory)
     // 5. Crea subdirectory se non esiste
-    // 6. Scrivi file (se non esiste già)
+    // 6. Scrivi file (se non esiste già, né loose né in un pack), compresso se abilitato in config,
+    //    passando per un file temporaneo: un crash non lascia oggetti troncati;
+    //    se il file loose c'è già se ne aggiorna l'mtime
     // 7. Restituisci OObjectId
     
+    let store_path = objects.store_path();
     let ser = obj.serialize();
-    let hashed_ser = hash_bytes(&ser);
-    let hashed_hexed = bytes_to_hex(&hashed_ser);
+    let id = objects.format().hash_id(&ser);
 
-    let (subdir, filename) = hashed_hexed.split_at(2);
-    let dir_path = store_path.join("objects").join(subdir);
-    let file_path = dir_path.join(filename);
+    // Evita scritture inutili su BLOB grandi (l'hash non dipende dalla compressione)
+    if !freshen_loose(&object_path(store_path, &id)) && !objects.packs()?.contains(&id)? {
+        write_loose(store_path, &id, ser, objects.compression())?;
+    }
+    
+    Ok(id)
+}
+
+/// Se il file loose esiste già ne porta l'mtime ad adesso, come fa git:
+/// `prune` misura il periodo di grazia dall'mtime, e un oggetto vecchio e
+/// irraggiungibile appena riusato da un commit in corso non deve essere
+/// eliminato prima che il ref lo renda raggiungibile. `true` se il file esiste.
+///
+/// Non riuscire ad aggiornarlo (permessi, file system in sola lettura) non è
+/// un errore: l'oggetto c'è comunque, al più `prune` lo vede più vecchio.
+fn freshen_loose(path: &Path) -> bool {
+    // `set_modified` su Windows richiede un handle aperto in scrittura
+    match File::options().write(true).open(path) {
+        Ok(file) => {
+            let _ = file.set_modified(SystemTime::now());
+            true
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => false,
+        Err(_) => path.exists(),
+    }
+}
+
+/// Salva un blob di `size` byte letti da `reader`, senza tenerlo in memoria.
+///
+/// Algoritmo:
+/// 1. apre un file temporaneo in `objects/` (attraverso zlib se la compressione è attiva)
+/// 2. scrive l'header `blob <size>\0`, poi copia i dati a blocchi aggiornando l'hash
+/// 3. l'hash dà l'id: se l'oggetto c'è già (loose o in un pack) il temporaneo
+///    si scarta (aggiornando l'mtime del file loose), altrimenti diventa
+///    `objects/aa/bbccdd...`
+///
+/// Se `reader` non fornisce esattamente `size` byte (file modificato durante
+/// la lettura) non viene scritto niente e l'errore è `Corrupt`.
+pub fn write_blob_stream(store_path: &Path, mut reader: impl Read, size: u64) -> Result<OObjectId, OgitError> {
+    LooseStore::open(store_path)?.write_blob_stream(&mut reader, size)
+}
 
-    create_dir_all(&dir_path)
-        .map_err(|e| format!("Failed to create dir: {e}"))?;
+/// `write_blob_stream` nello store già aperto `objects`: formato, compressione
+/// e indici dei pack sono i suoi.
+pub(crate) fn write_blob_stream_in(objects: &LooseStore, reader: impl Read, size: u64) -> Result<OObjectId, OgitError> {
+    let store_path = objects.store_path();
+    let mut hasher = ObjectHasher::new(objects.format(), OObjectType::Blob, size);
 
-    // Evita scritture inutili su BLOB grandi
-    if !file_path.exists() {
-    write(&file_path, &ser)
-        .map_err(|e| format!("Failed to write: {e}"))?;
+    // 1-2. Temporaneo (rimosso da solo se qualcosa va storto)
+    let temp = TempFile::create(&store_path.join("objects").join("blob"))?;
+    let temp = if objects.compression() {
+        let mut zlib = encoder(temp);
+        copy_blob(reader, size, &mut hasher, &mut zlib)?;
+        zlib.finish().map_err(OgitError::io("Failed to compress"))?
+    } else {
+        let mut temp = temp;
+        copy_blob(reader, size, &mut hasher, &mut temp)?;
+        temp
+    };
+    let id = hasher.finish()?;
+
+    // 3. Nome definitivo
+    let file_path = object_path(store_path, &id);
+    if freshen_loose(&file_path) || objects.packs()?.contains(&id)? {
+        return Ok(id);
     }
-    
-    Ok(OObjectId(hashed_hexed))
+    if let Some(dir_path) = file_path.parent() {
+        create_dir_all(dir_path)
+            .map_err(OgitError::io("Failed to create dir"))?;
+    }
+    temp.persist(&file_path)?;
+    Ok(id)
+}
+
+/// Header e dati di un blob verso `out`; `hasher` riceve solo i dati.
+fn copy_blob(mut reader: impl Read, size: u64, hasher: &mut ObjectHasher, out: &mut impl Write) -> Result<(), OgitError> {
+    let write_failed = |source: io::Error| OgitError::Io { context: "Failed to write blob".into(), source };
+    out.write_all(&object_header(OObjectType::Blob, size)).map_err(write_failed)?;
+
+    let mut buffer = vec![0; STREAM_BUFFER_SIZE];
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(source) => return Err(OgitError::Io { context: "Failed to read blob data".into(), source }),
+        };
+        hasher.update(&buffer[..n]);
+        out.write_all(&buffer[..n]).map_err(write_failed)?;
+    }
+}
+
+/// Scrive i byte serializzati di `id` come file loose, anche se l'oggetto è
+/// già in un pack (serve a `gc` per estrarre gli oggetti da scartare).
+pub(crate) fn write_loose(
+    store_path: &Path,
+    id: &OObjectId,
+    serialized: Vec<u8>,
+    compression: bool,
+) -> Result<PathBuf, OgitError> {
+    let file_path = object_path(store_path, id);
+    if let Some(dir_path) = file_path.parent() {
+        create_dir_all(dir_path)
+            .map_err(OgitError::io("Failed to create dir"))?;
+    }
+
+    let bytes = if compression { compress(&serialized)? } else { serialized };
+    write_atomic(&file_path, &bytes)?;
+    Ok(file_path)
 }
 
 pub fn create_commit(
     store_path: &Path,
     tree: &OObjectId,
-    parent: Option<&OObjectId>,
-    author: &str,
+    parents: &[OObjectId],
+    author: &Signature,
     message: &str,
-) -> Result<OObjectId, String> {
+) -> Result<OObjectId, OgitError> {
     /* Algoritmo: */
-    // 1. Costruisci Commit struct
+    // 1. Costruisci Commit struct (committer = author)
     // 2. Serializza
     // 3. Crea OObject::new_commit(payload)
     // 4. Salva con write_object
     // 5. Restituisci hash
     
-    let data = Commit { tree: tree.clone(), parent: parent.cloned(), author: author.to_string(), message: message.to_string() };
-    let commit = OObject::new_commit(data.serialize());
-    write_object(store_path, &commit)
+    LooseStore::open(store_path)?.create_commit(tree, parents, author, message)
+}
+
+/// Serializza e salva un commit già costruito (autore e committer distinti).
+pub fn write_commit(store_path: &Path, commit: &Commit) -> Result<OObjectId, OgitError> {
+    LooseStore::open(store_path)?.write_commit(commit)
+}
+
+/// Legge un oggetto e lo interpreta come commit.
+pub fn read_commit(store_path: &Path, id: &OObjectId) -> Result<Commit, OgitError> {
+    LooseStore::open(store_path)?.read_commit(id)
+}
+
+/// Legge un oggetto e lo interpreta come tree.
+pub fn read_tree(store_path: &Path, id: &OObjectId) -> Result<Vec<TreeEntry>, OgitError> {
+    LooseStore::open(store_path)?.read_tree(id)
+}
+
+/// Serializza le entry nel formato del repository e salva il tree.
+pub fn write_tree(store_path: &Path, entries: &[TreeEntry]) -> Result<OObjectId, OgitError> {
+    LooseStore::open(store_path)?.write_tree(entries)
+}
+
+/// Elenca gli hash di tutti gli oggetti loose in `.ogit/objects/<aa>/<resto>`
+/// (quelli nei pack si elencano con `pack::list_packed`).
+pub fn list_objects(store_path: &Path) -> Result<Vec<OObjectId>, OgitError> {
+    let objects_path = store_path.join("objects");
+    let mut ids = Vec::new();
+
+    let subdirs = fs::read_dir(&objects_path)
+        .map_err(OgitError::io("Failed to read objects"))?;
+
+    for subdir in subdirs {
+        let subdir = subdir.map_err(OgitError::io("Failed to read objects"))?;
+        let subdir_name = subdir.file_name().to_string_lossy().to_string();
+
+        // Solo le directory di sharding (2 caratteri hex)
+        if !subdir.path().is_dir() || subdir_name.len() != 2 {
+            continue;
+        }
+
+        let files = fs::read_dir(subdir.path()).map_err(OgitError::io("Failed to read objects"))?;
+        for file in files {
+            let file = file.map_err(OgitError::io("Failed to read objects"))?;
+            let filename = file.file_name().to_string_lossy().to_string();
+            // File estranei (temporanei, lock...) non sono oggetti
+            if let Ok(id) = parse_stored_id(&format!("{subdir_name}{filename}")) {
+                ids.push(id);
+            }
+        }
+    }
+
+    ids.sort();
+    Ok(ids)
+}
+
+/// Migrazione: riscrive ogni oggetto loose nel formato indicato da
+/// `core.compression`. Restituisce il numero di oggetti riscritti.
+pub fn migrate_objects(store_path: &Path) -> Result<usize, OgitError> {
+    let (_, compress_enabled) = store_settings(store_path)?;
+    let mut rewritten = 0;
+
+    for id in list_objects(store_path)? {
+        let file_path = object_path(store_path, &id);
+        let stored = read(&file_path)
+            .map_err(OgitError::io(format!("Failed to read {}", id)))?;
+
+        if is_compressed(&stored) == compress_enabled {
+            continue;
+        }
+
+        // Verifica l'integrità prima di sovrascrivere
+        let ser = decode_loose(&stored)?;
+        if ObjectFormat::from_id(&id).hash_id(&ser) != id {
+            return Err(OgitError::corrupt(format!("Object {} is corrupt, migration aborted", id)));
+        }
+
+        let bytes = if compress_enabled { compress(&ser)? } else { ser };
+        write_atomic(&file_path, &bytes)?;
+        rewritten += 1;
+    }
+
+    Ok(rewritten)
 }
 
@@ -4,10 +4,12 @@
 /// inizializza lista entries vuota
 ///
 /// per ogni elemento presente in path:
+///     se l'elemento è escluso da .ogitignore / .ogit/info/exclude:
+///         saltalo
+///
 ///     se l'elemento è un file:
-///         leggi il contenuto del file
-///         crea un oggetto Blob con il contenuto
-///         scrivi l'oggetto nello storage e ottieni il relativo hash
+///         copia il file a blocchi nello storage come oggetto Blob
+///         (l'hash si calcola durante la copia) e ottieni il relativo hash
 ///         aggiungi a entries una voce { tipo: Blob, hash, nome }
 ///
 ///     altrimenti se l'elemento è una directory:
@@ -21,23 +23,45 @@
 /// restituisci l'hash risultante
 /// ```
 
-use std::fs::{self, read};
+use std::collections::BTreeMap;
+use std::fs::{self, read, File};
+use std::io::{self, BufReader, Cursor, Read};
 use std::path::Path;
 
-use crate::object::{OObject, OObjectId, OObjectType, TreeEntry};
-use crate::store::write_object;
+use crate::error::OgitError;
+use crate::ignore::IgnoreRules;
+use crate::object::{FileMode, OObjectId, OObjectType, ObjectFormat, TreeEntry};
+use crate::object_store::{LooseStore, ObjectStore};
+
+pub fn build_tree_from_dir(store_path: &Path, dir_path: &Path) -> Result<OObjectId, OgitError> {
+    let ignore = IgnoreRules::load(store_path, dir_path)?;
+    build_tree(&LooseStore::open(store_path)?, dir_path, &ignore)
+}
 
-pub fn build_tree_from_dir(store_path: &Path, dir_path: &Path) -> Result<OObjectId, String> {
+/// Come `build_tree_from_dir`, scrivendo in uno store qualsiasi con le
+/// regole di esclusione `ignore` della root.
+pub fn build_tree(objects: &dyn ObjectStore, dir_path: &Path, ignore: &IgnoreRules) -> Result<OObjectId, OgitError> {
+    build_subtree(objects, dir_path, "", ignore)
+}
+
+/// `rel` è il path di `dir_path` relativo alla root (`""` per la root),
+/// `ignore` le regole valide dentro `dir_path`.
+fn build_subtree(
+    objects: &dyn ObjectStore,
+    dir_path: &Path,
+    rel: &str,
+    ignore: &IgnoreRules,
+) -> Result<OObjectId, OgitError> {
     let mut entries: Vec<TreeEntry> = Vec::new();
     
     // 1. Leggi contenuto directory
     let read_dir = fs::read_dir(dir_path)
-        .map_err(|e| format!("Failed to read dir: {}", e))?;
+        .map_err(OgitError::io("Failed to read dir"))?;
     
     // 2. Itera su ogni elemento
     for entry_result in read_dir {
         let entry = entry_result
-            .map_err(|e| format!("Failed to read entry: {}", e))?;
+            .map_err(OgitError::io("Failed to read entry"))?;
         
         let path = entry.path();
         let name = entry.file_name().to_string_lossy().to_string();
@@ -47,26 +71,273 @@ pub fn build_tree_from_dir(store_path: &Path, dir_path: &Path) -> Result<OObject
             continue;
         }
         
-        // 4. Gestisci file vs directory
-        if path.is_file() {
-            // Leggo il contenuto del file
-            let content = read(&path)
-                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
-            // Creo un blob
-            let blob = OObject::new_blob(content);
-            // Scrivo il blob
-            let hash = write_object(store_path, &blob)?;
+        // 4. Gestisci file (anche eseguibili e symlink) vs directory.
+        //    `file_type` non segue i symlink: un link a una directory resta un link
+        let file_type = entry.file_type()
+            .map_err(OgitError::io(format!("Failed to stat {}", path.display())))?;
+        
+        // 5. Salta i path esclusi
+        let child_rel = if rel.is_empty() { name.clone() } else { format!("{rel}/{name}") };
+        if ignore.is_ignored(&child_rel, file_type.is_dir()) {
+            continue;
+        }
+        
+        if file_type.is_file() || file_type.is_symlink() {
+            // Scrivo il blob, copiando il file a blocchi (per un symlink: il path di destinazione)
+            let (mode, hash) = store_worktree_file(objects, &path)?;
             // Inserisco nelle entries
-            entries.push(TreeEntry { kind: OObjectType::Blob, hash, name });
+            entries.push(TreeEntry::with_mode(mode, hash, name));
             
-        } else if path.is_dir() {
-            let hash = build_tree_from_dir(store_path, &path);
-            entries.push(TreeEntry { kind: OObjectType::Tree, hash: hash.unwrap(), name  });
+        } else if file_type.is_dir() {
+            let child_ignore = ignore.child(&path, &child_rel)?;
+            let hash = build_subtree(objects, &path, &child_rel, &child_ignore)?;
+            entries.push(TreeEntry::new(OObjectType::Tree, hash, name));
         }
     }
     
-    // 5. Serializza e salva tree
-    let tree_data = TreeEntry::serialize_tree(&entries);
-    let tree_obj = OObject::new_tree(tree_data);
-    write_object(store_path, &tree_obj)
-}
\ No newline at end of file
+    // 6. Serializza e salva tree (nel formato del repository)
+    objects.write_tree(&entries)
+}
+
+/// Appiattisce ricorsivamente un tree in una mappa `path → hash blob`.
+///
+/// ```text
+/// tree
+/// ├── readme.txt        → "readme.txt"
+/// └── src
+///     └── main.rs       → "src/main.rs"
+/// ```
+///
+/// I path usano sempre `/` come separatore, indipendentemente dalla piattaforma.
+pub fn flatten_tree(store_path: &Path, tree_id: &OObjectId) -> Result<BTreeMap<String, OObjectId>, OgitError> {
+    Ok(flatten_tree_modes(store_path, tree_id)?
+        .into_iter()
+        .map(|(path, (hash, _))| (path, hash))
+        .collect())
+}
+
+/// Come `flatten_tree`, conservando anche il modo di ogni file.
+pub fn flatten_tree_modes(
+    store_path: &Path,
+    tree_id: &OObjectId,
+) -> Result<BTreeMap<String, (OObjectId, FileMode)>, OgitError> {
+    flatten_tree_in(&LooseStore::open(store_path)?, tree_id)
+}
+
+/// Come `flatten_tree_modes`, leggendo da uno store qualsiasi.
+pub fn flatten_tree_in(
+    objects: &dyn ObjectStore,
+    tree_id: &OObjectId,
+) -> Result<BTreeMap<String, (OObjectId, FileMode)>, OgitError> {
+    let mut files = BTreeMap::new();
+    flatten_into(objects, tree_id, "", &mut files)?;
+    Ok(files)
+}
+
+fn flatten_into(
+    objects: &dyn ObjectStore,
+    tree_id: &OObjectId,
+    prefix: &str,
+    files: &mut BTreeMap<String, (OObjectId, FileMode)>,
+) -> Result<(), OgitError> {
+    for entry in objects.read_tree(tree_id)? {
+        let path = format!("{prefix}{}", entry.name);
+        match entry.kind {
+            OObjectType::Tree => flatten_into(objects, &entry.hash, &format!("{path}/"), files)?,
+            _ => {
+                files.insert(path, (entry.hash, entry.mode));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Calcola l'hash di ogni file della working directory senza scrivere nello store.
+/// Stesso formato di `flatten_tree`, così le due mappe sono confrontabili.
+pub fn hash_worktree(dir_path: &Path, format: ObjectFormat) -> Result<BTreeMap<String, OObjectId>, OgitError> {
+    let mut files = BTreeMap::new();
+    hash_worktree_into(dir_path, "", format, &mut files)?;
+    Ok(files)
+}
+
+fn hash_worktree_into(
+    dir_path: &Path,
+    prefix: &str,
+    format: ObjectFormat,
+    files: &mut BTreeMap<String, OObjectId>,
+) -> Result<(), OgitError> {
+    let read_dir = fs::read_dir(dir_path)
+        .map_err(OgitError::io("Failed to read dir"))?;
+
+    for entry_result in read_dir {
+        let entry = entry_result
+            .map_err(OgitError::io("Failed to read entry"))?;
+
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if name == ".ogit" {
+            continue;
+        }
+
+        let file_type = entry.file_type()
+            .map_err(OgitError::io(format!("Failed to stat {}", path.display())))?;
+
+        if file_type.is_file() || file_type.is_symlink() {
+            let (_, hash) = hash_worktree_file(&path, format)?;
+            files.insert(format!("{prefix}{name}"), hash);
+        } else if file_type.is_dir() {
+            hash_worktree_into(&path, &format!("{prefix}{name}/"), format, files)?;
+        }
+    }
+    Ok(())
+}
+
+/// Modo di un elemento della working directory; `metadata` deve venire
+/// da `symlink_metadata` (o `DirEntry::metadata`) per riconoscere i symlink.
+#[must_use]
+pub fn worktree_mode(metadata: &fs::Metadata) -> FileMode {
+    if metadata.file_type().is_symlink() {
+        FileMode::Symlink
+    } else if metadata.is_dir() {
+        FileMode::Directory
+    } else if is_executable(metadata) {
+        FileMode::Executable
+    } else {
+        FileMode::Regular
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Legge un file senza seguire i symlink: restituisce il modo e il contenuto
+/// del blob (per un symlink, il path di destinazione).
+pub fn read_worktree_file(path: &Path) -> Result<(FileMode, Vec<u8>), OgitError> {
+    let metadata = fs::symlink_metadata(path)
+        .map_err(OgitError::io(format!("Failed to stat {}", path.display())))?;
+    let mode = worktree_mode(&metadata);
+
+    let content = if mode == FileMode::Symlink {
+        let target = fs::read_link(path)
+            .map_err(OgitError::io(format!("Failed to read link {}", path.display())))?;
+        target.to_string_lossy().into_owned().into_bytes()
+    } else {
+        read(path).map_err(OgitError::io(format!("Failed to read {}", path.display())))?
+    };
+
+    Ok((mode, content))
+}
+
+/// Apre il contenuto del blob di un file senza seguire i symlink: il file
+/// stesso, letto a blocchi, o il path di destinazione di un link.
+/// Restituisce modo, lettore e dimensione del blob.
+fn open_worktree_file(path: &Path) -> Result<(FileMode, Box<dyn Read>, u64), OgitError> {
+    let metadata = fs::symlink_metadata(path)
+        .map_err(OgitError::io(format!("Failed to stat {}", path.display())))?;
+    let mode = worktree_mode(&metadata);
+
+    if mode == FileMode::Symlink {
+        let (_, target) = read_worktree_file(path)?;
+        let size = target.len() as u64;
+        return Ok((mode, Box::new(Cursor::new(target)), size));
+    }
+
+    let file = File::open(path)
+        .map_err(OgitError::io(format!("Failed to read {}", path.display())))?;
+    // La dimensione del file aperto: se cambia durante la lettura, l'hash lo segnala
+    let size = file.metadata()
+        .map_err(OgitError::io(format!("Failed to stat {}", path.display())))?
+        .len();
+    Ok((mode, Box::new(BufReader::new(file)), size))
+}
+
+/// Salva il blob di un file della working directory e ne restituisce modo e
+/// hash. Il file si copia a blocchi, senza mai caricarlo tutto in memoria.
+pub fn store_worktree_file(objects: &dyn ObjectStore, path: &Path) -> Result<(FileMode, OObjectId), OgitError> {
+    let (mode, mut reader, size) = open_worktree_file(path)?;
+    Ok((mode, objects.write_blob_stream(&mut reader, size)?))
+}
+
+/// Come `store_worktree_file`, ma calcola soltanto l'hash.
+pub fn hash_worktree_file(path: &Path, format: ObjectFormat) -> Result<(FileMode, OObjectId), OgitError> {
+    let (mode, reader, size) = open_worktree_file(path)?;
+    Ok((mode, format.hash_reader(OObjectType::Blob, reader, size)?))
+}
+
+/// Scrive un blob nella working directory rispettando il modo:
+/// symlink ricreati come link, bit di esecuzione impostato per `Executable`.
+pub fn write_worktree_file(path: &Path, mode: FileMode, data: &[u8]) -> Result<(), OgitError> {
+    write_worktree_stream(path, mode, &mut &data[..])
+}
+
+/// Come `write_worktree_file`, con il contenuto letto a blocchi da `reader`.
+pub fn write_worktree_stream(path: &Path, mode: FileMode, reader: &mut impl Read) -> Result<(), OgitError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(OgitError::io(format!("Failed to create {}", parent.display())))?;
+    }
+
+    // Un file o link preesistente va rimosso: `symlink` non sovrascrive
+    if fs::symlink_metadata(path).is_ok_and(|m| !m.is_dir()) {
+        fs::remove_file(path)
+            .map_err(OgitError::io(format!("Failed to replace {}", path.display())))?;
+    }
+
+    if mode == FileMode::Symlink {
+        let mut target = Vec::new();
+        reader
+            .read_to_end(&mut target)
+            .map_err(OgitError::io(format!("Failed to read link target for {}", path.display())))?;
+        return create_symlink(path, &target);
+    }
+
+    File::create(path)
+        .and_then(|mut file| io::copy(reader, &mut file))
+        .map_err(OgitError::io(format!("Failed to write {}", path.display())))?;
+    set_executable(path, mode == FileMode::Executable)
+}
+
+#[cfg(unix)]
+fn create_symlink(path: &Path, target: &[u8]) -> Result<(), OgitError> {
+    let target = String::from_utf8_lossy(target).into_owned();
+    std::os::unix::fs::symlink(&target, path)
+        .map_err(OgitError::io(format!("Failed to create link {}", path.display())))
+}
+
+/// Senza symlink nativi il link diventa un file che contiene la destinazione (come Git).
+#[cfg(not(unix))]
+fn create_symlink(path: &Path, target: &[u8]) -> Result<(), OgitError> {
+    fs::write(path, target)
+        .map_err(OgitError::io(format!("Failed to write {}", path.display())))
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path, executable: bool) -> Result<(), OgitError> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = fs::metadata(path)
+        .map_err(OgitError::io(format!("Failed to stat {}", path.display())))?
+        .permissions();
+    let mode = perms.mode();
+    let new_mode = if executable { mode | ((mode & 0o444) >> 2) } else { mode & !0o111 };
+    if new_mode != mode {
+        perms.set_mode(new_mode);
+        fs::set_permissions(path, perms)
+            .map_err(OgitError::io(format!("Failed to chmod {}", path.display())))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path, _executable: bool) -> Result<(), OgitError> {
+    Ok(())
+}
+
@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use ogit::branch::{create_branch, delete_branch, list_branches, rename_branch, validate_branch_name};
+    use ogit::error::OgitError;
+    use ogit::object::{OObjectId, Signature};
+    use ogit::refs::{current_branch, resolve_ref, update_ref, HEAD};
+    use ogit::store::create_commit;
+
+    fn author() -> Signature {
+        Signature::new("Author", "author@example.com", 0, 0)
+    }
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_branch_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("objects")).unwrap();
+        fs::create_dir_all(dir.join("refs/heads")).unwrap();
+        fs::write(dir.join("HEAD"), "ref: refs/heads/master\n").unwrap();
+        dir
+    }
+
+    /// Crea un commit figlio di HEAD e avanza il branch corrente
+    fn commit(store: &Path, message: &str) -> OObjectId {
+        let tree = "0".repeat(64).parse::<OObjectId>().unwrap();
+        let parent = resolve_ref(store, HEAD).unwrap();
+        let id = create_commit(store, &tree, parent.as_slice(), &author(), message).unwrap();
+        update_ref(store, HEAD, &id).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_create_and_list_branches() {
+        let store = setup_test_dir("list");
+        let first = commit(&store, "first");
+
+        create_branch(&store, "feature/login", None).unwrap();
+        create_branch(&store, "dev", Some(&first)).unwrap();
+
+        let branches = list_branches(&store).unwrap();
+        let names: Vec<&str> = branches.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["dev", "feature/login", "master"]);
+        assert!(branches.iter().all(|b| b.id == first));
+        assert!(branches[2].is_current);
+        assert!(!branches[0].is_current);
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_create_existing_or_invalid_fails() {
+        let store = setup_test_dir("invalid");
+        commit(&store, "first");
+
+        assert!(create_branch(&store, "master", None).is_err());
+        assert!(validate_branch_name("bad..name").is_err());
+        assert!(validate_branch_name("with space").is_err());
+        assert!(validate_branch_name("-dash").is_err());
+        assert!(validate_branch_name("feature/ok").is_ok());
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_create_branch_without_commits_fails() {
+        let store = setup_test_dir("unborn");
+
+        assert!(create_branch(&store, "dev", None).is_err());
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_delete_merged_and_unmerged() {
+        let store = setup_test_dir("delete");
+        commit(&store, "first");
+        create_branch(&store, "merged", None).unwrap();
+        commit(&store, "second");
+
+        // "merged" è antenato di HEAD → eliminabile
+        delete_branch(&store, "merged", false).unwrap();
+        assert!(!store.join("refs/heads/merged").exists());
+
+        // "ahead" punta a un commit non raggiungibile da HEAD
+        let tree = "0".repeat(64).parse::<OObjectId>().unwrap();
+        let head = resolve_ref(&store, HEAD).unwrap().unwrap();
+        let other = create_commit(&store, &tree, std::slice::from_ref(&head), &author(), "side").unwrap();
+        create_branch(&store, "ahead", Some(&other)).unwrap();
+
+        assert!(delete_branch(&store, "ahead", false).is_err());
+        delete_branch(&store, "ahead", true).unwrap();
+
+        // Il branch corrente non si elimina
+        assert!(delete_branch(&store, "master", true).is_err());
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_branch_merged_through_second_parent_is_deletable() {
+        let store = setup_test_dir("delete_merge");
+        let base = commit(&store, "base");
+        let tree = "0".repeat(64).parse::<OObjectId>().unwrap();
+        let side = create_commit(&store, &tree, std::slice::from_ref(&base), &author(), "side").unwrap();
+        create_branch(&store, "topic", Some(&side)).unwrap();
+
+        // Merge su master: "topic" è raggiungibile solo dal secondo parent
+        let main = commit(&store, "main");
+        let merge = create_commit(&store, &tree, &[main, side], &author(), "merge").unwrap();
+        update_ref(&store, HEAD, &merge).unwrap();
+
+        delete_branch(&store, "topic", false).unwrap();
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_rename_current_branch_moves_head() {
+        let store = setup_test_dir("rename");
+        let id = commit(&store, "first");
+
+        rename_branch(&store, "master", "main").unwrap();
+
+        assert_eq!(current_branch(&store).unwrap(), Some("main".to_string()));
+        assert_eq!(resolve_ref(&store, HEAD).unwrap(), Some(id));
+        assert!(!store.join("refs/heads/master").exists());
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_delete_and_rename_reject_paths_outside_heads() {
+        let store = setup_test_dir("escape");
+        let id = commit(&store, "first");
+        // File fuori da refs/heads con l'aspetto di un ref
+        fs::write(store.join("victim"), format!("{id}\n")).unwrap();
+
+        for name in ["../../victim", "/victim", "a/../../../victim"] {
+            assert!(matches!(delete_branch(&store, name, true), Err(OgitError::InvalidRef(_))), "{name}");
+            assert!(matches!(rename_branch(&store, name, "moved"), Err(OgitError::InvalidRef(_))), "{name}");
+        }
+        assert!(store.join("victim").exists());
+        assert!(!store.join("refs/heads/moved").exists());
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+}
@@ -35,7 +35,8 @@ mod tests {
         let tree_id = build_tree_from_dir(&store_path, &content_dir).unwrap();
         
         // Verifica che tree esista
-        let (subdir, filename) = tree_id.as_str().split_at(2);
+        let hex = tree_id.to_hex();
+        let (subdir, filename) = hex.split_at(2);
         let tree_path = store_path.join("objects").join(subdir).join(filename);
         assert!(tree_path.exists());
         
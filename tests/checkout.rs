@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use ogit::branch::create_branch;
+    use ogit::checkout::{checkout, CheckoutTarget};
+    use ogit::error::OgitError;
+    use ogit::index::{add_paths, Index};
+    use ogit::object::{OObjectId, Signature};
+    use ogit::refs::{current_branch, resolve_ref, update_ref, HEAD};
+    use ogit::store::create_commit;
+    use ogit::tree::build_tree_from_dir;
+
+    /// Working directory con `.ogit/` inizializzato al suo interno
+    fn author() -> Signature {
+        Signature::new("Author", "author@example.com", 0, 0)
+    }
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_checkout_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".ogit/objects")).unwrap();
+        fs::create_dir_all(dir.join(".ogit/refs/heads")).unwrap();
+        fs::write(dir.join(".ogit/HEAD"), "ref: refs/heads/master\n").unwrap();
+        dir
+    }
+
+    fn commit_all(dir: &Path, message: &str) -> OObjectId {
+        let store = dir.join(".ogit");
+        let tree = build_tree_from_dir(&store, dir).unwrap();
+        let parent = resolve_ref(&store, HEAD).unwrap();
+        let id = create_commit(&store, &tree, parent.as_slice(), &author(), message).unwrap();
+        update_ref(&store, HEAD, &id).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_checkout_branch_restores_files() {
+        let dir = setup_test_dir("restore");
+        let store = dir.join(".ogit");
+
+        fs::write(dir.join("a.txt"), "first").unwrap();
+        commit_all(&dir, "first");
+        create_branch(&store, "old", None).unwrap();
+
+        fs::write(dir.join("a.txt"), "second").unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/new.rs"), "fn x() {}").unwrap();
+        commit_all(&dir, "second");
+
+        let result = checkout(&store, &dir, "old").unwrap();
+        assert_eq!(result, CheckoutTarget::Branch("old".to_string()));
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "first");
+        assert!(!dir.join("src/new.rs").exists());
+        assert!(!dir.join("src").exists());
+        assert_eq!(current_branch(&store).unwrap(), Some("old".to_string()));
+
+        checkout(&store, &dir, "master").unwrap();
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "second");
+        assert_eq!(fs::read_to_string(dir.join("src/new.rs")).unwrap(), "fn x() {}");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_checkout_commit_detaches_head() {
+        let dir = setup_test_dir("detach");
+        let store = dir.join(".ogit");
+
+        fs::write(dir.join("a.txt"), "first").unwrap();
+        let first = commit_all(&dir, "first");
+        fs::write(dir.join("a.txt"), "second").unwrap();
+        commit_all(&dir, "second");
+
+        let result = checkout(&store, &dir, &first.to_hex()).unwrap();
+        assert_eq!(result, CheckoutTarget::Detached(first.clone()));
+        assert_eq!(current_branch(&store).unwrap(), None);
+        assert_eq!(resolve_ref(&store, HEAD).unwrap(), Some(first));
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "first");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_checkout_abbreviated_commit() {
+        let dir = setup_test_dir("abbrev");
+        let store = dir.join(".ogit");
+
+        fs::write(dir.join("a.txt"), "first").unwrap();
+        let first = commit_all(&dir, "first");
+        fs::write(dir.join("a.txt"), "second").unwrap();
+        commit_all(&dir, "second");
+
+        let result = checkout(&store, &dir, &first.short()).unwrap();
+        assert_eq!(result, CheckoutTarget::Detached(first));
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "first");
+
+        let err = checkout(&store, &dir, "zz12").unwrap_err();
+        assert_eq!(err.to_string(), "Not a branch or commit: zz12");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_checkout_refuses_to_clobber_local_changes() {
+        let dir = setup_test_dir("clobber");
+        let store = dir.join(".ogit");
+
+        fs::write(dir.join("a.txt"), "first").unwrap();
+        commit_all(&dir, "first");
+        create_branch(&store, "old", None).unwrap();
+        fs::write(dir.join("a.txt"), "second").unwrap();
+        commit_all(&dir, "second");
+
+        // Modifica non committata su un file che il checkout cambierebbe
+        fs::write(dir.join("a.txt"), "dirty").unwrap();
+        assert!(checkout(&store, &dir, "old").is_err());
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "dirty");
+        assert_eq!(current_branch(&store).unwrap(), Some("master".to_string()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_checkout_refuses_to_drop_staged_changes() {
+        let dir = setup_test_dir("staged");
+        let store = dir.join(".ogit");
+
+        fs::write(dir.join("a.txt"), "first").unwrap();
+        commit_all(&dir, "first");
+        create_branch(&store, "old", None).unwrap();
+        fs::write(dir.join("a.txt"), "second").unwrap();
+        commit_all(&dir, "second");
+
+        // Versione nell'indice, poi il file su disco torna uguale a HEAD
+        fs::write(dir.join("a.txt"), "staged").unwrap();
+        add_paths(&store, &dir, &["a.txt".to_string()], false).unwrap();
+        let staged = Index::load(&store).unwrap().get("a.txt").unwrap().hash.clone();
+        fs::write(dir.join("a.txt"), "second").unwrap();
+
+        let err = checkout(&store, &dir, "old").unwrap_err();
+        assert!(matches!(err, OgitError::Rejected(_)), "{err}");
+        assert_eq!(Index::load(&store).unwrap().get("a.txt").unwrap().hash, staged);
+        assert_eq!(current_branch(&store).unwrap(), Some("master".to_string()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_checkout_keeps_unrelated_local_changes() {
+        let dir = setup_test_dir("keep");
+        let store = dir.join(".ogit");
+
+        fs::write(dir.join("a.txt"), "first").unwrap();
+        fs::write(dir.join("b.txt"), "same").unwrap();
+        commit_all(&dir, "first");
+        create_branch(&store, "old", None).unwrap();
+        fs::write(dir.join("a.txt"), "second").unwrap();
+        commit_all(&dir, "second");
+
+        // b.txt è uguale nei due commit: la modifica locale sopravvive
+        fs::write(dir.join("b.txt"), "local edit").unwrap();
+        checkout(&store, &dir, "old").unwrap();
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "local edit");
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "first");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
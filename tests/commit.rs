@@ -1,13 +1,18 @@
+mod common;
+
 #[cfg(test)]
 mod tests {
-    use ogit::object::{Commit, OObjectId};
+    use ogit::object::{format_tz, parse_date, Commit, Signature};
+
+    use super::common::fake_id;
 
         #[test]
     fn test_commit_serialize_deserialize_with_parent() {
         let original = Commit {
-            tree: OObjectId("abc123".to_string()),
-            parent: Some(OObjectId("def456".to_string())),
-            author: "Test Author".to_string(),
+            tree: fake_id("abc123"),
+            parents: vec![fake_id("def456")],
+            author: Signature::new("Test Author", "test@example.com", 1_700_000_000, 60),
+            committer: Signature::new("Test Committer", "ci@example.com", 1_700_000_100, -300),
             message: "Initial commit".to_string(),
         };
         
@@ -20,9 +25,10 @@ mod tests {
     #[test]
     fn test_commit_serialize_deserialize_without_parent() {
         let original = Commit {
-            tree: OObjectId("abc123".to_string()),
-            parent: None,
-            author: "Test Author".to_string(),
+            tree: fake_id("abc123"),
+            parents: vec![],
+            author: Signature::new("Test Author", "test@example.com", 1_700_000_000, 60),
+            committer: Signature::new("Test Committer", "ci@example.com", 1_700_000_100, -300),
             message: "First commit".to_string(),
         };
         
@@ -31,4 +37,69 @@ mod tests {
         
         assert_eq!(original, deserialized);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_commit_multiline_message_roundtrip() {
+        let original = Commit {
+            tree: fake_id("abc123"),
+            parents: vec![],
+            author: Signature::new("Test Author", "test@example.com", 0, 0),
+            committer: Signature::new("Test Author", "test@example.com", 0, 0),
+            message: "Subject\n\nBody line 1\nparent not-a-header\n".to_string(),
+        };
+
+        let serialized = original.serialize();
+        let text = String::from_utf8(serialized.clone()).unwrap();
+        assert!(text.contains("\ncommitter Test Author <test@example.com> 0 +0000\n\nSubject\n"));
+
+        assert_eq!(Commit::deserialize(&serialized).unwrap(), original);
+    }
+
+    #[test]
+    fn test_commit_deserialize_legacy_format() {
+        let legacy = format!(
+            "tree {}\nparent {}\nauthor Default Author\nmessage Old commit",
+            fake_id("abc123"),
+            fake_id("def456")
+        );
+        let commit = Commit::deserialize(legacy.as_bytes()).unwrap();
+
+        assert_eq!(commit.author, Signature::new("Default Author", "", 0, 0));
+        assert_eq!(commit.committer, commit.author);
+        assert_eq!(commit.message, "Old commit");
+        assert_eq!(commit.parents, vec![fake_id("def456")]);
+    }
+
+    #[test]
+    fn test_signature_parse_and_format() {
+        let sig = Signature::parse("Mario Rossi <mario@example.com> 1700000000 +0100").unwrap();
+        assert_eq!(sig, Signature::new("Mario Rossi", "mario@example.com", 1_700_000_000, 60));
+        assert_eq!(sig.to_ident(), "Mario Rossi <mario@example.com> 1700000000 +0100");
+        assert_eq!(sig.format_date(), "Tue Nov 14 23:13:20 2023 +0100");
+
+        assert!(Signature::parse("Mario Rossi").is_err());
+        assert_eq!(format_tz(-330), "-0530");
+        assert_eq!(parse_date("@0 -0530").unwrap(), (0, -330));
+        assert!(parse_date("0 0100").is_err());
+    }
+
+    #[test]
+    fn test_merge_commit_keeps_parent_order() {
+        let original = Commit {
+            tree: fake_id("abc123"),
+            parents: vec![fake_id("aaa111"), fake_id("bbb222")],
+            author: Signature::new("Test Author", "test@example.com", 0, 0),
+            committer: Signature::new("Test Author", "test@example.com", 0, 0),
+            message: "Merge branch 'topic'".to_string(),
+        };
+
+        let serialized = original.serialize();
+        let text = String::from_utf8(serialized.clone()).unwrap();
+        assert!(text.contains(&format!("parent {}\nparent {}\n", fake_id("aaa111"), fake_id("bbb222"))));
+
+        let deserialized = Commit::deserialize(&serialized).unwrap();
+        assert!(deserialized.is_merge());
+        assert_eq!(deserialized.first_parent(), Some(&fake_id("aaa111")));
+        assert_eq!(deserialized, original);
+    }
+}
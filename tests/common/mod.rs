@@ -0,0 +1,30 @@
+//! Fixture condivise dai test di integrazione (`mod common;` in ogni file).
+//!
+//! Ogni file sotto `tests/` è un crate a sé e usa solo alcune di queste
+//! funzioni: le altre risulterebbero codice morto.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use ogit::object::{OObject, OObjectId, OObjectType, Signature, TreeEntry};
+use ogit::store::{create_commit, write_object, write_tree};
+
+/// Commit con il solo file `a.txt` che contiene `content`; restituisce (commit, tree, blob).
+pub fn commit_file(store: &Path, parents: &[OObjectId], content: &str) -> (OObjectId, OObjectId, OObjectId) {
+    let blob = write_object(store, &OObject::new_blob(content.as_bytes().to_vec())).unwrap();
+    let tree = write_tree(store, &[TreeEntry::new(OObjectType::Blob, blob.clone(), "a.txt".to_string())]).unwrap();
+    let author = Signature::new("Author", "author@example.com", 0, 0);
+    let commit = create_commit(store, &tree, parents, &author, content).unwrap();
+    (commit, tree, blob)
+}
+
+/// Path del file loose di `id` (`objects/aa/bbccdd...`).
+pub fn loose_path(store: &Path, id: &OObjectId) -> PathBuf {
+    let hex = id.to_hex();
+    store.join("objects").join(&hex[..2]).join(&hex[2..])
+}
+
+/// Id fittizio ma valido: `prefix` completato con zeri fino a 64 hex.
+pub fn fake_id(prefix: &str) -> OObjectId {
+    format!("{prefix:0<64}").parse().unwrap()
+}
@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use ogit::compression::{compress, decompress, is_compressed};
+    use ogit::config::Config;
+    use ogit::object::OObject;
+    use ogit::store::{migrate_objects, read_object, write_object, COMPRESSION_KEY};
+
+    fn setup_test_dir(name: &str, compression: Option<bool>) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_compression_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("objects")).unwrap();
+        if let Some(enabled) = compression {
+            set_compression(&dir, enabled);
+        }
+        dir
+    }
+
+    fn set_compression(store: &Path, enabled: bool) {
+        let mut config = Config::load(store).unwrap();
+        config.set(COMPRESSION_KEY, if enabled { "true" } else { "false" }).unwrap();
+        config.save(store).unwrap();
+    }
+
+    fn stored_bytes(store: &Path, hash: &str) -> Vec<u8> {
+        let (subdir, filename) = hash.split_at(2);
+        fs::read(store.join("objects").join(subdir).join(filename)).unwrap()
+    }
+
+    #[test]
+    fn test_compress_roundtrip() {
+        let data = b"blob 11\0hello world".repeat(10);
+        let packed = compress(&data).unwrap();
+
+        assert!(is_compressed(&packed));
+        assert!(!is_compressed(&data));
+        assert!(packed.len() < data.len());
+        assert_eq!(decompress(&packed).unwrap(), data);
+    }
+
+    #[test]
+    fn test_config_parse_and_serialize() {
+        let config = Config::parse("# comment\n[core]\n\tcompression = yes\n[user]\nname = Mario Rossi\n").unwrap();
+
+        assert_eq!(config.get_bool("core.compression").unwrap(), Some(true));
+        assert_eq!(config.get("user.name"), Some("Mario Rossi"));
+        assert_eq!(config.get("user.email"), None);
+        assert_eq!(Config::parse(&config.serialize()).unwrap(), config);
+    }
+
+    #[test]
+    fn test_compressed_write_then_read() {
+        let store = setup_test_dir("enabled", Some(true));
+        let obj = OObject::new_blob(b"some text ".repeat(100));
+
+        let id = write_object(&store, &obj).unwrap();
+        let on_disk = stored_bytes(&store, &id.to_hex());
+
+        assert!(is_compressed(&on_disk));
+        assert!(on_disk.len() < obj.serialize().len());
+        assert_eq!(read_object(&store, &id).unwrap(), obj);
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_hash_independent_of_compression() {
+        let plain = setup_test_dir("plain", Some(false));
+        let packed = setup_test_dir("packed", Some(true));
+        let obj = OObject::new_blob(b"same content".to_vec());
+
+        assert_eq!(write_object(&plain, &obj).unwrap(), write_object(&packed, &obj).unwrap());
+
+        fs::remove_dir_all(&plain).unwrap();
+        fs::remove_dir_all(&packed).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_existing_objects() {
+        let store = setup_test_dir("migrate", None);
+        let obj = OObject::new_blob(b"legacy object".to_vec());
+        let id = write_object(&store, &obj).unwrap();
+        assert!(!is_compressed(&stored_bytes(&store, &id.to_hex())));
+
+        set_compression(&store, true);
+        assert_eq!(migrate_objects(&store).unwrap(), 1);
+        assert!(is_compressed(&stored_bytes(&store, &id.to_hex())));
+        assert_eq!(read_object(&store, &id).unwrap(), obj);
+
+        // Seconda esecuzione: niente da fare
+        assert_eq!(migrate_objects(&store).unwrap(), 0);
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use ogit::delta::{apply_delta, create_delta};
+    use ogit::error::OgitError;
+
+    #[test]
+    fn test_roundtrip_small_edit() {
+        let base: Vec<u8> = (0..500).flat_map(|i| format!("row {i}\n").into_bytes()).collect();
+        let mut target = base.clone();
+        target.splice(1000..1010, b"inserted text".iter().copied());
+        target.extend_from_slice(b"trailer\n");
+
+        let delta = create_delta(&base, &target).unwrap();
+        assert!(delta.len() < 64, "delta of {} bytes", delta.len());
+        assert_eq!(apply_delta(&base, &delta).unwrap(), target);
+    }
+
+    #[test]
+    fn test_roundtrip_edge_cases() {
+        let long: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let cases: [(&[u8], &[u8]); 5] = [
+            (b"", b""),
+            (b"", b"only inserts"),
+            (b"everything removed", b""),
+            (b"short", b"short"),
+            (&long, &long[3..]),
+        ];
+        for (base, target) in cases {
+            let delta = create_delta(base, target).unwrap();
+            assert_eq!(apply_delta(base, &delta).unwrap(), target);
+        }
+    }
+
+    #[test]
+    fn test_corrupt_delta_is_rejected() {
+        let base = b"the quick brown fox jumps over the lazy dog".repeat(4);
+        let delta = create_delta(&base, &base).unwrap();
+
+        let err = apply_delta(b"different base", &delta).unwrap_err();
+        assert!(matches!(err, OgitError::Corrupt { .. }));
+        assert!(err.to_string().contains("base size"));
+        assert!(apply_delta(&base, &delta[..delta.len() - 1]).is_err());
+        // Copia oltre la fine della base
+        let mut bad = create_delta(&base, b"").unwrap();
+        bad.extend_from_slice(&[0x91, 0xff, 0x10]);
+        assert!(apply_delta(&base, &bad).unwrap_err().to_string().contains("outside the base"));
+    }
+}
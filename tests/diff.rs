@@ -0,0 +1,290 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use ogit::diff::{
+        detect_renames, diff_files, diff_lines, diff_trees, format_patch, hunks, index_files, matching_lines,
+        similarity, unified_diff, worktree_files, LineTag, RenameOptions,
+    };
+    use ogit::index::{add_paths, write_tree_from_index, Index};
+    use ogit::object::FileMode;
+    use ogit::object_store::LooseStore;
+    use ogit::status::{Change, ChangeKind};
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_diff_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".ogit/objects")).unwrap();
+        fs::create_dir_all(dir.join(".ogit/refs/heads")).unwrap();
+        fs::write(dir.join(".ogit/HEAD"), "ref: refs/heads/master\n").unwrap();
+        dir
+    }
+
+    fn numbered(lines: std::ops::RangeInclusive<u32>) -> String {
+        lines.map(|i| format!("{i}\n")).collect()
+    }
+
+    #[test]
+    fn test_matching_lines_is_longest_common_subsequence() {
+        // Esempio classico dell'articolo di Myers: LCS di lunghezza 4
+        let a: Vec<char> = "ABCABBA".chars().collect();
+        let b: Vec<char> = "CBABAC".chars().collect();
+        let pairs = matching_lines(&a, &b);
+
+        assert_eq!(pairs.len(), 4);
+        assert!(pairs.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+        assert!(pairs.iter().all(|&(i, j)| a[i] == b[j]));
+
+        assert!(matching_lines::<char>(&[], &[]).is_empty());
+        assert_eq!(matching_lines(&['x'], &['x']), vec![(0, 0)]);
+    }
+
+    /// Lunghezza della LCS con la programmazione dinamica classica, come riferimento.
+    fn lcs_len(a: &[u8], b: &[u8]) -> usize {
+        let mut row = vec![0; b.len() + 1];
+        for x in a {
+            let mut diag = 0;
+            for (j, y) in b.iter().enumerate() {
+                let up = row[j + 1];
+                row[j + 1] = if x == y { diag + 1 } else { up.max(row[j]) };
+                diag = up;
+            }
+        }
+        row[b.len()]
+    }
+
+    #[test]
+    fn test_matching_lines_is_optimal_on_random_inputs() {
+        // Generatore lineare congruenziale: sequenze riproducibili senza dipendenze
+        let mut seed: u64 = 42;
+        let mut next = |bound: u64| {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+            (seed >> 33) % bound
+        };
+        for _ in 0..500 {
+            let a: Vec<u8> = (0..next(20)).map(|_| next(4) as u8).collect();
+            let b: Vec<u8> = (0..next(20)).map(|_| next(4) as u8).collect();
+            let pairs = matching_lines(&a, &b);
+
+            assert_eq!(pairs.len(), lcs_len(&a, &b), "{a:?} {b:?}");
+            assert!(pairs.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+            assert!(pairs.iter().all(|&(i, j)| a[i] == b[j]));
+        }
+    }
+
+    #[test]
+    fn test_full_rewrite_of_large_file() {
+        // Nessuna riga in comune: D = N + M, il caso che con i fronti salvati esauriva la memoria
+        let old: String = (0..5_000).map(|i| format!("old {i}\n")).collect();
+        let new: String = (0..5_000).map(|i| format!("new {i}\n")).collect();
+        let lines = diff_lines(old.as_bytes(), new.as_bytes());
+        assert_eq!(lines.len(), 10_000);
+        assert!(lines[..5_000].iter().all(|l| l.tag == LineTag::Removed));
+    }
+
+    #[test]
+    fn test_diff_lines_tags() {
+        let lines = diff_lines(b"a\nb\nc\nd\n", b"a\nc\nd\ne\n");
+        let tags: Vec<(char, &[u8])> = lines.iter().map(|l| (l.tag.prefix(), l.text)).collect();
+        assert_eq!(
+            tags,
+            vec![(' ', &b"a\n"[..]), ('-', b"b\n"), (' ', b"c\n"), (' ', b"d\n"), ('+', b"e\n")]
+        );
+        assert!(diff_lines(b"same\n", b"same\n").iter().all(|l| l.tag == LineTag::Context));
+    }
+
+    #[test]
+    fn test_hunks_split_and_merge_by_context() {
+        let old = numbered(1..=20);
+        let new = old.replace("\n3\n", "\nthree\n").replace("\n15\n", "\nfifteen\n");
+
+        let split = hunks(old.as_bytes(), new.as_bytes(), 3);
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[0].header(), "@@ -1,6 +1,6 @@");
+        assert_eq!(split[1].header(), "@@ -12,7 +12,7 @@");
+
+        // Con più contesto i due hunk si uniscono
+        let merged = hunks(old.as_bytes(), new.as_bytes(), 6);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].header(), "@@ -1,20 +1,20 @@");
+    }
+
+    #[test]
+    fn test_unified_diff_output() {
+        assert_eq!(unified_diff("a/f", "b/f", b"x\n", b"x\n", 3), "");
+
+        let out = unified_diff("a/f", "b/f", b"x\ny", b"x\nz", 3);
+        assert_eq!(
+            out,
+            "--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n x\n-y\n\\ No newline at end of file\n+z\n\\ No newline at end of file\n"
+        );
+
+        let out = unified_diff("/dev/null", "b/f", b"", b"new\n", 3);
+        assert_eq!(out, "--- /dev/null\n+++ b/f\n@@ -0,0 +1 @@\n+new\n");
+
+        assert_eq!(unified_diff("a/f", "b/f", b"\0a", b"\0b", 3), "Binary files a/f and b/f differ\n");
+    }
+
+    #[test]
+    fn test_diff_trees_recurses_into_changed_dirs() {
+        let dir = setup_test_dir("trees");
+        let store = dir.join(".ogit");
+        let objects = LooseStore::open(&store).unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("docs")).unwrap();
+        fs::write(dir.join("src/lib.rs"), "lib\n").unwrap();
+        fs::write(dir.join("src/old.rs"), "old\n").unwrap();
+        fs::write(dir.join("docs/readme"), "docs\n").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+        let before = write_tree_from_index(&store, &Index::load(&store).unwrap()).unwrap();
+
+        fs::write(dir.join("src/lib.rs"), "lib v2\n").unwrap();
+        fs::remove_file(dir.join("src/old.rs")).unwrap();
+        fs::write(dir.join("src/new.rs"), "new\n").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+        let after = write_tree_from_index(&store, &Index::load(&store).unwrap()).unwrap();
+
+        let changes = diff_trees(&objects, Some(&before), Some(&after)).unwrap();
+        let summary: Vec<(ChangeKind, &str)> = changes.iter().map(|c| (c.kind, c.path.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (ChangeKind::Modified, "src/lib.rs"),
+                (ChangeKind::Added, "src/new.rs"),
+                (ChangeKind::Deleted, "src/old.rs"),
+            ]
+        );
+
+        assert!(diff_trees(&objects, Some(&after), Some(&after)).unwrap().is_empty());
+        assert_eq!(diff_trees(&objects, None, Some(&after)).unwrap().len(), 3);
+
+        let patch = format_patch(&objects, &changes[0], None, 3).unwrap();
+        assert!(patch.starts_with("diff --git a/src/lib.rs b/src/lib.rs\nindex "));
+        assert!(patch.ends_with("@@ -1 +1 @@\n-lib\n+lib v2\n"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_index_against_worktree() {
+        let dir = setup_test_dir("worktree");
+        let store = dir.join(".ogit");
+        let objects = LooseStore::open(&store).unwrap();
+        fs::write(dir.join("a.txt"), "one\ntwo\n").unwrap();
+        fs::write(dir.join("b.txt"), "bye\n").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+
+        fs::write(dir.join("a.txt"), "one\n2\n").unwrap();
+        fs::remove_file(dir.join("b.txt")).unwrap();
+        fs::write(dir.join("untracked.txt"), "ignored by diff\n").unwrap();
+
+        let old = index_files(&store).unwrap();
+        let new = worktree_files(&store, &dir, old.keys()).unwrap();
+        let changes = diff_files(&old, &new);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[1].kind, ChangeKind::Deleted);
+        assert_eq!(changes[1].old.as_ref().unwrap().1, FileMode::Regular);
+
+        let patch = format_patch(&objects, &changes[0], Some(&dir), 3).unwrap();
+        assert!(patch.ends_with("--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n one\n-two\n+2\n"));
+
+        let patch = format_patch(&objects, &changes[1], Some(&dir), 3).unwrap();
+        assert!(patch.contains("deleted file mode 100644\n"));
+        assert!(patch.ends_with("--- a/b.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-bye\n"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity(b"a\nb\n", b"a\nb\n"), 100);
+        assert_eq!(similarity(b"a\nb\nc\nd\n", b"a\nb\nc\nX\n"), 75);
+        assert_eq!(similarity(b"a\n", b"b\n"), 0);
+        assert_eq!(similarity(b"", b""), 0);
+    }
+
+    #[test]
+    fn test_detect_renames_and_copies() {
+        let dir = setup_test_dir("renames");
+        let store = dir.join(".ogit");
+        let objects = LooseStore::open(&store).unwrap();
+        let body = numbered(1..=10);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("exact.txt"), "same content\n").unwrap();
+        fs::write(dir.join("src/edited.rs"), numbered(11..=20)).unwrap();
+        fs::write(dir.join("base.txt"), &body).unwrap();
+        fs::write(dir.join("empty"), "").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+        let before = write_tree_from_index(&store, &Index::load(&store).unwrap()).unwrap();
+
+        for path in ["exact.txt", "src/edited.rs", "empty"] {
+            fs::remove_file(dir.join(path)).unwrap();
+        }
+        fs::write(dir.join("moved.txt"), "same content\n").unwrap();
+        fs::write(dir.join("edited.rs"), numbered(11..=20).replace("\n20\n", "\ntwenty\n")).unwrap();
+        fs::write(dir.join("base.txt"), body.replace("1\n", "one\n")).unwrap();
+        fs::write(dir.join("copy.txt"), &body).unwrap();
+        fs::write(dir.join("empty2"), "").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+        let after = write_tree_from_index(&store, &Index::load(&store).unwrap()).unwrap();
+
+        let summary = |options: &RenameOptions| -> Vec<(ChangeKind, String, u8)> {
+            let changes = diff_trees(&objects, Some(&before), Some(&after)).unwrap();
+            detect_renames(&objects, changes, None, options)
+                .unwrap()
+                .iter()
+                .map(|c| (c.kind, Change::from(c.clone()).display_path(), c.similarity))
+                .collect()
+        };
+
+        assert_eq!(
+            summary(&RenameOptions::default()),
+            vec![
+                (ChangeKind::Modified, "base.txt".to_string(), 0),
+                (ChangeKind::Added, "copy.txt".to_string(), 0),
+                (ChangeKind::Renamed, "src/edited.rs -> edited.rs".to_string(), 79),
+                (ChangeKind::Deleted, "empty".to_string(), 0),
+                (ChangeKind::Added, "empty2".to_string(), 0),
+                (ChangeKind::Renamed, "exact.txt -> moved.txt".to_string(), 100),
+            ]
+        );
+
+        // Soglia più alta: resta solo la rinomina esatta
+        let strict = RenameOptions { threshold: 95, ..RenameOptions::default() };
+        assert_eq!(summary(&strict).iter().filter(|c| c.0 == ChangeKind::Renamed).count(), 1);
+
+        // Con le copie, copy.txt viene dal file modificato base.txt
+        let copies = RenameOptions { copies: true, ..RenameOptions::default() };
+        assert!(summary(&copies).contains(&(ChangeKind::Copied, "base.txt -> copy.txt".to_string(), 100)));
+
+        let none = RenameOptions { renames: false, ..RenameOptions::default() };
+        assert!(summary(&none).iter().all(|c| c.0 != ChangeKind::Renamed));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rename_patch_header() {
+        let dir = setup_test_dir("rename_patch");
+        let store = dir.join(".ogit");
+        let objects = LooseStore::open(&store).unwrap();
+        fs::write(dir.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+        let before = write_tree_from_index(&store, &Index::load(&store).unwrap()).unwrap();
+
+        fs::remove_file(dir.join("a.txt")).unwrap();
+        fs::write(dir.join("b.txt"), "one\ntwo\nthree\nfour\n").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+        let after = write_tree_from_index(&store, &Index::load(&store).unwrap()).unwrap();
+
+        let changes = diff_trees(&objects, Some(&before), Some(&after)).unwrap();
+        let changes = detect_renames(&objects, changes, None, &RenameOptions::default()).unwrap();
+        let patch = format_patch(&objects, &changes[0], None, 3).unwrap();
+        assert!(patch.starts_with("diff --git a/a.txt b/b.txt\nsimilarity index 73%\nrename from a.txt\nrename to b.txt\nindex "));
+        assert!(patch.ends_with("--- a/a.txt\n+++ b/b.txt\n@@ -1,3 +1,4 @@\n one\n two\n three\n+four\n"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use ogit::object::{FileMode, OObjectId, OObjectType, ObjectFormat, TreeEntry};
+    #[cfg(unix)]
+    use ogit::object::Signature;
+
+    use super::common::fake_id;
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_modes_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".ogit/objects")).unwrap();
+        fs::create_dir_all(dir.join(".ogit/refs/heads")).unwrap();
+        fs::write(dir.join(".ogit/HEAD"), "ref: refs/heads/master\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_default_mode_keeps_legacy_line() {
+        let entry = TreeEntry::new(OObjectType::Blob, fake_id("abc123"), "a.txt".to_string());
+        assert_eq!(entry.mode, FileMode::Regular);
+        assert_eq!(entry.to_line(), format!("blob {} a.txt", fake_id("abc123")));
+    }
+
+    #[test]
+    fn test_mode_roundtrip_in_tree() {
+        let entries = vec![
+            TreeEntry::with_mode(FileMode::Executable, fake_id("aaa111"), "run.sh".to_string()),
+            TreeEntry::with_mode(FileMode::Symlink, fake_id("bbb222"), "link".to_string()),
+            TreeEntry::new(OObjectType::Tree, fake_id("ccc333"), "src".to_string()),
+        ];
+        assert_eq!(entries[0].to_line(), format!("100755 blob {} run.sh", fake_id("aaa111")));
+
+        let parsed = TreeEntry::deserialize_tree(&TreeEntry::serialize_tree(&entries)).unwrap();
+        assert_eq!(parsed[0].name, "link");
+        assert_eq!(parsed[0].mode, FileMode::Symlink);
+        assert_eq!(parsed[1].mode, FileMode::Executable);
+        assert_eq!(parsed[2].mode, FileMode::Directory);
+    }
+
+    #[test]
+    fn test_mode_must_match_kind() {
+        let line = format!("40000 blob {} a.txt\n", fake_id("aaa111"));
+        assert!(TreeEntry::deserialize_tree(line.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_git_tree_keeps_modes() {
+        let hash = "ce013625030ba8dba906f756967f9e9ca394464a".parse::<OObjectId>().unwrap();
+        let entries = vec![
+            TreeEntry::with_mode(FileMode::Executable, hash.clone(), "run.sh".to_string()),
+            TreeEntry::with_mode(FileMode::Symlink, hash, "link".to_string()),
+        ];
+
+        let bytes = TreeEntry::serialize_tree_as(&entries, ObjectFormat::Git).unwrap();
+        assert!(bytes.starts_with(b"120000 link\0"));
+
+        let parsed = TreeEntry::deserialize_tree_as(&bytes, ObjectFormat::Git).unwrap();
+        assert_eq!(parsed[0].mode, FileMode::Symlink);
+        assert_eq!(parsed[1].mode, FileMode::Executable);
+    }
+
+    #[cfg(unix)]
+    fn author() -> Signature {
+        Signature::new("Author", "author@example.com", 0, 0)
+    }
+
+    #[cfg(unix)]
+    fn commit_all(dir: &Path, message: &str) -> OObjectId {
+        use ogit::refs::{resolve_ref, update_ref, HEAD};
+        use ogit::store::create_commit;
+        use ogit::tree::build_tree_from_dir;
+
+        let store = dir.join(".ogit");
+        let tree = build_tree_from_dir(&store, dir).unwrap();
+        let parent = resolve_ref(&store, HEAD).unwrap();
+        let id = create_commit(&store, &tree, parent.as_slice(), &author(), message).unwrap();
+        update_ref(&store, HEAD, &id).unwrap();
+        id
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_build_tree_records_exec_and_symlink() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        use ogit::store::read_commit;
+        use ogit::tree::flatten_tree_modes;
+
+        let dir = setup_test_dir("build");
+        let store = dir.join(".ogit");
+        fs::write(dir.join("run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(dir.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        symlink("a.txt", dir.join("link")).unwrap();
+
+        let id = commit_all(&dir, "modes");
+        let files = flatten_tree_modes(&store, &read_commit(&store, &id).unwrap().tree).unwrap();
+
+        assert_eq!(files["a.txt"].1, FileMode::Regular);
+        assert_eq!(files["run.sh"].1, FileMode::Executable);
+        assert_eq!(files["link"].1, FileMode::Symlink);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_checkout_restores_exec_and_symlink() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        use ogit::branch::create_branch;
+        use ogit::checkout::checkout;
+        use ogit::status::status;
+
+        let dir = setup_test_dir("checkout");
+        let store = dir.join(".ogit");
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        commit_all(&dir, "plain");
+        create_branch(&store, "plain", None).unwrap();
+
+        fs::write(dir.join("run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(dir.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        symlink("a.txt", dir.join("link")).unwrap();
+        commit_all(&dir, "modes");
+        create_branch(&store, "modes", None).unwrap();
+
+        checkout(&store, &dir, "plain").unwrap();
+        assert!(fs::symlink_metadata(dir.join("link")).is_err());
+        assert!(!dir.join("run.sh").exists());
+
+        checkout(&store, &dir, "modes").unwrap();
+        let exec = fs::metadata(dir.join("run.sh")).unwrap().permissions().mode();
+        assert_ne!(exec & 0o111, 0);
+        assert_eq!(fs::read_link(dir.join("link")).unwrap(), Path::new("a.txt"));
+        assert!(status(&store, &dir).unwrap().is_clean());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_status_reports_mode_change() {
+        use std::os::unix::fs::PermissionsExt;
+
+        use ogit::index::add_paths;
+        use ogit::status::{status, ChangeKind};
+
+        let dir = setup_test_dir("status");
+        let store = dir.join(".ogit");
+        fs::write(dir.join("run.sh"), "#!/bin/sh\n").unwrap();
+        add_paths(&store, &dir, &["run.sh".to_string()], false).unwrap();
+        commit_all(&dir, "plain");
+
+        fs::set_permissions(dir.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        let st = status(&store, &dir).unwrap();
+        assert_eq!(st.unstaged.len(), 1);
+        assert_eq!(st.unstaged[0].kind, ChangeKind::Modified);
+
+        add_paths(&store, &dir, &["run.sh".to_string()], false).unwrap();
+        let st = status(&store, &dir).unwrap();
+        assert!(st.unstaged.is_empty());
+        assert_eq!(st.staged[0].path, "run.sh");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
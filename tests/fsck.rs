@@ -0,0 +1,165 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use ogit::fsck::{fsck, FsckIssue};
+    use ogit::initialize_repository::init_repo_at;
+    use ogit::object::{OObject, OObjectType, ObjectFormat};
+    use ogit::pack::{repack, PackOptions, PACK_DIR};
+    use ogit::refs::{update_ref, HEAD};
+    use ogit::store::write_object;
+
+    use super::common::{commit_file, loose_path};
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_fsck_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        init_repo_at(&dir.join(".ogit"), ObjectFormat::Ogit).unwrap();
+        dir
+    }
+
+    fn pack_file(store: &Path) -> PathBuf {
+        fs::read_dir(store.join(PACK_DIR))
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .find(|p| p.extension().is_some_and(|ext| ext == "pack"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_clean_repository() {
+        let dir = setup_test_dir("clean");
+        let store = dir.join(".ogit");
+        let (first, ..) = commit_file(&store, &[], "one");
+        let (second, ..) = commit_file(&store, &[first], "two");
+        update_ref(&store, HEAD, &second).unwrap();
+
+        let report = fsck(&store).unwrap();
+        assert_eq!(report.objects, 6);
+        assert_eq!(report.issues, vec![]);
+
+        // Gli oggetti nei pack si verificano allo stesso modo
+        repack(&store, &PackOptions::default()).unwrap();
+        let report = fsck(&store).unwrap();
+        assert_eq!(report.objects, 6);
+        assert!(report.issues.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_pack_checksum_mismatch() {
+        let dir = setup_test_dir("pack_checksum");
+        let store = dir.join(".ogit");
+        let (commit, ..) = commit_file(&store, &[], "one");
+        update_ref(&store, HEAD, &commit).unwrap();
+        repack(&store, &PackOptions::default()).unwrap();
+        let pack = pack_file(&store);
+
+        // Un byte del checksum in coda: gli oggetti restano leggibili
+        let mut bytes = fs::read(&pack).unwrap();
+        *bytes.last_mut().unwrap() ^= 0xff;
+        fs::write(&pack, bytes).unwrap();
+
+        let report = fsck(&store).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.issues.len(), 1);
+        match &report.issues[0] {
+            FsckIssue::BadPack { path, reason } => {
+                assert_eq!(path, &pack.display().to_string());
+                assert!(reason.contains("checksum mismatch"), "{reason}");
+            }
+            other => panic!("unexpected issue: {other}"),
+        }
+        assert!(report.issues[0].to_string().starts_with("bad-pack - - "));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_dangling_and_unreachable() {
+        let dir = setup_test_dir("dangling");
+        let store = dir.join(".ogit");
+        let (first, ..) = commit_file(&store, &[], "one");
+        let (lost, lost_tree, lost_blob) = commit_file(&store, std::slice::from_ref(&first), "two");
+        update_ref(&store, HEAD, &first).unwrap();
+        let stray = write_object(&store, &OObject::new_blob(b"stray".to_vec())).unwrap();
+
+        let report = fsck(&store).unwrap();
+        assert!(report.is_ok());
+        let mut issues: Vec<String> = report.issues.iter().map(ToString::to_string).collect();
+        issues.sort();
+        let mut expected = vec![
+            format!("dangling commit {lost}"),
+            format!("dangling blob {stray}"),
+            format!("unreachable tree {lost_tree}"),
+            format!("unreachable blob {lost_blob}"),
+        ];
+        expected.sort();
+        assert_eq!(issues, expected);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_and_corrupt_objects() {
+        let dir = setup_test_dir("broken");
+        let store = dir.join(".ogit");
+        let (first, first_tree, first_blob) = commit_file(&store, &[], "one");
+        let (second, _, second_blob) = commit_file(&store, std::slice::from_ref(&first), "two");
+        update_ref(&store, HEAD, &second).unwrap();
+
+        // Blob cancellato, blob sostituito da un altro contenuto, commit troncato
+        fs::remove_file(loose_path(&store, &first_blob)).unwrap();
+        let other = OObject::new_blob(b"tampered".to_vec());
+        fs::write(loose_path(&store, &second_blob), other.serialize()).unwrap();
+        let truncated = OObject::new_commit(b"tree".to_vec()).serialize();
+        fs::write(loose_path(&store, &first), &truncated[..truncated.len() - 2]).unwrap();
+
+        let report = fsck(&store).unwrap();
+        assert!(!report.is_ok());
+        assert!(report.issues.contains(&FsckIssue::Missing {
+            id: first_blob,
+            kind: OObjectType::Blob,
+            referenced_by: first_tree.to_string(),
+        }));
+        assert!(report.issues.contains(&FsckIssue::HashMismatch {
+            id: second_blob,
+            kind: OObjectType::Blob,
+            actual: ObjectFormat::Ogit.hash_id(&other.serialize()),
+        }));
+        assert!(report.issues.iter().any(|issue| matches!(issue, FsckIssue::Corrupt { id, .. } if *id == first)));
+        // Il commit rotto non è anche "missing" per il figlio
+        assert!(!report.issues.iter().any(|issue| matches!(issue, FsckIssue::Missing { id, .. } if *id == first)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_bad_refs_and_types() {
+        let dir = setup_test_dir("refs");
+        let store = dir.join(".ogit");
+        let (commit, tree, _) = commit_file(&store, &[], "one");
+        update_ref(&store, HEAD, &commit).unwrap();
+        fs::write(store.join("refs/heads/garbage"), "not a hash\n").unwrap();
+        fs::write(store.join("refs/heads/tree"), format!("{tree}\n")).unwrap();
+
+        let report = fsck(&store).unwrap();
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            FsckIssue::BadRef { name, .. } if name == "refs/heads/garbage"
+        )));
+        assert!(report.issues.contains(&FsckIssue::BadType {
+            id: tree,
+            expected: OObjectType::Commit,
+            referenced_by: "refs/heads/tree".to_string(),
+        }));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    use ogit::error::OgitError;
+    use ogit::fsck::fsck;
+    use ogit::gc::{gc, parse_expire, prune, reachable_objects, PruneOptions, DEFAULT_PRUNE_EXPIRE};
+    use ogit::initialize_repository::init_repo_at;
+    use ogit::object::{OObject, ObjectFormat};
+    use ogit::object_store::{LooseStore, ObjectStore};
+    use ogit::pack::{pack_indexes, repack, PackOptions};
+    use ogit::refs::{update_ref, HEAD};
+    use ogit::store::{list_objects, write_blob_stream, write_object};
+
+    use super::common::{commit_file, loose_path};
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_gc_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        init_repo_at(&dir.join(".ogit"), ObjectFormat::Ogit).unwrap();
+        dir
+    }
+
+    fn age(path: &Path, by: Duration) {
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - by).unwrap();
+    }
+
+    fn options(expire: Option<Duration>, dry_run: bool) -> PruneOptions {
+        PruneOptions { expire, dry_run }
+    }
+
+    #[test]
+    fn test_parse_expire() {
+        assert_eq!(parse_expire("now").unwrap(), Some(Duration::ZERO));
+        assert_eq!(parse_expire("never").unwrap(), None);
+        assert_eq!(parse_expire("90").unwrap(), Some(Duration::from_secs(90)));
+        assert_eq!(parse_expire("3h").unwrap(), Some(3 * HOUR));
+        assert_eq!(parse_expire("2w").unwrap(), Some(DEFAULT_PRUNE_EXPIRE));
+        for invalid in ["", "h", "2 weeks", "-1d", "99999999999999999999w"] {
+            assert!(matches!(parse_expire(invalid), Err(OgitError::InvalidInput(_))), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_prune_respects_grace_period_and_dry_run() {
+        let dir = setup_test_dir("prune");
+        let store = dir.join(".ogit");
+        let (first, ..) = commit_file(&store, &[], "one");
+        let (lost, lost_tree, lost_blob) = commit_file(&store, std::slice::from_ref(&first), "two");
+        update_ref(&store, HEAD, &first).unwrap();
+        let stray = write_object(&store, &OObject::new_blob(b"stray".to_vec())).unwrap();
+
+        // Appena scritti: il periodo di grazia li protegge
+        let report = prune(&store, &PruneOptions::default()).unwrap();
+        assert_eq!(report.pruned, vec![]);
+        assert_eq!(report.kept, 4);
+
+        // Solo quelli più vecchi del periodo di grazia
+        age(&loose_path(&store, &stray), 2 * HOUR);
+        age(&loose_path(&store, &lost), 2 * HOUR);
+        let report = prune(&store, &options(Some(HOUR), true)).unwrap();
+        let mut expected = vec![stray.clone(), lost.clone()];
+        expected.sort();
+        assert_eq!(report.pruned, expected);
+        assert_eq!(report.kept, 2);
+        assert_eq!(list_objects(&store).unwrap().len(), 7, "dry run must not delete");
+
+        prune(&store, &options(Some(HOUR), false)).unwrap();
+        let remaining = list_objects(&store).unwrap();
+        assert!(!remaining.contains(&stray) && !remaining.contains(&lost));
+        assert!(remaining.contains(&lost_tree) && remaining.contains(&lost_blob));
+
+        // `now` elimina tutto ciò che non è raggiungibile
+        let report = prune(&store, &options(Some(Duration::ZERO), false)).unwrap();
+        assert_eq!(report.pruned.len(), 2);
+        assert_eq!(list_objects(&store).unwrap().len(), 3);
+        assert!(fsck(&store).unwrap().issues.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_writing_existing_object_refreshes_mtime() {
+        let dir = setup_test_dir("freshen");
+        let store = dir.join(".ogit");
+        let stray = write_object(&store, &OObject::new_blob(b"stray".to_vec())).unwrap();
+        let streamed = write_blob_stream(&store, &b"streamed"[..], 8).unwrap();
+
+        // Riscritti dopo essere invecchiati: tornano dentro il periodo di grazia
+        age(&loose_path(&store, &stray), 2 * HOUR);
+        age(&loose_path(&store, &streamed), 2 * HOUR);
+        assert_eq!(write_object(&store, &OObject::new_blob(b"stray".to_vec())).unwrap(), stray);
+        assert_eq!(write_blob_stream(&store, &b"streamed"[..], 8).unwrap(), streamed);
+
+        let report = prune(&store, &options(Some(HOUR), false)).unwrap();
+        assert_eq!(report.pruned, vec![]);
+        assert_eq!(report.kept, 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reachable_from_every_ref() {
+        let dir = setup_test_dir("refs");
+        let store = dir.join(".ogit");
+        let (first, first_tree, first_blob) = commit_file(&store, &[], "one");
+        let (side, ..) = commit_file(&store, std::slice::from_ref(&first), "side");
+        update_ref(&store, HEAD, &first).unwrap();
+        fs::create_dir_all(store.join("refs/tags")).unwrap();
+        fs::write(store.join("refs/tags/v1"), format!("{side}\n")).unwrap();
+
+        let reachable = reachable_objects(&store).unwrap();
+        assert_eq!(reachable.len(), 6);
+        assert!([&first, &first_tree, &first_blob, &side].iter().all(|id| reachable.contains(id)));
+
+        // Un oggetto raggiungibile mancante blocca tutto, senza eliminare niente
+        fs::remove_file(loose_path(&store, &first_blob)).unwrap();
+        let stray = write_object(&store, &OObject::new_blob(b"stray".to_vec())).unwrap();
+        assert!(matches!(
+            prune(&store, &options(Some(Duration::ZERO), false)),
+            Err(OgitError::ObjectNotFound(id)) if id == first_blob
+        ));
+        assert!(loose_path(&store, &stray).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_gc_drops_unreachable_packed_objects() {
+        let dir = setup_test_dir("gc");
+        let store = dir.join(".ogit");
+        let (first, ..) = commit_file(&store, &[], "one");
+        update_ref(&store, HEAD, &first).unwrap();
+        let stray = write_object(&store, &OObject::new_blob(b"stray".to_vec())).unwrap();
+        repack(&store, &PackOptions::default()).unwrap();
+        assert!(list_objects(&store).unwrap().is_empty());
+
+        // Pack recente: l'oggetto non raggiungibile torna loose, il resto resta nel pack
+        let (report, stats) = gc(&store, &PruneOptions::default(), &PackOptions::default()).unwrap();
+        assert_eq!((report.pruned.len(), report.kept), (0, 1));
+        assert_eq!(stats.unwrap().objects, 3);
+        assert_eq!(list_objects(&store).unwrap(), vec![stray.clone()]);
+
+        // Rimesso in un pack vecchio, `gc` lo elenca e poi lo elimina
+        repack(&store, &PackOptions::default()).unwrap();
+        age(pack_indexes(&store).unwrap()[0].pack_path(), 2 * HOUR);
+        let (report, stats) = gc(&store, &options(Some(HOUR), true), &PackOptions::default()).unwrap();
+        assert_eq!(report.pruned, vec![stray.clone()]);
+        assert_eq!(stats, None);
+        assert!(LooseStore::open(&store).unwrap().exists(&stray).unwrap());
+
+        gc(&store, &options(Some(HOUR), false), &PackOptions::default()).unwrap();
+        let objects = LooseStore::open(&store).unwrap();
+        assert!(!objects.exists(&stray).unwrap());
+        assert_eq!(objects.ids().unwrap().len(), 3);
+        assert_eq!(pack_indexes(&store).unwrap().len(), 1);
+        assert!(fsck(&store).unwrap().issues.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
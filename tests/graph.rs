@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use ogit::graph::{is_ancestor, merge_base, merge_bases, RevWalk, WalkOrder};
+    use ogit::object::{OObjectId, Signature};
+    use ogit::object_store::{LooseStore, ObjectStore};
+    use ogit::store::create_commit;
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_graph_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("objects")).unwrap();
+        dir
+    }
+
+    /// Commit con i parent indicati e data del committer `time`.
+    fn commit(store: &Path, parents: &[&OObjectId], time: i64, message: &str) -> OObjectId {
+        let tree = "0".repeat(64).parse::<OObjectId>().unwrap();
+        let author = Signature::new("Author", "author@example.com", time, 0);
+        let parents: Vec<OObjectId> = parents.iter().map(|p| (*p).clone()).collect();
+        create_commit(store, &tree, &parents, &author, message).unwrap()
+    }
+
+    fn walk(objects: &dyn ObjectStore, starts: &[OObjectId], order: WalkOrder) -> Vec<OObjectId> {
+        RevWalk::new(objects, starts, order)
+            .unwrap()
+            .map(|entry| entry.unwrap().0)
+            .collect()
+    }
+
+    #[test]
+    fn test_is_ancestor() {
+        let store = setup_test_dir("ancestor");
+        let objects = LooseStore::open(&store).unwrap();
+        let a = commit(&store, &[], 1, "a");
+        let b = commit(&store, &[&a], 2, "b");
+        let side = commit(&store, &[&a], 3, "side");
+        let merge = commit(&store, &[&b, &side], 4, "merge");
+
+        assert!(is_ancestor(&objects, &a, &merge).unwrap());
+        assert!(is_ancestor(&objects, &side, &merge).unwrap());
+        assert!(is_ancestor(&objects, &b, &b).unwrap());
+        assert!(!is_ancestor(&objects, &merge, &a).unwrap());
+        assert!(!is_ancestor(&objects, &b, &side).unwrap());
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_merge_base_after_merge() {
+        //   a ← b ← c ← m ← d
+        //        ↖ x ↙
+        //          ↖ y
+        let store = setup_test_dir("base");
+        let objects = LooseStore::open(&store).unwrap();
+        let a = commit(&store, &[], 1, "a");
+        let b = commit(&store, &[&a], 2, "b");
+        let c = commit(&store, &[&b], 3, "c");
+        let x = commit(&store, &[&b], 4, "x");
+        let m = commit(&store, &[&c, &x], 5, "m");
+        let d = commit(&store, &[&m], 6, "d");
+        let y = commit(&store, &[&x], 7, "y");
+
+        // x è già stato unito: è lui l'antenato comune, non b
+        assert_eq!(merge_base(&objects, &d, &y).unwrap(), Some(x.clone()));
+        assert_eq!(merge_base(&objects, &c, &x).unwrap(), Some(b));
+        assert_eq!(merge_base(&objects, &a, &d).unwrap(), Some(a));
+
+        let unrelated = commit(&store, &[], 8, "unrelated");
+        assert_eq!(merge_base(&objects, &d, &unrelated).unwrap(), None);
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_criss_cross_has_two_bases() {
+        let store = setup_test_dir("criss");
+        let objects = LooseStore::open(&store).unwrap();
+        let root = commit(&store, &[], 1, "root");
+        let p = commit(&store, &[&root], 2, "p");
+        let q = commit(&store, &[&root], 3, "q");
+        let left = commit(&store, &[&p, &q], 4, "left");
+        let right = commit(&store, &[&q, &p], 5, "right");
+
+        assert_eq!(merge_bases(&objects, &left, &right).unwrap(), vec![q.clone(), p]);
+        assert_eq!(merge_base(&objects, &left, &right).unwrap(), Some(q));
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_walk_orders() {
+        // Il ramo `side` ha date più vecchie del ramo principale
+        let store = setup_test_dir("walk");
+        let objects = LooseStore::open(&store).unwrap();
+        let a = commit(&store, &[], 10, "a");
+        let side = commit(&store, &[&a], 11, "side");
+        let b = commit(&store, &[&a], 20, "b");
+        let c = commit(&store, &[&b], 30, "c");
+        let side2 = commit(&store, &[&side], 5, "side2");
+        let merge = commit(&store, &[&c, &side2], 40, "merge");
+
+        // Per data side2 (la più vecchia) esce dopo a, e side solo dopo side2
+        let by_date = walk(&objects, std::slice::from_ref(&merge), WalkOrder::Date);
+        assert_eq!(by_date, vec![merge.clone(), c.clone(), b.clone(), a.clone(), side2.clone(), side.clone()]);
+
+        // In ordine topologico a esce solo dopo tutti i suoi figli
+        let topo = walk(&objects, std::slice::from_ref(&merge), WalkOrder::Topological);
+        assert_eq!(topo, vec![merge, c.clone(), b.clone(), side2, side, a]);
+
+        // Più partenze: ogni commit compare una volta sola
+        let both = walk(&objects, &[c.clone(), b.clone(), c], WalkOrder::Topological);
+        assert_eq!(both.len(), 3);
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+}
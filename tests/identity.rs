@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ogit::config::Config;
+    use ogit::identity::{resolve_identity, Role, USER_EMAIL_KEY, USER_NAME_KEY};
+    use ogit::object::Signature;
+
+    fn config_with_user() -> Config {
+        let mut config = Config::default();
+        config.set(USER_NAME_KEY, "Mario Rossi").unwrap();
+        config.set(USER_EMAIL_KEY, "mario@example.com").unwrap();
+        config
+    }
+
+    fn env_from(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let map: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |key| map.get(key).cloned()
+    }
+
+    #[test]
+    fn test_identity_from_config() {
+        let sig = resolve_identity(&config_with_user(), Role::Author, env_from(&[]), 1234).unwrap();
+        assert_eq!(sig, Signature::new("Mario Rossi", "mario@example.com", 1234, 0));
+    }
+
+    #[test]
+    fn test_env_overrides_config_per_role() {
+        let env = env_from(&[
+            ("OGIT_COMMITTER_NAME", "Bot"),
+            ("OGIT_COMMITTER_EMAIL", "bot@example.com"),
+            ("OGIT_AUTHOR_DATE", "1700000000 +0100"),
+        ]);
+        let config = config_with_user();
+
+        let author = resolve_identity(&config, Role::Author, &env, 1234).unwrap();
+        assert_eq!(author, Signature::new("Mario Rossi", "mario@example.com", 1_700_000_000, 60));
+
+        let committer = resolve_identity(&config, Role::Committer, &env, 1234).unwrap();
+        assert_eq!(committer, Signature::new("Bot", "bot@example.com", 1234, 0));
+    }
+
+    #[test]
+    fn test_missing_identity_is_an_error() {
+        let err = resolve_identity(&Config::default(), Role::Author, env_from(&[]), 0).unwrap_err();
+        assert!(err.to_string().contains("user.name"));
+
+        // EMAIL vale come ultima risorsa per l'indirizzo
+        let env = env_from(&[("OGIT_AUTHOR_NAME", "Anna"), ("EMAIL", "anna@example.com")]);
+        let sig = resolve_identity(&Config::default(), Role::Author, env, 0).unwrap();
+        assert_eq!(sig.email, "anna@example.com");
+    }
+
+    #[test]
+    fn test_invalid_date_is_an_error() {
+        let env = env_from(&[("OGIT_AUTHOR_DATE", "yesterday")]);
+        assert!(resolve_identity(&config_with_user(), Role::Author, env, 0).is_err());
+    }
+}
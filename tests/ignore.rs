@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use ogit::ignore::{glob_match, IgnoreRules};
+    use ogit::index::{add_paths, Index};
+    use ogit::error::OgitError;
+    use ogit::status::status;
+    use ogit::tree::{build_tree_from_dir, flatten_tree};
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_ignore_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".ogit/objects")).unwrap();
+        fs::create_dir_all(dir.join(".ogit/refs/heads")).unwrap();
+        fs::write(dir.join(".ogit/HEAD"), "ref: refs/heads/master\n").unwrap();
+        dir
+    }
+
+    fn rules(content: &str) -> IgnoreRules {
+        let mut rules = IgnoreRules::default();
+        rules.add_patterns(content, "");
+        rules
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.swp", ".main.rs.swp"));
+        assert!(!glob_match("*.rs", "src/main.rs"));
+        assert!(glob_match("file?.txt", "file1.txt"));
+        assert!(glob_match("[a-c]x", "bx"));
+        assert!(!glob_match("[!a-c]x", "bx"));
+        assert!(glob_match("**/logs", "a/b/logs"));
+        assert!(glob_match("**/logs", "logs"));
+        assert!(glob_match("a/**/b", "a/b"));
+        assert!(glob_match("a/**/b", "a/x/y/b"));
+        assert!(glob_match("doc/**", "doc/x/y.txt"));
+        assert!(glob_match("\\*", "*"));
+        assert!(!glob_match("\\*", "a"));
+    }
+
+    #[test]
+    fn test_rules_negation_and_dir_only() {
+        let rules = rules("# commento\n*.log\n!keep.log\nbuild/\n/root.txt\n\\#hash\n");
+
+        assert!(rules.is_ignored("a.log", false));
+        assert!(rules.is_ignored("deep/dir/a.log", false));
+        assert!(!rules.is_ignored("keep.log", false));
+
+        assert!(rules.is_ignored("build", true));
+        assert!(rules.is_ignored("src/build", true));
+        assert!(!rules.is_ignored("build", false));
+
+        assert!(rules.is_ignored("root.txt", false));
+        assert!(!rules.is_ignored("sub/root.txt", false));
+
+        assert!(rules.is_ignored("#hash", false));
+        assert!(!rules.is_ignored("commento", false));
+    }
+
+    #[test]
+    fn test_build_tree_skips_ignored() {
+        let dir = setup_test_dir("build");
+        let store = dir.join(".ogit");
+        fs::create_dir_all(dir.join("target/debug")).unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join(".ogitignore"), "target/\n*.swp\n").unwrap();
+        fs::write(dir.join("target/debug/app"), "bin").unwrap();
+        fs::write(dir.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.join("src/.main.rs.swp"), "swap").unwrap();
+        // Regole annidate: valgono solo sotto src/
+        fs::write(dir.join("src/.ogitignore"), "gen.rs\n").unwrap();
+        fs::write(dir.join("src/gen.rs"), "generated").unwrap();
+        fs::write(dir.join("gen.rs"), "top level").unwrap();
+
+        let tree = build_tree_from_dir(&store, &dir).unwrap();
+        let files: Vec<String> = flatten_tree(&store, &tree).unwrap().into_keys().collect();
+
+        assert_eq!(files, vec![".ogitignore", "gen.rs", "src/.ogitignore", "src/main.rs"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_info_exclude() {
+        let dir = setup_test_dir("exclude");
+        let store = dir.join(".ogit");
+        fs::create_dir_all(store.join("info")).unwrap();
+        fs::write(store.join("info/exclude"), "local.txt\n").unwrap();
+        fs::write(dir.join("local.txt"), "mine").unwrap();
+        fs::write(dir.join("a.txt"), "A").unwrap();
+
+        let st = status(&store, &dir).unwrap();
+        assert_eq!(st.untracked, vec!["a.txt"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_add_and_status_honor_ignore() {
+        let dir = setup_test_dir("add");
+        let store = dir.join(".ogit");
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        fs::write(dir.join("tracked.log"), "old").unwrap();
+        add_paths(&store, &dir, &["tracked.log".to_string()], false).unwrap();
+
+        fs::write(dir.join(".ogitignore"), "*.log\n").unwrap();
+        fs::write(dir.join("new.log"), "noise").unwrap();
+
+        let st = status(&store, &dir).unwrap();
+        assert_eq!(st.untracked, vec![".ogitignore", "a.txt"]);
+        assert!(st.unstaged.is_empty());
+
+        // Un file tracciato resta tracciato anche se corrisponde a un pattern
+        fs::write(dir.join("tracked.log"), "new").unwrap();
+        assert_eq!(status(&store, &dir).unwrap().unstaged[0].path, "tracked.log");
+
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+        let index = Index::load(&store).unwrap();
+        let paths: Vec<&String> = index.entries.keys().collect();
+        assert_eq!(paths, vec![".ogitignore", "a.txt", "tracked.log"]);
+        assert!(status(&store, &dir).unwrap().unstaged.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_add_rejects_ignored_paths_unless_forced() {
+        let dir = setup_test_dir("add_force");
+        let store = dir.join(".ogit");
+        fs::write(dir.join(".ogitignore"), "*.log\ntarget/\n").unwrap();
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        fs::write(dir.join("x.log"), "log").unwrap();
+        fs::create_dir_all(dir.join("target/debug")).unwrap();
+        fs::write(dir.join("target/debug/app"), "bin").unwrap();
+
+        for path in ["x.log", "target", "target/debug/app"] {
+            let err = add_paths(&store, &dir, &["a.txt".to_string(), path.to_string()], false).unwrap_err();
+            assert!(matches!(err, OgitError::Rejected(ref msg) if msg.contains(path)), "{path}: {err}");
+        }
+        // Il rifiuto non tocca l'indice, nemmeno per gli altri path
+        assert!(!Index::exists(&store));
+
+        add_paths(&store, &dir, &["x.log".to_string(), "target".to_string()], true).unwrap();
+        let index = Index::load(&store).unwrap();
+        let paths: Vec<&String> = index.entries.keys().collect();
+        assert_eq!(paths, vec!["target/debug/app", "x.log"]);
+
+        // Una volta tracciato, il file si aggiorna anche senza -f
+        fs::write(dir.join("x.log"), "more").unwrap();
+        assert_eq!(add_paths(&store, &dir, &["x.log".to_string()], false).unwrap(), vec!["x.log"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
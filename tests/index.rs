@@ -0,0 +1,188 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use ogit::index::{add_paths, normalize_path, remove_paths, reset_paths, write_tree_from_index, Index};
+    use ogit::object::{OObjectId, Signature};
+    use ogit::refs::{update_ref, HEAD};
+    use ogit::store::create_commit;
+    use ogit::tree::{build_tree_from_dir, flatten_tree};
+
+    fn author() -> Signature {
+        Signature::new("Author", "author@example.com", 0, 0)
+    }
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_index_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".ogit/objects")).unwrap();
+        fs::create_dir_all(dir.join(".ogit/refs/heads")).unwrap();
+        fs::write(dir.join(".ogit/HEAD"), "ref: refs/heads/master\n").unwrap();
+        dir
+    }
+
+    fn commit_index(store: &Path) -> OObjectId {
+        let tree = write_tree_from_index(store, &Index::load(store).unwrap()).unwrap();
+        let id = create_commit(store, &tree, &[], &author(), "msg").unwrap();
+        update_ref(store, HEAD, &id).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_index_serialize_roundtrip_with_spaces() {
+        let (a, b) = ("a1".repeat(32), "b2".repeat(32));
+        let content = format!("123 5 100644 {a} my file.txt\n0 0 100755 {b} src/main.rs\n");
+        let index = Index::deserialize(&content).unwrap();
+
+        assert_eq!(index.entries.len(), 2);
+        assert_eq!(index.get("my file.txt").unwrap().size, 5);
+        assert_eq!(index.serialize(), content);
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("./src//main.rs").unwrap(), "src/main.rs");
+        assert_eq!(normalize_path(".").unwrap(), "");
+        assert!(normalize_path("../etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_add_subset_and_write_tree() {
+        let dir = setup_test_dir("subset");
+        let store = dir.join(".ogit");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        fs::write(dir.join("b.txt"), "B").unwrap();
+        fs::write(dir.join("src/lib.rs"), "lib").unwrap();
+
+        add_paths(&store, &dir, &["a.txt".to_string(), "src".to_string()], false).unwrap();
+
+        let index = Index::load(&store).unwrap();
+        let tree = write_tree_from_index(&store, &index).unwrap();
+        let files = flatten_tree(&store, &tree).unwrap();
+
+        let paths: Vec<&str> = files.keys().map(String::as_str).collect();
+        assert_eq!(paths, vec!["a.txt", "src/lib.rs"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_index_tree_matches_dir_tree() {
+        let dir = setup_test_dir("same");
+        let store = dir.join(".ogit");
+        fs::create_dir_all(dir.join("src/bin")).unwrap();
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        fs::write(dir.join("src/lib.rs"), "lib").unwrap();
+        fs::write(dir.join("src/bin/x.rs"), "x").unwrap();
+
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+        let from_index = write_tree_from_index(&store, &Index::load(&store).unwrap()).unwrap();
+        let from_dir = build_tree_from_dir(&store, &dir).unwrap();
+
+        assert_eq!(from_index, from_dir);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_add_directory_stages_deletions() {
+        let dir = setup_test_dir("deletions");
+        let store = dir.join(".ogit");
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        fs::write(dir.join("b.txt"), "B").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+
+        fs::remove_file(dir.join("b.txt")).unwrap();
+        let changed = add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+
+        assert_eq!(changed, vec!["b.txt".to_string()]);
+        assert!(Index::load(&store).unwrap().get("b.txt").is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rm_cached_keeps_file() {
+        let dir = setup_test_dir("rm");
+        let store = dir.join(".ogit");
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        fs::write(dir.join("b.txt"), "B").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+
+        remove_paths(&store, &dir, &["a.txt".to_string()], true).unwrap();
+        assert!(dir.join("a.txt").exists());
+
+        remove_paths(&store, &dir, &["b.txt".to_string()], false).unwrap();
+        assert!(!dir.join("b.txt").exists());
+
+        assert!(Index::load(&store).unwrap().entries.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rm_refuses_modified_file() {
+        let dir = setup_test_dir("rm_dirty");
+        let store = dir.join(".ogit");
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        add_paths(&store, &dir, &["a.txt".to_string()], false).unwrap();
+        fs::write(dir.join("a.txt"), "changed").unwrap();
+
+        assert!(remove_paths(&store, &dir, &["a.txt".to_string()], false).is_err());
+        assert!(dir.join("a.txt").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reset_restores_head_entries() {
+        let dir = setup_test_dir("reset");
+        let store = dir.join(".ogit");
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        add_paths(&store, &dir, &["a.txt".to_string()], false).unwrap();
+        commit_index(&store);
+        let committed = Index::load(&store).unwrap().get("a.txt").unwrap().hash.clone();
+
+        fs::write(dir.join("a.txt"), "A2").unwrap();
+        fs::write(dir.join("new.txt"), "N").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+
+        let changed = reset_paths(&store, &[]).unwrap();
+        assert_eq!(changed, vec!["a.txt".to_string(), "new.txt".to_string()]);
+
+        let index = Index::load(&store).unwrap();
+        assert_eq!(index.get("a.txt").unwrap().hash, committed);
+        assert!(index.get("new.txt").is_none());
+        // La working directory non viene toccata
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "A2");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_index_means_head_tree() {
+        let dir = setup_test_dir("missing");
+        let store = dir.join(".ogit");
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        fs::write(dir.join("b.txt"), "B").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+        let head = commit_index(&store);
+        let head_tree = ogit::store::read_commit(&store, &head).unwrap().tree;
+
+        // Senza indice, un commit riprodurrebbe HEAD invece di un tree vuoto
+        fs::remove_file(store.join("index")).unwrap();
+        let index = Index::load_or_head(&store).unwrap();
+        assert_eq!(write_tree_from_index(&store, &index).unwrap(), head_tree);
+
+        // e `add` di un solo file non cancella gli altri
+        fs::write(dir.join("b.txt"), "B2").unwrap();
+        add_paths(&store, &dir, &["b.txt".to_string()], false).unwrap();
+        let files = flatten_tree(&store, &write_tree_from_index(&store, &Index::load(&store).unwrap()).unwrap()).unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(), vec!["a.txt", "b.txt"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
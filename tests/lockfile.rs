@@ -0,0 +1,162 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use ogit::error::OgitError;
+    use ogit::initialize_repository::init_repo_at;
+    use ogit::lockfile::{write_atomic, write_locked, LockFile};
+    use ogit::object::{OObject, ObjectFormat, Signature};
+    use ogit::index::{add_paths, Index};
+    use ogit::refs::{resolve_ref, update_ref, update_ref_checked, HEAD};
+    use ogit::store::{create_commit, list_objects, write_object};
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_lock_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> =
+            fs::read_dir(dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_write_locked_replaces_file_and_releases_lock() {
+        let dir = setup_test_dir("replace");
+        let path = dir.join("HEAD");
+        fs::write(&path, "old\n").unwrap();
+
+        write_locked(&path, "new\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new\n");
+        assert_eq!(file_names(&dir), vec!["HEAD"]);
+
+        write_atomic(&dir.join("object"), b"data").unwrap();
+        assert_eq!(file_names(&dir), vec!["HEAD", "object"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_held_lock_rejects_second_writer() {
+        let dir = setup_test_dir("held");
+        let path = dir.join("index");
+        fs::write(&path, "original").unwrap();
+
+        let mut lock = LockFile::acquire(&path).unwrap();
+        lock.write_all(b"half written").unwrap();
+
+        match write_locked(&path, "other") {
+            Err(OgitError::Locked(lock_path)) => assert_eq!(lock_path, dir.join("index.lock")),
+            other => panic!("expected a lock error, got {other:?}"),
+        }
+
+        // Senza commit il file resta quello vecchio e il lock sparisce
+        drop(lock);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+        assert_eq!(file_names(&dir), vec!["index"]);
+        write_locked(&path, "other").unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_update_ref_respects_branch_lock() {
+        let dir = setup_test_dir("ref");
+        let store = dir.join(".ogit");
+        init_repo_at(&store, ObjectFormat::Ogit).unwrap();
+
+        let tree = write_object(&store, &OObject::new_tree(Vec::new())).unwrap();
+        let author = Signature::new("Author", "author@example.com", 0, 0);
+        let first = create_commit(&store, &tree, &[], &author, "first").unwrap();
+        update_ref(&store, HEAD, &first).unwrap();
+
+        // Un altro processo sta aggiornando master
+        fs::write(store.join("refs/heads/master.lock"), "").unwrap();
+        let second = create_commit(&store, &tree, std::slice::from_ref(&first), &author, "second").unwrap();
+        assert!(matches!(update_ref(&store, HEAD, &second), Err(OgitError::Locked(_))));
+        assert_eq!(resolve_ref(&store, HEAD).unwrap(), Some(first));
+
+        fs::remove_file(store.join("refs/heads/master.lock")).unwrap();
+        update_ref(&store, HEAD, &second).unwrap();
+        assert_eq!(resolve_ref(&store, HEAD).unwrap(), Some(second));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_update_ref_checked_compares_old_value() {
+        let dir = setup_test_dir("cas");
+        let store = dir.join(".ogit");
+        init_repo_at(&store, ObjectFormat::Ogit).unwrap();
+
+        let tree = write_object(&store, &OObject::new_tree(Vec::new())).unwrap();
+        let author = Signature::new("Author", "author@example.com", 0, 0);
+        let first = create_commit(&store, &tree, &[], &author, "first").unwrap();
+        let second = create_commit(&store, &tree, std::slice::from_ref(&first), &author, "second").unwrap();
+        let other = create_commit(&store, &tree, std::slice::from_ref(&first), &author, "other").unwrap();
+
+        assert!(matches!(update_ref_checked(&store, HEAD, &first, Some(&second)), Err(OgitError::Rejected(_))));
+        update_ref_checked(&store, HEAD, &first, None).unwrap();
+
+        // Due commit partiti dallo stesso parent: il secondo non scarta il primo
+        update_ref_checked(&store, HEAD, &second, Some(&first)).unwrap();
+        assert!(matches!(update_ref_checked(&store, HEAD, &other, Some(&first)), Err(OgitError::Rejected(_))));
+        assert_eq!(resolve_ref(&store, HEAD).unwrap(), Some(second.clone()));
+
+        // Il lock si prende prima di leggere il valore corrente
+        fs::write(store.join("refs/heads/master.lock"), "").unwrap();
+        assert!(matches!(update_ref_checked(&store, HEAD, &other, Some(&second)), Err(OgitError::Locked(_))));
+        assert_eq!(resolve_ref(&store, HEAD).unwrap(), Some(second));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_index_lock_spans_load_and_save() {
+        let dir = setup_test_dir("index");
+        let store = dir.join(".ogit");
+        init_repo_at(&store, ObjectFormat::Ogit).unwrap();
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        fs::write(dir.join("b.txt"), "B").unwrap();
+        add_paths(&store, &dir, &["a.txt".to_string()], false).unwrap();
+
+        // Un altro processo ha letto l'indice e lo sta modificando
+        let lock = Index::lock(&store).unwrap();
+        let mut index = Index::load(&store).unwrap();
+        index.remove("a.txt");
+        assert!(matches!(
+            add_paths(&store, &dir, &["b.txt".to_string()], false),
+            Err(OgitError::Locked(_))
+        ));
+        index.save_locked(lock).unwrap();
+
+        add_paths(&store, &dir, &["b.txt".to_string()], false).unwrap();
+        let index = Index::load(&store).unwrap();
+        assert_eq!(index.entries.keys().collect::<Vec<_>>(), vec!["b.txt"]);
+        assert!(!store.join("index.lock").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_leftover_temp_files_are_not_objects() {
+        let dir = setup_test_dir("leftover");
+        let store = dir.join(".ogit");
+        init_repo_at(&store, ObjectFormat::Ogit).unwrap();
+
+        let id = write_object(&store, &OObject::new_blob(b"hello".to_vec())).unwrap();
+        // Resto di una scrittura interrotta da un crash
+        let hex = id.to_hex();
+        fs::write(store.join("objects").join(&hex[..2]).join(format!("{}.tmp-1-0", &hex[2..])), "trunc").unwrap();
+
+        assert_eq!(list_objects(&store).unwrap(), vec![id]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
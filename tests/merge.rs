@@ -0,0 +1,232 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use ogit::branch::create_branch;
+    use ogit::checkout::checkout;
+    use ogit::error::OgitError;
+    use ogit::index::{add_paths, write_tree_from_index, Index};
+    use ogit::graph::merge_base;
+    use ogit::merge::{merge, merge_content, read_merge_state, MergeOutcome};
+    use ogit::object::{OObjectId, Signature};
+    use ogit::object_store::LooseStore;
+    use ogit::refs::{resolve_ref, update_ref, HEAD};
+    use ogit::status::status;
+    use ogit::store::{create_commit, read_commit};
+    use ogit::tree::flatten_tree;
+
+    fn author() -> Signature {
+        Signature::new("Author", "author@example.com", 0, 0)
+    }
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_merge_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".ogit/objects")).unwrap();
+        fs::create_dir_all(dir.join(".ogit/refs/heads")).unwrap();
+        fs::write(dir.join(".ogit/HEAD"), "ref: refs/heads/master\n").unwrap();
+        dir
+    }
+
+    /// Scrive i file, li aggiunge all'indice e crea un commit su HEAD.
+    fn commit_files(dir: &Path, files: &[(&str, &str)], message: &str) -> OObjectId {
+        let store = dir.join(".ogit");
+        for (path, content) in files {
+            fs::write(dir.join(path), content).unwrap();
+        }
+        add_paths(&store, dir, &[".".to_string()], false).unwrap();
+
+        let tree = write_tree_from_index(&store, &Index::load(&store).unwrap()).unwrap();
+        let parent = resolve_ref(&store, HEAD).unwrap();
+        let id = create_commit(&store, &tree, parent.as_slice(), &author(), message).unwrap();
+        update_ref(&store, HEAD, &id).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_merge_content_clean() {
+        let base = b"a\nb\nc\nd\n";
+        let ours = b"a\nB\nc\nd\n";
+        let theirs = b"a\nb\nc\nD\ne\n";
+
+        let result = merge_content(base, ours, theirs, "HEAD", "topic");
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.data, b"a\nB\nc\nD\ne\n");
+    }
+
+    #[test]
+    fn test_merge_content_same_change_on_both_sides() {
+        let result = merge_content(b"a\nb\n", b"a\nX\n", b"a\nX\n", "HEAD", "topic");
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.data, b"a\nX\n");
+    }
+
+    #[test]
+    fn test_merge_content_conflict_markers() {
+        let result = merge_content(b"a\nb\nc\n", b"a\nours\nc\n", b"a\ntheirs\nc\n", "HEAD", "topic");
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(
+            String::from_utf8(result.data).unwrap(),
+            "a\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> topic\nc\n"
+        );
+
+        // Senza `\n` finale i marker restano comunque su righe proprie
+        let result = merge_content(b"a\nb", b"a\nours", b"a\ntheirs", "HEAD", "topic");
+        assert_eq!(
+            String::from_utf8(result.data).unwrap(),
+            "a\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> topic\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_content_binary_keeps_ours() {
+        let result = merge_content(b"\0base", b"\0ours", b"\0theirs", "HEAD", "topic");
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(result.data, b"\0ours");
+    }
+
+    #[test]
+    fn test_merge_base_of_diverged_branches() {
+        let dir = setup_test_dir("base");
+        let store = dir.join(".ogit");
+        let base = commit_files(&dir, &[("f.txt", "base\n")], "base");
+        create_branch(&store, "topic", None).unwrap();
+        let ours = commit_files(&dir, &[("f.txt", "ours\n")], "ours");
+
+        checkout(&store, &dir, "topic").unwrap();
+        let theirs = commit_files(&dir, &[("g.txt", "theirs\n")], "theirs");
+
+        let objects = LooseStore::open(&store).unwrap();
+        assert_eq!(merge_base(&objects, &ours, &theirs).unwrap(), Some(base.clone()));
+        assert_eq!(merge_base(&objects, &base, &theirs).unwrap(), Some(base));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fast_forward_and_up_to_date() {
+        let dir = setup_test_dir("ff");
+        let store = dir.join(".ogit");
+        commit_files(&dir, &[("a.txt", "A")], "first");
+        create_branch(&store, "topic", None).unwrap();
+        checkout(&store, &dir, "topic").unwrap();
+        let tip = commit_files(&dir, &[("b.txt", "B")], "second");
+        checkout(&store, &dir, "master").unwrap();
+        assert!(!dir.join("b.txt").exists());
+
+        let outcome = merge(&store, &dir, "topic", &author(), &author()).unwrap();
+        assert_eq!(outcome, MergeOutcome::FastForward(tip.clone()));
+        assert_eq!(resolve_ref(&store, HEAD).unwrap(), Some(tip));
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "B");
+        assert!(status(&store, &dir).unwrap().is_clean());
+
+        assert_eq!(merge(&store, &dir, "topic", &author(), &author()).unwrap(), MergeOutcome::UpToDate);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_three_way_merge_creates_two_parent_commit() {
+        let dir = setup_test_dir("clean");
+        let store = dir.join(".ogit");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        commit_files(&dir, &[("f.txt", "a\nb\nc\n"), ("src/lib.rs", "lib\n")], "base");
+        create_branch(&store, "topic", None).unwrap();
+        let ours = commit_files(&dir, &[("f.txt", "A\nb\nc\n")], "ours");
+
+        checkout(&store, &dir, "topic").unwrap();
+        fs::remove_file(dir.join("src/lib.rs")).unwrap();
+        let theirs = commit_files(&dir, &[("f.txt", "a\nb\nC\n"), ("new.txt", "new\n")], "theirs");
+        checkout(&store, &dir, "master").unwrap();
+
+        let MergeOutcome::Merged(id) = merge(&store, &dir, "topic", &author(), &author()).unwrap() else {
+            panic!("expected a merge commit");
+        };
+        let commit = read_commit(&store, &id).unwrap();
+        assert_eq!(commit.parents, vec![ours, theirs]);
+        assert_eq!(commit.message, "Merge branch 'topic'");
+
+        let files = flatten_tree(&store, &commit.tree).unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(), vec!["f.txt", "new.txt"]);
+        assert_eq!(fs::read_to_string(dir.join("f.txt")).unwrap(), "A\nb\nC\n");
+        assert!(!dir.join("src").exists());
+        assert!(status(&store, &dir).unwrap().is_clean());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_conflict_is_recorded_until_resolved() {
+        let dir = setup_test_dir("conflict");
+        let store = dir.join(".ogit");
+        commit_files(&dir, &[("f.txt", "a\nb\nc\n")], "base");
+        create_branch(&store, "topic", None).unwrap();
+        let ours = commit_files(&dir, &[("f.txt", "a\nours\nc\n")], "ours");
+
+        checkout(&store, &dir, "topic").unwrap();
+        let theirs = commit_files(&dir, &[("f.txt", "a\ntheirs\nc\n")], "theirs");
+        checkout(&store, &dir, "master").unwrap();
+
+        let outcome = merge(&store, &dir, "topic", &author(), &author()).unwrap();
+        assert_eq!(outcome, MergeOutcome::Conflicts(vec!["f.txt".to_string()]));
+        assert_eq!(resolve_ref(&store, HEAD).unwrap(), Some(ours));
+        assert!(fs::read_to_string(dir.join("f.txt")).unwrap().contains("<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> topic\n"));
+
+        let state = read_merge_state(&store).unwrap().unwrap();
+        assert_eq!(state.head, theirs);
+        assert_eq!(status(&store, &dir).unwrap().conflicts, vec!["f.txt"]);
+        assert!(merge(&store, &dir, "topic", &author(), &author()).is_err());
+
+        // `add` segna il conflitto come risolto
+        fs::write(dir.join("f.txt"), "a\nresolved\nc\n").unwrap();
+        add_paths(&store, &dir, &["f.txt".to_string()], false).unwrap();
+        assert!(read_merge_state(&store).unwrap().unwrap().conflicts.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_merge_refuses_to_overwrite_local_changes() {
+        let dir = setup_test_dir("dirty");
+        let store = dir.join(".ogit");
+        commit_files(&dir, &[("f.txt", "base\n"), ("g.txt", "g\n")], "base");
+        create_branch(&store, "topic", None).unwrap();
+        commit_files(&dir, &[("g.txt", "ours\n")], "ours");
+
+        checkout(&store, &dir, "topic").unwrap();
+        commit_files(&dir, &[("f.txt", "theirs\n")], "theirs");
+        checkout(&store, &dir, "master").unwrap();
+
+        fs::write(dir.join("f.txt"), "local edit\n").unwrap();
+        let err = merge(&store, &dir, "topic", &author(), &author()).unwrap_err();
+        assert!(matches!(err, OgitError::Rejected(_)));
+        assert!(err.to_string().contains("f.txt"));
+        assert_eq!(fs::read_to_string(dir.join("f.txt")).unwrap(), "local edit\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_merge_refuses_staged_changes() {
+        let dir = setup_test_dir("staged");
+        let store = dir.join(".ogit");
+        commit_files(&dir, &[("f.txt", "base\n"), ("g.txt", "g\n")], "base");
+        create_branch(&store, "topic", None).unwrap();
+        commit_files(&dir, &[("g.txt", "ours\n")], "ours");
+
+        checkout(&store, &dir, "topic").unwrap();
+        commit_files(&dir, &[("f.txt", "theirs\n")], "theirs");
+        checkout(&store, &dir, "master").unwrap();
+
+        // Un file nuovo nell'indice: il merge non tocca la working directory
+        fs::write(dir.join("h.txt"), "staged\n").unwrap();
+        add_paths(&store, &dir, &["h.txt".to_string()], false).unwrap();
+        let err = merge(&store, &dir, "topic", &author(), &author()).unwrap_err();
+        assert!(matches!(err, OgitError::Rejected(_)));
+        assert!(Index::load(&store).unwrap().get("h.txt").is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use ogit::hashing_values::hex_to_bytes;
+    use ogit::object::{Commit, OObject, OObjectId, OObjectType, ObjectFormat, Signature, TreeEntry};
+    use ogit::store::{create_commit, object_id_as, read_commit, read_tree, write_object, write_tree, OBJECT_FORMAT_KEY};
+    use ogit::config::Config;
+
+    fn author() -> Signature {
+        Signature::new("Author", "author@example.com", 0, 0)
+    }
+
+    fn setup_git_store(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_format_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("objects")).unwrap();
+        let mut config = Config::default();
+        config.set(OBJECT_FORMAT_KEY, "git").unwrap();
+        config.save(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_hex_to_bytes() {
+        assert_eq!(hex_to_bytes("abcd12").unwrap(), vec![0xab, 0xcd, 0x12]);
+        assert!(hex_to_bytes("abc").is_err());
+        assert!(hex_to_bytes("zz").is_err());
+    }
+
+    #[test]
+    fn test_git_blob_hash_matches_git() {
+        // echo hello | git hash-object --stdin
+        let blob = OObject::new_blob(b"hello\n".to_vec());
+        assert_eq!(
+            object_id_as(&blob, ObjectFormat::Git).to_hex(),
+            "ce013625030ba8dba906f756967f9e9ca394464a"
+        );
+    }
+
+    #[test]
+    fn test_git_tree_roundtrip_and_order() {
+        let hash = "ce013625030ba8dba906f756967f9e9ca394464a".parse::<OObjectId>().unwrap();
+        let entries = vec![
+            TreeEntry::new(OObjectType::Tree, hash.clone(), "a".to_string()),
+            TreeEntry::new(OObjectType::Blob, hash.clone(), "a.txt".to_string()),
+        ];
+
+        let bytes = TreeEntry::serialize_tree_as(&entries, ObjectFormat::Git).unwrap();
+        // Git ordina "a.txt" prima di "a/" ('.' < '/')
+        assert!(bytes.starts_with(b"100644 a.txt\0"));
+        assert_eq!(bytes.len(), 2 * 20 + "100644 a.txt\0".len() + "40000 a\0".len());
+
+        let parsed = TreeEntry::deserialize_tree_as(&bytes, ObjectFormat::Git).unwrap();
+        assert_eq!(parsed[0].name, "a.txt");
+        assert_eq!(parsed[1].kind, OObjectType::Tree);
+        assert_eq!(parsed[1].hash, hash);
+    }
+
+    #[test]
+    fn test_git_tree_rejects_sha256_hash() {
+        let entries = vec![TreeEntry::new(OObjectType::Blob, "a".repeat(64).parse::<OObjectId>().unwrap(), "f".to_string())];
+        assert!(TreeEntry::serialize_tree_as(&entries, ObjectFormat::Git).is_err());
+    }
+
+    #[test]
+    fn test_git_commit_layout() {
+        let commit = Commit {
+            tree: "1".repeat(40).parse::<OObjectId>().unwrap(),
+            parents: vec!["2".repeat(40).parse::<OObjectId>().unwrap()],
+            author: Signature::new("Mario", "mario@example.com", 1_700_000_000, 60),
+            committer: Signature::new("Mario", "mario@example.com", 1_700_000_000, 60),
+            message: "Subject\n\nBody line".to_string(),
+        };
+
+        let bytes = commit.serialize_as(ObjectFormat::Git);
+        let text = String::from_utf8(bytes.clone()).unwrap();
+        assert!(text.contains("\ncommitter Mario <mario@example.com> 1700000000 +0100\n\nSubject"));
+        assert!(text.ends_with("Body line\n"));
+
+        assert_eq!(Commit::deserialize_as(&bytes, ObjectFormat::Git).unwrap(), commit);
+    }
+
+    #[test]
+    fn test_git_store_uses_sha1_ids() {
+        let store = setup_git_store("store");
+
+        let blob_id = write_object(&store, &OObject::new_blob(b"hello\n".to_vec())).unwrap();
+        assert_eq!(blob_id.to_hex(), "ce013625030ba8dba906f756967f9e9ca394464a");
+
+        let tree_id = write_tree(&store, &[TreeEntry::new(OObjectType::Blob, blob_id, "a.txt".to_string())]).unwrap();
+        assert_eq!(tree_id.to_hex().len(), 40);
+        assert_eq!(read_tree(&store, &tree_id).unwrap()[0].name, "a.txt");
+
+        let commit_id = create_commit(&store, &tree_id, &[], &author(), "msg").unwrap();
+        let commit = read_commit(&store, &commit_id).unwrap();
+        assert_eq!(commit.tree, tree_id);
+        assert_eq!(commit.message, "msg");
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ogit::error::OgitError;
+    use ogit::object::{OObject, OObjectId, ObjectFormat};
+    use ogit::object_store::{MemoryStore, ObjectStore};
+
+    const SHA256_HEX: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+    const SHA1_HEX: &str = "ce013625030ba8dba906f756967f9e9ca394464a";
+
+    #[test]
+    fn test_parse_and_display_roundtrip() {
+        let id: OObjectId = SHA256_HEX.parse().unwrap();
+        assert_eq!(id.to_string(), SHA256_HEX);
+        assert_eq!(id.as_bytes().len(), 32);
+        assert_eq!(id.short(), "2cf24db");
+
+        let sha1: OObjectId = SHA1_HEX.parse().unwrap();
+        assert_eq!(sha1.as_bytes().len(), 20);
+        assert_eq!(ObjectFormat::from_id(&sha1), ObjectFormat::Git);
+
+        // Maiuscole accettate, output sempre minuscolo
+        let upper: OObjectId = SHA1_HEX.to_uppercase().parse().unwrap();
+        assert_eq!(upper, sha1);
+        assert_eq!(OObjectId::from_bytes(sha1.as_bytes()).unwrap(), sha1);
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_ids() {
+        for bad in ["", "abc123", &SHA256_HEX[..63], &format!("{SHA256_HEX}0"), &"g".repeat(64), &"é".repeat(32)] {
+            let err = bad.parse::<OObjectId>().unwrap_err();
+            assert!(matches!(err, OgitError::InvalidInput(_)), "{bad:?} → {err:?}");
+        }
+        assert!(OObjectId::from_bytes(&[0; 16]).is_err());
+
+        // Un segno davanti a una coppia di cifre non è hex
+        for sign in ["+", "-"] {
+            for hex in [SHA256_HEX, SHA1_HEX] {
+                let bad = format!("{sign}{}", &hex[1..]);
+                let err = bad.parse::<OObjectId>().unwrap_err();
+                assert!(matches!(err, OgitError::InvalidInput(_)), "{bad:?} → {err:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolve_unique_prefix() {
+        let objects = MemoryStore::new(ObjectFormat::Ogit);
+        let id = objects.write(&OObject::new_blob(b"hello".to_vec())).unwrap();
+        let hex = id.to_hex();
+
+        assert_eq!(objects.resolve_prefix(&hex).unwrap(), id);
+        assert_eq!(objects.resolve_prefix(&hex[..6]).unwrap(), id);
+        assert_eq!(objects.resolve_prefix(&hex[..4].to_uppercase()).unwrap(), id);
+
+        // Troppo corto, non hex, nessuna corrispondenza
+        assert!(matches!(objects.resolve_prefix(&hex[..3]), Err(OgitError::InvalidInput(_))));
+        assert!(matches!(objects.resolve_prefix("zzzzzz"), Err(OgitError::InvalidInput(_))));
+        let signed = format!("+{}", &hex[1..]);
+        let err = objects.resolve_prefix(&signed).unwrap_err();
+        assert_eq!(err.to_string(), format!("Not a valid object name: {signed}"));
+        let other = if hex.starts_with('0') { "1111" } else { "0000" };
+        assert!(matches!(objects.resolve_prefix(other), Err(OgitError::InvalidRef(_))));
+    }
+
+    #[test]
+    fn test_resolve_ambiguous_prefix() {
+        let objects = MemoryStore::new(ObjectFormat::Ogit);
+
+        // Scrive blob finché due hash non condividono i primi 4 caratteri
+        let mut seen: HashMap<String, OObjectId> = HashMap::new();
+        let (first, second) = (0..)
+            .find_map(|n: u32| {
+                let id = objects.write(&OObject::new_blob(n.to_string().into_bytes())).unwrap();
+                seen.insert(id.to_hex()[..4].to_string(), id.clone()).map(|prev| (prev, id))
+            })
+            .unwrap();
+
+        let prefix = &first.to_hex()[..4];
+        match objects.resolve_prefix(prefix) {
+            Err(OgitError::AmbiguousId { candidates, .. }) => {
+                assert!(candidates.contains(&first) && candidates.contains(&second));
+                let message = OgitError::AmbiguousId { prefix: prefix.to_string(), candidates }.to_string();
+                assert!(message.contains("is ambiguous") && message.contains(&first.to_hex()));
+            }
+            other => panic!("expected an ambiguity error, got {other:?}"),
+        }
+
+        // Un prefisso più lungo torna univoco
+        assert_eq!(objects.resolve_prefix(&first.to_hex()[..16]).unwrap(), first);
+    }
+}
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use ogit::error::OgitError;
+    use ogit::ignore::IgnoreRules;
+    use ogit::object::{OObject, OObjectId, OObjectType, ObjectFormat, Signature};
+    use ogit::object_store::{LooseStore, MemoryStore, ObjectStore};
+    use ogit::pack::{repack, PackOptions};
+    use ogit::store::list_objects;
+    use ogit::tree::{build_tree, build_tree_from_dir, flatten_tree_in};
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_object_store_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".ogit/objects")).unwrap();
+        fs::create_dir_all(dir.join("work/src")).unwrap();
+        fs::write(dir.join("work/readme.txt"), "hello\n").unwrap();
+        fs::write(dir.join("work/src/main.rs"), "fn main() {}\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_memory_store_tree_and_commit() {
+        let dir = setup_test_dir("memory");
+        let objects = MemoryStore::new(ObjectFormat::Ogit);
+
+        let tree = build_tree(&objects, &dir.join("work"), &IgnoreRules::default()).unwrap();
+        // 2 blob, il tree src e la root
+        assert_eq!(objects.len(), 4);
+
+        let author = Signature::new("Author", "author@example.com", 1_700_000_000, 0);
+        let commit = objects.create_commit(&tree, &[], &author, "first").unwrap();
+        assert_eq!(objects.read_commit(&commit).unwrap().tree, tree);
+        assert!(matches!(
+            objects.read_tree(&commit),
+            Err(OgitError::WrongType { expected: OObjectType::Tree, .. })
+        ));
+
+        let files = flatten_tree_in(&objects, &tree).unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(), ["readme.txt", "src/main.rs"]);
+        assert_eq!(objects.read(&files["readme.txt"].0).unwrap().data, b"hello\n");
+
+        // Nessun file scritto su disco
+        assert!(list_objects(&dir.join(".ogit")).unwrap().is_empty());
+
+        // Stessi hash dello store su disco
+        assert_eq!(build_tree_from_dir(&dir.join(".ogit"), &dir.join("work")).unwrap(), tree);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_memory_store_basics() {
+        let objects = MemoryStore::new(ObjectFormat::Git);
+        assert!(objects.is_empty());
+
+        let id = objects.write(&OObject::new_blob(b"hello".to_vec())).unwrap();
+        assert_eq!(id.to_hex(), "b6fc4c620b67d95f953a5c1c1230aaab5db5a1b0");
+        assert_eq!(objects.write(&OObject::new_blob(b"hello".to_vec())).unwrap(), id);
+        assert_eq!(objects.ids().unwrap(), vec![id.clone()]);
+        assert!(objects.exists(&id).unwrap());
+
+        let missing = "0".repeat(40).parse::<OObjectId>().unwrap();
+        assert!(!objects.exists(&missing).unwrap());
+        assert!(matches!(objects.read(&missing), Err(OgitError::ObjectNotFound(id)) if id == missing));
+    }
+
+    #[test]
+    fn test_loose_store_sees_packed_objects() {
+        let dir = setup_test_dir("loose");
+        let store_path = dir.join(".ogit");
+        let objects = LooseStore::open(&store_path).unwrap();
+
+        let packed = objects.write(&OObject::new_blob(b"packed".to_vec())).unwrap();
+        repack(&store_path, &PackOptions::default()).unwrap();
+        let loose = objects.write(&OObject::new_blob(b"loose".to_vec())).unwrap();
+
+        let mut expected = vec![packed.clone(), loose.clone()];
+        expected.sort();
+        assert_eq!(objects.ids().unwrap(), expected);
+        assert!(objects.exists(&packed).unwrap());
+        assert!(objects.exists(&loose).unwrap());
+        assert_eq!(objects.read(&packed).unwrap().data, b"packed");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use ogit::config::Config;
+    use ogit::error::OgitError;
+    use ogit::object::{OObject, OObjectId, OObjectType};
+    use ogit::pack::{
+        has_packed, list_packed, pack_indexes, repack, write_pack, PackIndex, PackOptions, RepackStats, PACK_DEPTH_KEY,
+    };
+    use ogit::store::{list_objects, read_object, write_object, OBJECT_FORMAT_KEY};
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_pack_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("objects")).unwrap();
+        dir
+    }
+
+    fn write_blobs(store: &Path, count: usize) -> Vec<OObjectId> {
+        (0..count)
+            .map(|i| write_object(store, &OObject::new_blob(format!("blob number {i}\n").into_bytes())).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_write_pack_and_lookup() {
+        let store = setup_test_dir("write");
+        let ids = write_blobs(&store, 20);
+
+        let pack_path = write_pack(&store, &ids, &PackOptions::default()).unwrap();
+        let index = PackIndex::open(&pack_path.with_extension("idx")).unwrap();
+        assert_eq!(index.len(), 20);
+
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_eq!(index.ids().unwrap(), sorted);
+
+        for id in &ids {
+            let offset = index.find(id).unwrap().expect("object should be in the pack");
+            assert_eq!(index.read_at(offset).unwrap(), read_object(&store, id).unwrap());
+        }
+        assert_eq!(index.find(&"0".repeat(64).parse::<OObjectId>().unwrap()).unwrap(), None);
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_repack_moves_loose_objects() {
+        let store = setup_test_dir("repack");
+        let ids = write_blobs(&store, 5);
+
+        let stats = repack(&store, &PackOptions::default()).unwrap();
+        assert_eq!(stats.objects, 5);
+        assert_eq!(stats.loose_removed, 5);
+        assert!(list_objects(&store).unwrap().is_empty());
+
+        // `read_object` trova gli oggetti nel pack
+        for (i, id) in ids.iter().enumerate() {
+            let obj = read_object(&store, id).unwrap();
+            assert_eq!(obj.data, format!("blob number {i}\n").into_bytes());
+            assert!(has_packed(&store, id).unwrap());
+        }
+
+        // Un oggetto già nel pack non torna loose
+        write_object(&store, &OObject::new_blob(b"blob number 0\n".to_vec())).unwrap();
+        assert!(list_objects(&store).unwrap().is_empty());
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_repack_merges_packs() {
+        let store = setup_test_dir("merge");
+        write_blobs(&store, 3);
+        repack(&store, &PackOptions::default()).unwrap();
+        let extra = write_object(&store, &OObject::new_blob(b"later".to_vec())).unwrap();
+
+        let stats = repack(&store, &PackOptions::default()).unwrap();
+        assert_eq!(stats, RepackStats { objects: 4, loose_removed: 1, packs_removed: 1, deltas: 0 });
+        assert_eq!(pack_indexes(&store).unwrap().len(), 1);
+        assert!(list_packed(&store).unwrap().contains(&extra));
+
+        // Niente da riunire: il pack resta lo stesso
+        assert_eq!(repack(&store, &PackOptions::default()).unwrap().loose_removed, 0);
+        assert_eq!(pack_indexes(&store).unwrap().len(), 1);
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_missing_object_error() {
+        let store = setup_test_dir("missing");
+        write_blobs(&store, 1);
+        repack(&store, &PackOptions::default()).unwrap();
+
+        let err = read_object(&store, &"ab".repeat(32).parse::<OObjectId>().unwrap()).unwrap_err();
+        assert!(matches!(err, OgitError::ObjectNotFound(ref id) if id.to_hex() == "ab".repeat(32)));
+        assert_eq!(err.to_string(), format!("Object not found: {}", "ab".repeat(32)));
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_pack_with_sha1_ids() {
+        let store = setup_test_dir("sha1");
+        let mut config = Config::default();
+        config.set(OBJECT_FORMAT_KEY, "git").unwrap();
+        config.save(&store).unwrap();
+
+        let ids = write_blobs(&store, 3);
+        assert_eq!(ids[0].to_hex().len(), 40);
+        repack(&store, &PackOptions::default()).unwrap();
+
+        let index = &pack_indexes(&store).unwrap()[0];
+        assert_eq!(index.len(), 3);
+        assert_eq!(read_object(&store, &ids[2]).unwrap().data, b"blob number 2\n");
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    /// Versioni successive di un file di testo grande: ognuna cambia una riga.
+    fn write_versions(store: &Path, count: usize) -> Vec<(OObjectId, Vec<u8>)> {
+        let mut lines: Vec<String> = (0..2000).map(|i| format!("line {i} of a large text asset")).collect();
+        (0..count)
+            .map(|v| {
+                lines[v * 97 % 2000] = format!("line edited in version {v}");
+                let data = (lines.join("\n") + "\n").into_bytes();
+                (write_object(store, &OObject::new_blob(data.clone())).unwrap(), data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_repack_stores_versions_as_deltas() {
+        let store = setup_test_dir("delta");
+        let versions = write_versions(&store, 8);
+        let total: usize = versions.iter().map(|(_, data)| data.len()).sum();
+
+        let stats = repack(&store, &PackOptions::default()).unwrap();
+        assert_eq!(stats.objects, 8);
+        assert_eq!(stats.deltas, 7);
+
+        // Una sola versione intera: il pack è molto più piccolo dei dati
+        let index = &pack_indexes(&store).unwrap()[0];
+        let pack_size = fs::metadata(index.pack_path()).unwrap().len() as usize;
+        assert!(pack_size < total / 10, "pack of {pack_size} bytes for {total} bytes of data");
+
+        for (id, data) in &versions {
+            assert_eq!(&read_object(&store, id).unwrap().data, data);
+            // Tipo e dimensione anche dalle entry delta, senza ricostruirle
+            let offset = index.find(id).unwrap().unwrap();
+            assert_eq!(index.header_at(offset).unwrap(), (OObjectType::Blob, data.len() as u64));
+        }
+
+        // Repack senza delta: stessi oggetti, tutti interi
+        let plain = write_pack(&store, &list_packed(&store).unwrap(), &PackOptions { window: 0, depth: 50 }).unwrap();
+        let plain = PackIndex::open(&plain.with_extension("idx")).unwrap();
+        for (id, data) in &versions {
+            let offset = plain.find(id).unwrap().unwrap();
+            assert_eq!(plain.delta_depth(offset).unwrap(), 0);
+            assert_eq!(&plain.read_at(offset).unwrap().data, data);
+        }
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_delta_chains_respect_depth() {
+        let store = setup_test_dir("depth");
+        let mut config = Config::default();
+        config.set(PACK_DEPTH_KEY, "2").unwrap();
+        config.save(&store).unwrap();
+        let versions = write_versions(&store, 10);
+
+        // Finestra 1: ogni blob può usare solo il precedente come base
+        let stats = repack(&store, &PackOptions { window: 1, ..PackOptions::load(&store).unwrap() }).unwrap();
+        assert_eq!(stats.deltas, 6);
+
+        let index = &pack_indexes(&store).unwrap()[0];
+        let mut depths: Vec<usize> = versions
+            .iter()
+            .map(|(id, _)| index.delta_depth(index.find(id).unwrap().unwrap()).unwrap())
+            .collect();
+        depths.sort_unstable();
+        assert_eq!(depths, vec![0, 0, 0, 0, 1, 1, 1, 2, 2, 2]);
+
+        for (id, data) in &versions {
+            assert_eq!(&read_object(&store, id).unwrap().data, data);
+        }
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+}
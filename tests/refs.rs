@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use ogit::object::OObjectId;
+    use ogit::refs::{current_branch, read_ref, resolve_ref, update_ref, write_ref, RefValue, HEAD};
+
+    fn setup_test_dir(name: &str) -> std::path::PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_refs_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("refs/heads")).unwrap();
+        fs::write(dir.join("HEAD"), "ref: refs/heads/master\n").unwrap();
+        dir
+    }
+
+    fn hash(c: char) -> OObjectId {
+        c.to_string().repeat(64).parse().unwrap()
+    }
+
+    #[test]
+    fn test_resolve_unborn_branch_is_none() {
+        let store = setup_test_dir("unborn");
+
+        assert_eq!(resolve_ref(&store, HEAD).unwrap(), None);
+        assert_eq!(current_branch(&store).unwrap(), Some("master".to_string()));
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_update_head_writes_through_to_branch() {
+        let store = setup_test_dir("through");
+        let id = hash('a');
+
+        update_ref(&store, HEAD, &id).unwrap();
+
+        // HEAD resta simbolico, il branch contiene l'hash
+        assert_eq!(
+            read_ref(&store, HEAD).unwrap(),
+            Some(RefValue::Symbolic("refs/heads/master".to_string()))
+        );
+        assert_eq!(resolve_ref(&store, "master").unwrap(), Some(id.clone()));
+        assert_eq!(resolve_ref(&store, HEAD).unwrap(), Some(id));
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_detached_head() {
+        let store = setup_test_dir("detached");
+        let id = hash('b');
+
+        write_ref(&store, HEAD, &RefValue::Direct(id.clone())).unwrap();
+
+        assert_eq!(current_branch(&store).unwrap(), None);
+        assert_eq!(resolve_ref(&store, HEAD).unwrap(), Some(id.clone()));
+
+        // Un nuovo commit aggiorna HEAD stesso, non un branch
+        let next = hash('c');
+        update_ref(&store, HEAD, &next).unwrap();
+        assert_eq!(resolve_ref(&store, HEAD).unwrap(), Some(next));
+        assert!(!store.join("refs/heads/master").exists());
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_ref_content_is_error() {
+        let store = setup_test_dir("invalid");
+        fs::write(store.join("refs/heads/master"), "not-a-hash\n").unwrap();
+
+        assert!(resolve_ref(&store, HEAD).is_err());
+
+        fs::remove_dir_all(&store).unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use ogit::error::OgitError;
+    use ogit::initialize_repository::init_repo_at;
+    use ogit::object::ObjectFormat;
+    use ogit::repository::Repository;
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_repo_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        init_repo_at(&dir.join(".ogit"), ObjectFormat::Ogit).unwrap();
+        fs::canonicalize(dir).unwrap()
+    }
+
+    #[test]
+    fn test_discover_from_subdirectory() {
+        let dir = setup_test_dir("discover");
+        let nested = dir.join("src/deep");
+        fs::create_dir_all(&nested).unwrap();
+
+        for start in [dir.clone(), nested.clone(), nested.join("..")] {
+            let repo = Repository::discover(&start).unwrap();
+            assert_eq!(repo.work_tree(), dir);
+            assert_eq!(repo.store_path(), dir.join(".ogit"));
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_discover_ignores_incomplete_store() {
+        let dir = setup_test_dir("incomplete");
+        // Una `.ogit` senza HEAD/objects più vicina non nasconde il repository vero
+        let inner = dir.join("vendor");
+        fs::create_dir_all(inner.join(".ogit")).unwrap();
+
+        assert_eq!(Repository::discover(&inner).unwrap().work_tree(), dir);
+        assert!(matches!(
+            Repository::open(&inner.join(".ogit"), &inner),
+            Err(OgitError::NotARepository(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_open_with_separate_work_tree() {
+        let dir = setup_test_dir("separate");
+        let work_tree = dir.join("checkout");
+        fs::create_dir_all(&work_tree).unwrap();
+
+        let repo = Repository::open(&dir.join(".ogit"), &work_tree).unwrap();
+        assert_eq!(repo.work_tree(), work_tree);
+        assert_eq!(repo.worktree_path(&work_tree, "a.txt").unwrap(), "a.txt");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_worktree_path_is_relative_to_root() {
+        let dir = setup_test_dir("paths");
+        let src = dir.join("src");
+        fs::create_dir_all(&src).unwrap();
+        let repo = Repository::discover(&src).unwrap();
+
+        assert_eq!(repo.worktree_path(&src, "main.rs").unwrap(), "src/main.rs");
+        assert_eq!(repo.worktree_path(&src, "./lib/../gone.rs").unwrap(), "src/gone.rs");
+        assert_eq!(repo.worktree_path(&src, "..").unwrap(), "");
+        assert_eq!(repo.worktree_path(&dir, ".").unwrap(), "");
+        assert_eq!(repo.worktree_path(&src, dir.join("README").to_str().unwrap()).unwrap(), "README");
+
+        for outside in ["../..", "/etc/passwd"] {
+            assert!(matches!(repo.worktree_path(&src, outside), Err(OgitError::InvalidInput(_))));
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,18 +1,22 @@
+mod common;
+
 #[cfg(test)]
 mod tests {
-    use ogit::object::{OObjectId, OObjectType, TreeEntry};
+    use ogit::object::{FileMode, OObjectType, TreeEntry};
+
+    use super::common::fake_id;
 
-    
 
     #[test]
     fn test_tree_entry_to_line() {
         let entry = TreeEntry {
             kind: OObjectType::Blob,
-            hash: OObjectId("abc123".to_string()),
+            mode: FileMode::Regular,
+            hash: fake_id("abc123"),
             name: "readme.txt".to_string(),
         };
         
-        assert_eq!(entry.to_line(), "blob abc123 readme.txt");
+        assert_eq!(entry.to_line(), format!("blob {} readme.txt", fake_id("abc123")));
     }
 
     #[test]
@@ -20,17 +24,20 @@ mod tests {
         let entries = vec![
             TreeEntry {
                 kind: OObjectType::Blob,
-                hash: OObjectId("aaa111".to_string()),
+                mode: FileMode::Regular,
+                hash: fake_id("aaa111"),
                 name: "zebra.txt".to_string(),
             },
             TreeEntry {
                 kind: OObjectType::Tree,
-                hash: OObjectId("bbb222".to_string()),
+                mode: FileMode::Directory,
+                hash: fake_id("bbb222"),
                 name: "alpha".to_string(),
             },
             TreeEntry {
                 kind: OObjectType::Blob,
-                hash: OObjectId("ccc333".to_string()),
+                mode: FileMode::Regular,
+                hash: fake_id("ccc333"),
                 name: "middle.rs".to_string(),
             },
         ];
@@ -46,7 +53,7 @@ mod tests {
         
         // Verifica contenuto completo
         assert_eq!(deserialized[0].kind, OObjectType::Tree);
-        assert_eq!(deserialized[0].hash.as_str(), "bbb222");
+        assert_eq!(deserialized[0].hash, fake_id("bbb222"));
     }
 
     #[test]
@@ -54,7 +61,8 @@ mod tests {
         let entries = vec![
             TreeEntry {
                 kind: OObjectType::Blob,
-                hash: OObjectId("abc123".to_string()),
+                mode: FileMode::Regular,
+                hash: fake_id("abc123"),
                 name: "my file with spaces.txt".to_string(),
             },
         ];
@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use ogit::index::{add_paths, remove_paths, write_tree_from_index, Index};
+    use ogit::object::{OObjectId, Signature};
+    use ogit::refs::{update_ref, HEAD};
+    use ogit::status::{diff_maps, status, ChangeKind};
+    use ogit::store::create_commit;
+
+    fn author() -> Signature {
+        Signature::new("Author", "author@example.com", 0, 0)
+    }
+
+    fn setup_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_status_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".ogit/objects")).unwrap();
+        fs::create_dir_all(dir.join(".ogit/refs/heads")).unwrap();
+        fs::write(dir.join(".ogit/HEAD"), "ref: refs/heads/master\n").unwrap();
+        dir
+    }
+
+    fn commit_index(store: &Path) {
+        let tree = write_tree_from_index(store, &Index::load(store).unwrap()).unwrap();
+        let id = create_commit(store, &tree, &[], &author(), "msg").unwrap();
+        update_ref(store, HEAD, &id).unwrap();
+    }
+
+    #[test]
+    fn test_diff_maps() {
+        let id = |s: &str| s.repeat(64).parse::<OObjectId>().unwrap();
+        let old = BTreeMap::from([("a".to_string(), id("1")), ("b".to_string(), id("2"))]);
+        let new = BTreeMap::from([("b".to_string(), id("3")), ("c".to_string(), id("4"))]);
+
+        let changes = diff_maps(&old, &new);
+        let kinds: Vec<(ChangeKind, &str)> = changes.iter().map(|c| (c.kind, c.path.as_str())).collect();
+
+        assert_eq!(
+            kinds,
+            vec![(ChangeKind::Deleted, "a"), (ChangeKind::Modified, "b"), (ChangeKind::Added, "c")]
+        );
+    }
+
+    #[test]
+    fn test_clean_after_commit() {
+        let dir = setup_test_dir("clean");
+        let store = dir.join(".ogit");
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+        commit_index(&store);
+
+        let st = status(&store, &dir).unwrap();
+        assert!(st.is_clean());
+        assert_eq!(st.branch, Some("master".to_string()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_staged_unstaged_untracked() {
+        let dir = setup_test_dir("mixed");
+        let store = dir.join(".ogit");
+        fs::write(dir.join("a.txt"), "A").unwrap();
+        fs::write(dir.join("b.txt"), "B").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+        commit_index(&store);
+
+        fs::write(dir.join("new.txt"), "N").unwrap();
+        add_paths(&store, &dir, &["new.txt".to_string()], false).unwrap();
+        fs::write(dir.join("a.txt"), "A modified").unwrap();
+        fs::remove_file(dir.join("b.txt")).unwrap();
+        fs::write(dir.join("loose.txt"), "L").unwrap();
+
+        let st = status(&store, &dir).unwrap();
+
+        assert_eq!(st.staged.len(), 1);
+        assert_eq!(st.staged[0].kind, ChangeKind::Added);
+        assert_eq!(st.staged[0].path, "new.txt");
+
+        let unstaged: Vec<(ChangeKind, &str)> =
+            st.unstaged.iter().map(|c| (c.kind, c.path.as_str())).collect();
+        assert_eq!(
+            unstaged,
+            vec![(ChangeKind::Modified, "a.txt"), (ChangeKind::Deleted, "b.txt")]
+        );
+
+        assert_eq!(st.untracked, vec!["loose.txt".to_string()]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_untracked_are_sorted() {
+        let dir = setup_test_dir("untracked_sorted");
+        let store = dir.join(".ogit");
+        let names = ["z.txt", "m/b.txt", "a.txt", "m/a.txt", "k.txt", "b/z.txt"];
+        fs::create_dir_all(dir.join("m")).unwrap();
+        fs::create_dir_all(dir.join("b")).unwrap();
+        for name in names {
+            fs::write(dir.join(name), name).unwrap();
+        }
+
+        let st = status(&store, &dir).unwrap();
+
+        let mut expected: Vec<String> = names.iter().map(|n| (*n).to_string()).collect();
+        expected.sort();
+        assert_eq!(st.untracked, expected);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_status_without_index_compares_head_to_worktree() {
+        let dir = setup_test_dir("no_index");
+        let store = dir.join(".ogit");
+        fs::write(dir.join("a.txt"), "A").unwrap();
+
+        let st = status(&store, &dir).unwrap();
+        assert!(st.staged.is_empty());
+        assert!(st.unstaged.is_empty());
+        assert_eq!(st.untracked, vec!["a.txt".to_string()]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_staged_move_is_reported_as_rename() {
+        let dir = setup_test_dir("rename");
+        let store = dir.join(".ogit");
+        fs::write(dir.join("old.txt"), "one\ntwo\nthree\nfour\n").unwrap();
+        add_paths(&store, &dir, &[".".to_string()], false).unwrap();
+        commit_index(&store);
+
+        fs::rename(dir.join("old.txt"), dir.join("new.txt")).unwrap();
+        fs::write(dir.join("new.txt"), "one\ntwo\nthree\nfour!\n").unwrap();
+        remove_paths(&store, &dir, &["old.txt".to_string()], true).unwrap();
+        add_paths(&store, &dir, &["new.txt".to_string()], false).unwrap();
+
+        let st = status(&store, &dir).unwrap();
+        assert_eq!(st.staged.len(), 1);
+        assert_eq!(st.staged[0].kind, ChangeKind::Renamed);
+        assert_eq!(st.staged[0].display_path(), "old.txt -> new.txt");
+        assert!(st.unstaged.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
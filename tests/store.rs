@@ -1,8 +1,12 @@
+mod common;
+
 #[cfg(test)]
 mod tests {
     use std::env;
+    use std::error::Error;
     use std::fs;
 
+    use ogit::error::OgitError;
     use ogit::object::OObject;
     use ogit::store::read_object;
     use ogit::store::write_object;
@@ -32,7 +36,8 @@ mod tests {
         let id = write_object(&temp_dir, &obj).unwrap();
 
         // 3. Verifica che il file esista
-        let (subdir, filename) = id.as_str().split_at(2);
+        let hex = id.to_hex();
+        let (subdir, filename) = hex.split_at(2);
         let file_path = temp_dir.join("objects").join(subdir).join(filename);
         assert!(file_path.exists(), "Object file should exist");
 
@@ -59,6 +64,40 @@ mod tests {
 
         fs::remove_dir_all(&temp_dir).unwrap();
     }
+
+    #[test]
+    fn test_read_errors_are_typed() {
+        let temp_dir = env::temp_dir().join(format!("ogit_test_errors_{}", std::process::id()));
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let id = write_object(&temp_dir, &OObject::new_blob(b"will be damaged".to_vec())).unwrap();
+        let hex = id.to_hex();
+        let (subdir, filename) = hex.split_at(2);
+        let file_path = temp_dir.join("objects").join(subdir).join(filename);
+
+        // Oggetto troncato: corrotto, non mancante
+        fs::write(&file_path, b"blob 15\0will be").unwrap();
+        assert!(matches!(read_object(&temp_dir, &id), Err(OgitError::Corrupt { .. })));
+
+        // Stream zlib rotto: corrotto, con l'errore di decompressione come causa
+        fs::write(&file_path, b"\x78\x9c not really zlib").unwrap();
+        let err = read_object(&temp_dir, &id).unwrap_err();
+        assert!(matches!(err, OgitError::Corrupt { source: Some(_), .. }));
+        assert!(err.source().is_some());
+        assert!(err.to_string().starts_with("Failed to decompress: "), "{err}");
+
+        // Al posto del file c'è una directory: errore di I/O con la causa originale
+        fs::remove_file(&file_path).unwrap();
+        fs::create_dir(&file_path).unwrap();
+        let err = read_object(&temp_dir, &id).unwrap_err();
+        assert!(matches!(err, OgitError::Io { .. }));
+        assert!(err.source().is_some());
+
+        fs::remove_dir(&file_path).unwrap();
+        assert!(matches!(read_object(&temp_dir, &id), Err(OgitError::ObjectNotFound(missing)) if missing == id));
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
 }
 
 mod create_commit_tests {
@@ -66,10 +105,16 @@ mod create_commit_tests {
     use std::fs;
 
     use ogit::object::Commit;
-    use ogit::object::OObjectId;
+    use ogit::object::Signature;
     use ogit::store::create_commit;
     use ogit::store::read_object;
 
+    use super::common::fake_id;
+
+    fn author() -> Signature {
+        Signature::new("Author", "author@example.com", 0, 0)
+    }
+
     fn setup_test_dir(name: &str) -> std::path::PathBuf {
         let dir = env::temp_dir().join(format!("ogit_commit_test_{}_{}", name, std::process::id()));
         let _ = fs::remove_dir_all(&dir);
@@ -80,16 +125,17 @@ mod create_commit_tests {
     #[test]
     fn test_create_commit_without_parent() {
         let store = setup_test_dir("no_parent");
-        let tree_id = OObjectId("abc123def456".to_string());
+        let tree_id = fake_id("abc123def456");
         
-        let commit_id = create_commit(&store, &tree_id, None, "Author", "First commit").unwrap();
+        let commit_id = create_commit(&store, &tree_id, &[], &author(), "First commit").unwrap();
         
         let obj = read_object(&store, &commit_id).unwrap();
         let commit = Commit::deserialize(&obj.data).unwrap();
         
-        assert_eq!(commit.tree.as_str(), "abc123def456");
-        assert!(commit.parent.is_none());
-        assert_eq!(commit.author, "Author");
+        assert_eq!(commit.tree, fake_id("abc123def456"));
+        assert!(commit.parents.is_empty());
+        assert_eq!(commit.author.name, "Author");
+        assert_eq!(commit.committer, commit.author);
         assert_eq!(commit.message, "First commit");
         
         fs::remove_dir_all(&store).unwrap();
@@ -98,15 +144,15 @@ mod create_commit_tests {
     #[test]
     fn test_create_commit_with_parent() {
         let store = setup_test_dir("with_parent");
-        let tree_id = OObjectId("abc123".to_string());
-        let parent_id = OObjectId("parent789".to_string());
+        let tree_id = fake_id("abc123");
+        let parent_id = fake_id("789");
         
-        let commit_id = create_commit(&store, &tree_id, Some(&parent_id), "Author", "Second commit").unwrap();
+        let commit_id = create_commit(&store, &tree_id, std::slice::from_ref(&parent_id), &author(), "Second commit").unwrap();
         
         let obj = read_object(&store, &commit_id).unwrap();
         let commit = Commit::deserialize(&obj.data).unwrap();
         
-        assert_eq!(commit.parent.unwrap().as_str(), "parent789");
+        assert_eq!(commit.parents, vec![fake_id("789")]);
         
         fs::remove_dir_all(&store).unwrap();
     }
@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io::{self, Read};
+    use std::path::{Path, PathBuf};
+
+    use ogit::config::Config;
+    use ogit::error::OgitError;
+    use ogit::initialize_repository::init_repo_at;
+    use ogit::object::{OObject, OObjectType, ObjectFormat, ObjectHasher};
+    use ogit::pack::{repack, PackOptions};
+    use ogit::store::{
+        list_objects, read_object, read_object_stream, write_blob_stream, write_object, COMPRESSION_KEY,
+    };
+
+    fn setup_test_dir(name: &str, format: ObjectFormat) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ogit_stream_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        init_repo_at(&dir.join(".ogit"), format).unwrap();
+        dir
+    }
+
+    fn set_compression(store: &Path, enabled: bool) {
+        let mut config = Config::load(store).unwrap();
+        config.set(COMPRESSION_KEY, if enabled { "true" } else { "false" }).unwrap();
+        config.save(store).unwrap();
+    }
+
+    /// Sorgente di `len` byte pseudo-casuali generati al volo, mai tutti in memoria.
+    struct Pattern {
+        len: u64,
+        pos: u64,
+    }
+
+    impl Read for Pattern {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min((self.len - self.pos) as usize);
+            for byte in &mut buf[..n] {
+                *byte = (self.pos.wrapping_mul(2_654_435_761) >> 13) as u8;
+                self.pos += 1;
+            }
+            Ok(n)
+        }
+    }
+
+    /// Nomi dei file direttamente in `objects/` (dove finiscono i temporanei).
+    fn loose_files(store: &Path) -> Vec<String> {
+        fs::read_dir(store.join("objects"))
+            .unwrap()
+            .map(|e| e.unwrap())
+            .filter(|e| e.path().is_file())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_object_hasher_matches_serialized_hash() {
+        let data = b"hello streaming world".repeat(100);
+        for format in [ObjectFormat::Ogit, ObjectFormat::Git] {
+            let expected = format.hash_id(&OObject::new_blob(data.clone()).serialize());
+
+            let mut hasher = ObjectHasher::new(format, OObjectType::Blob, data.len() as u64);
+            for chunk in data.chunks(7) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finish().unwrap(), expected);
+            assert_eq!(format.hash_reader(OObjectType::Blob, &data[..], data.len() as u64).unwrap(), expected);
+
+            // Dimensione dichiarata diversa dai dati
+            let short = format.hash_reader(OObjectType::Blob, &data[..10], data.len() as u64);
+            assert!(matches!(short, Err(OgitError::Corrupt { .. })));
+        }
+    }
+
+    #[test]
+    fn test_write_blob_stream_matches_write_object() {
+        for (name, format, compressed) in [
+            ("plain", ObjectFormat::Ogit, false),
+            ("zlib", ObjectFormat::Ogit, true),
+            ("git", ObjectFormat::Git, true),
+        ] {
+            let dir = setup_test_dir(name, format);
+            let store = dir.join(".ogit");
+            set_compression(&store, compressed);
+
+            let data = b"some file content\n".repeat(1000);
+            let streamed = write_blob_stream(&store, &data[..], data.len() as u64).unwrap();
+            assert_eq!(streamed, write_object(&store, &OObject::new_blob(data.clone())).unwrap());
+            assert_eq!(read_object(&store, &streamed).unwrap().data, data);
+            assert_eq!(list_objects(&store).unwrap(), vec![streamed.clone()]);
+
+            let mut reader = read_object_stream(&store, &streamed).unwrap();
+            assert_eq!((reader.kind, reader.size), (OObjectType::Blob, data.len() as u64));
+            let mut read_back = Vec::new();
+            reader.read_to_end(&mut read_back).unwrap();
+            assert_eq!(read_back, data);
+
+            // Già presente: nessun file nuovo, nessun temporaneo rimasto
+            write_blob_stream(&store, &data[..], data.len() as u64).unwrap();
+            assert_eq!(loose_files(&store), Vec::<String>::new());
+
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_large_blob_round_trip() {
+        let dir = setup_test_dir("large", ObjectFormat::Ogit);
+        let store = dir.join(".ogit");
+        let len = 8 * 1024 * 1024 + 3;
+
+        let id = write_blob_stream(&store, Pattern { len, pos: 0 }, len).unwrap();
+        let expected = ObjectFormat::Ogit.hash_reader(OObjectType::Blob, Pattern { len, pos: 0 }, len).unwrap();
+        assert_eq!(id, expected);
+
+        // Riletto a blocchi, l'hash torna
+        let reader = read_object_stream(&store, &id).unwrap();
+        assert_eq!(reader.size, len);
+        assert_eq!(ObjectFormat::Ogit.hash_reader(OObjectType::Blob, reader, len).unwrap(), id);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_stream_errors_and_packed_objects() {
+        let dir = setup_test_dir("errors", ObjectFormat::Ogit);
+        let store = dir.join(".ogit");
+        set_compression(&store, false);
+
+        // Meno byte del dichiarato: niente oggetto, niente temporaneo
+        let result = write_blob_stream(&store, &b"short"[..], 100);
+        assert!(matches!(result, Err(OgitError::Corrupt { .. })));
+        assert!(list_objects(&store).unwrap().is_empty());
+        assert_eq!(loose_files(&store), Vec::<String>::new());
+
+        // File loose troncato: l'header promette più dati di quanti ce ne siano
+        let id = write_blob_stream(&store, &b"hello world"[..], 11).unwrap();
+        let hex = id.to_hex();
+        let path = store.join("objects").join(&hex[..2]).join(&hex[2..]);
+        fs::write(&path, b"blob 11\0hello").unwrap();
+        let mut truncated = Vec::new();
+        let error = read_object_stream(&store, &id).unwrap().read_to_end(&mut truncated).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        // Dopo il repack l'oggetto si legge dal pack
+        fs::write(&path, b"blob 11\0hello world").unwrap();
+        repack(&store, &PackOptions::default()).unwrap();
+        assert!(list_objects(&store).unwrap().is_empty());
+        let mut packed = Vec::new();
+        read_object_stream(&store, &id).unwrap().read_to_end(&mut packed).unwrap();
+        assert_eq!(packed, b"hello world");
+
+        let missing = ObjectFormat::Ogit.hash_id(b"nothing");
+        assert!(matches!(read_object_stream(&store, &missing), Err(OgitError::ObjectNotFound(_))));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}